@@ -0,0 +1,100 @@
+//the most inputs swept, each one doubles the number of runs
+pub const MAX_INPUTS: usize = 12;
+
+//what each output saw for every combination of inputs
+#[derive(Debug, Clone, Default)]
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    //input values and the state of the first ball on each output, None if none got there
+    pub rows: Vec<(Vec<bool>, Vec<Option<bool>>)>,
+}
+
+impl TruthTable {
+    pub fn to_csv(&self) -> String {
+        let header = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",");
+        let rows = self.rows.iter().map(|(inputs, outputs)| {
+            inputs
+                .iter()
+                .map(|on| bit(*on).to_owned())
+                .chain(
+                    outputs
+                        .iter()
+                        .map(|on| on.map(bit).unwrap_or_default().to_owned()),
+                )
+                .collect::<Vec<_>>()
+                .join(",")
+        });
+        std::iter::once(header)
+            .chain(rows)
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    //one sum of products per output, outputs no ball reached count as off
+    pub fn to_netlist(&self) -> String {
+        let mut out = String::from("# sum of products for each output\n");
+        self.inputs
+            .iter()
+            .for_each(|name| out += &format!("input {name}\n"));
+        self.outputs.iter().enumerate().for_each(|(i, name)| {
+            let terms: Vec<String> = self
+                .rows
+                .iter()
+                .filter(|(_, outputs)| outputs[i] == Some(true))
+                .map(|(inputs, _)| {
+                    let literals: Vec<String> = self
+                        .inputs
+                        .iter()
+                        .zip(inputs)
+                        .map(|(name, on)| {
+                            if *on {
+                                name.clone()
+                            } else {
+                                format!("!{name}")
+                            }
+                        })
+                        .collect();
+                    match literals.len() {
+                        0 => "1".to_owned(),
+                        1 => literals[0].clone(),
+                        _ => format!("({})", literals.join(" & ")),
+                    }
+                })
+                .collect();
+            let expression = if terms.is_empty() {
+                "0".to_owned()
+            } else {
+                terms.join(" | ")
+            };
+            out += &format!("output {name} = {expression}\n");
+        });
+        out
+    }
+}
+
+fn bit(on: bool) -> &'static str {
+    if on {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+//the cell's note if it has one, so the columns can be named in the inspector
+pub fn probe_name(pos: [i32; 2], note: &str) -> String {
+    let name = if note.trim().is_empty() {
+        format!("cell_{}_{}", pos[0], pos[1]).replace('-', "m")
+    } else {
+        note.trim().to_owned()
+    };
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
@@ -1,29 +1,53 @@
 use std::{
     array::from_fn,
     collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
     sync::Arc,
-    time::Instant,
+    thread,
+    time::{Duration, Instant},
 };
 
-use renderer::{
+use engine::{
     ball::{BallPosition, Direction},
-    chunk::{Chunk, ChunkPosition},
-    state::{CameraUniform, RenderState, SurfaceError},
+    chunk::{Chunk, ChunkPosition, CHUNK_SIZE},
+    tiles::{Layer, Tile},
+};
+use renderer::{
+    state::{CameraUniform, RenderState, SurfaceError, UploadStats},
+    text::TextLabel,
 };
 use shared::{
+    anyhow,
     egui::{self, Context},
     log,
     winit::{
         self,
         application::ApplicationHandler,
-        event::{KeyEvent, WindowEvent},
+        event::{KeyEvent, Touch, TouchPhase, WindowEvent},
         event_loop::ActiveEventLoop,
         keyboard::{KeyCode, PhysicalKey},
         window::Window,
     },
 };
 
-use crate::{tiles::Tile, LINE_HEIGHT};
+use crate::{
+    keybinds::Action,
+    log_viewer::LogViewer,
+    perf::PerfHud,
+    settings::{config_path, Settings},
+    LINE_HEIGHT, SCROLL_SPEED,
+};
+
+//the longest we block the event loop while pacing frames
+const MAX_FRAME_SLEEP: Duration = Duration::from_millis(2);
+//how close in time and screen pixels two left clicks must be to count as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(300);
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+//egui memory, where the windows were and which were collapsed
+const LAYOUT_FILE: &str = "layout.ron";
+//the window title before a world is named in it
+pub const TITLE: &str = "ball_sim";
 
 pub trait State {
     fn update(&mut self, app: &mut App, delta_time: f32);
@@ -34,23 +58,56 @@ pub struct App {
     render_state: Option<RenderState>,
 
     keys_down: HashSet<KeyCode>,
+    //keys that went down since the last update, for one shot actions
+    keys_pressed: HashSet<KeyCode>,
+    //the next key press is bound to this action instead of being handled
+    rebinding: Option<Action>,
     mouse_position: [f32; 2],
-    mouse_buttons: (bool, bool),
+    //left, right, middle
+    mouse_buttons: (bool, bool, bool),
+    last_click: Option<(Instant, [f32; 2])>,
+    //set until the next update once a double click happens
+    double_clicked: bool,
+    //fingers currently on the screen by id
+    touches: HashMap<u64, [f32; 2]>,
+    pinch_distance: Option<f32>,
+    //once a second finger lands the gesture never paints, even after it lifts
+    multi_touch: bool,
 
     camera: CameraUniform,
 
     scroll_level: f32,
 
+    settings: Settings,
+    uploads: UploadStats,
+    supported_msaa_samples: Vec<u32>,
+
     last_update_time: Instant,
     last_render_time: Instant,
+    perf: PerfHud,
+    log_viewer: LogViewer,
+
+    //redraws are skipped while none of these are set
+    input_since_update: bool,
+    redraw_requested: bool,
+    //something animated was in view the last time the state drew, unlike a request it lasts
+    animating: bool,
 
     exiting: bool,
+    //the window was asked to close and the state hasn't answered yet
+    close_requested: bool,
+
+    //kept so the window only gets a new title when it changes
+    title: String,
+
+    //files dropped onto the window since the state last took them
+    dropped_files: Vec<PathBuf>,
 
     state: Option<Box<dyn State>>,
 }
 
 impl App {
-    pub fn new(update_loop: Option<Box<(dyn State + 'static)>>) -> Self {
+    pub fn new(update_loop: Option<Box<dyn State + 'static>>) -> Self {
         Self {
             render_state: None,
             camera: CameraUniform {
@@ -60,17 +117,36 @@ impl App {
                 ..Default::default()
             },
             keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            rebinding: None,
             last_update_time: Instant::now(),
             last_render_time: Instant::now(),
+            perf: PerfHud::default(),
+            log_viewer: LogViewer::default(),
+            input_since_update: true,
+            redraw_requested: true,
+            animating: false,
             mouse_position: [0.0; 2],
-            mouse_buttons: (false, false),
+            mouse_buttons: (false, false, false),
+            last_click: None,
+            double_clicked: false,
+            touches: HashMap::new(),
+            pinch_distance: None,
+            multi_touch: false,
             scroll_level: 0.0,
+            settings: Settings::load(),
+            uploads: UploadStats::default(),
+            supported_msaa_samples: vec![1],
             exiting: false,
+            close_requested: false,
+            title: TITLE.to_owned(),
+            dropped_files: vec![],
             state: update_loop,
         }
     }
 
     fn ui(&mut self, ctx: &Context) {
+        ctx.set_visuals(self.settings.visuals());
         egui::Window::new("").show(ctx, |ui| {
             ui.label(format!("{:?}", self.camera));
             ui.label(format!("{:?}", self.get_mouse_position_world()));
@@ -83,7 +159,32 @@ impl App {
                 "fps: {:.2?}",
                 1.0 / self.last_render_time.elapsed().as_secs_f32()
             ));
+            if self.settings.debug_overlay {
+                ui.label(format!("{:#?}", self.uploads));
+            }
         });
+        self.perf.ui(ctx);
+        let errors = self.log_viewer.errors();
+        let log_title = match errors {
+            0 => "log".to_owned(),
+            _ => format!("log ({errors} errors)"),
+        };
+        egui::Window::new(log_title)
+            .id(egui::Id::new("log"))
+            .default_open(false)
+            .show(ctx, |ui| self.log_viewer.ui(ui));
+        egui::Window::new("settings")
+            .default_open(false)
+            .show(ctx, |ui| {
+                let old = self.settings.clone();
+                self.settings
+                    .ui(ui, &self.supported_msaa_samples, &mut self.rebinding);
+                if self.settings != old {
+                    if let Err(e) = self.settings.save() {
+                        log::error!("couldn't save settings: {e}");
+                    }
+                }
+            });
         let mut state = self.state.take();
         if let Some(ref mut state) = &mut state {
             state.ui(self, ctx);
@@ -100,9 +201,11 @@ impl App {
         }
     }
 
+    //a drag that began in the ui, like a blueprint pulled out of its list, stays there
     pub fn in_ui(&self) -> bool {
         if let Some(state) = &self.render_state {
-            state.egui_platform.context().is_pointer_over_area()
+            let context = state.egui_platform.context();
+            context.is_pointer_over_area() || context.dragged_id().is_some()
         } else {
             false
         }
@@ -112,7 +215,69 @@ impl App {
         if self.last_update_time.elapsed().as_secs_f32() > 1.0 / 60.0 {
             let delta = self.last_update_time.elapsed().as_millis();
             self.last_update_time = Instant::now();
+            self.input_since_update = false;
             self.update(delta as f32);
+            self.double_clicked = false;
+            self.keys_pressed.clear();
+        }
+    }
+
+    //states call this whenever they changed something that should be visible
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    //keeps the window redrawing while animations are on and something animated is in view
+    pub fn set_animating(&mut self, animating: bool) {
+        self.animating = animating && self.settings.animations;
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.input_since_update
+            || self.redraw_requested
+            || self.animating
+            || self
+                .render_state
+                .as_ref()
+                .is_some_and(|state| state.wants_repaint())
+    }
+
+    //one finger paints like the left button, two pan like the middle button and pinch to zoom
+    fn handle_touch(&mut self, touch: Touch) {
+        let pos = [touch.location.x as f32, touch.location.y as f32];
+        match touch.phase {
+            TouchPhase::Started | TouchPhase::Moved => {
+                self.touches.insert(touch.id, pos);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+            }
+        }
+        let fingers: Vec<[f32; 2]> = self.touches.values().copied().collect();
+        match fingers.as_slice() {
+            [finger] => {
+                self.mouse_position = *finger;
+                self.mouse_buttons.0 = !self.multi_touch;
+                self.mouse_buttons.2 = false;
+                self.pinch_distance = None;
+            }
+            [a, b] => {
+                self.multi_touch = true;
+                self.mouse_position = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+                self.mouse_buttons.0 = false;
+                self.mouse_buttons.2 = true;
+                let distance = (a[0] - b[0]).hypot(a[1] - b[1]).max(1.0);
+                if let Some(last) = self.pinch_distance {
+                    self.scroll_level += (distance / last).log2() * SCROLL_SPEED;
+                }
+                self.pinch_distance = Some(distance);
+            }
+            fingers => {
+                self.multi_touch = !fingers.is_empty();
+                self.mouse_buttons.0 = false;
+                self.mouse_buttons.2 = false;
+                self.pinch_distance = None;
+            }
         }
     }
 
@@ -121,11 +286,59 @@ impl App {
     }
 
     pub fn set_update_loop(&mut self, state: Box<dyn State>) {
+        self.animating = false;
         self.state = Some(state);
     }
 
-    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
-        self.keys_down.contains(&key)
+    //typing into a text field shouldn't trigger shortcuts
+    fn keyboard_captured(&self) -> bool {
+        self.render_state
+            .as_ref()
+            .is_some_and(|state| state.egui_platform.context().wants_keyboard_input())
+    }
+
+    //held down right now
+    pub fn is_action_active(&self, action: Action) -> bool {
+        !self.keyboard_captured() && self.keys_down.contains(&self.settings.keybinds.key(action))
+    }
+
+    //pressed since the last update
+    pub fn was_action_pressed(&self, action: Action) -> bool {
+        !self.keyboard_captured()
+            && self
+                .keys_pressed
+                .contains(&self.settings.keybinds.key(action))
+    }
+
+    //the state decides whether closing the window exits right away
+    pub fn take_close_request(&mut self) -> bool {
+        std::mem::take(&mut self.close_requested)
+    }
+
+    //hands a close request on to the state that takes over
+    pub fn request_close(&mut self) {
+        self.close_requested = true;
+        self.request_redraw();
+    }
+
+    pub fn exit(&mut self) {
+        self.exiting = true;
+        //the event loop only notices on the next event
+        self.request_redraw();
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        if title == self.title {
+            return;
+        }
+        if let Some(state) = &self.render_state {
+            state.window().set_title(&title);
+        }
+        self.title = title;
+    }
+
+    pub fn take_dropped_files(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.dropped_files)
     }
 
     pub fn camera(&self) -> &CameraUniform {
@@ -136,10 +349,29 @@ impl App {
         &mut self.camera
     }
 
-    pub fn mouse_buttons(&self) -> (bool, bool) {
+    pub fn mouse_buttons(&self) -> (bool, bool, bool) {
         self.mouse_buttons
     }
 
+    pub fn double_clicked(&self) -> bool {
+        self.double_clicked
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    //for states changing a setting themselves, saved like changes made in the settings window
+    pub fn update_settings(&mut self, change: impl FnOnce(&mut Settings)) {
+        let old = self.settings.clone();
+        change(&mut self.settings);
+        if self.settings != old {
+            if let Err(e) = self.settings.save() {
+                log::error!("couldn't save settings: {e}");
+            }
+        }
+    }
+
     pub fn scroll_level(&self) -> f32 {
         self.scroll_level
     }
@@ -148,28 +380,103 @@ impl App {
         &mut self.scroll_level
     }
 
-    pub fn set_chunk_to_draw(&mut self, chunks: Vec<(ChunkPosition, Chunk)>) {
+    //the farthest zoom setting, lowered to what the renderer can show all of: every chunk in
+    //view has to fit in its texture array and balls need at least a pixel per cell
+    pub fn max_zoom(&self) -> f32 {
+        let max_zoom = self.settings.max_zoom.max(self.settings.min_zoom);
+        let Some(render_state) = &self.render_state else {
+            return max_zoom;
+        };
+        let camera = &self.camera;
+        let fit = camera.screensize[0].min(camera.screensize[1] * camera.min_ratio);
+        //chunks across and up per cell of width, a partial chunk can show at both edges
+        let [p, q] = camera.screensize.map(|size| size / fit / CHUNK_SIZE as f32);
+        let chunks = render_state.max_chunks() as f32;
+        let widest = (-(p + q) + ((p + q).powi(2) + p * q * (chunks - 4.0)).sqrt()) / (p * q);
+        max_zoom.min(widest).min(fit).max(self.settings.min_zoom)
+    }
+
+    pub fn set_chunk_to_draw(&mut self, layer: Layer, mut chunks: Vec<(ChunkPosition, Chunk)>) {
         if let Some(ref mut render_state) = &mut self.render_state {
+            //when zoomed out past what the renderer can hold keep the chunks nearest the center
+            if chunks.len() > render_state.max_chunks() {
+                let center = self.camera.pos.map(|p| p / CHUNK_SIZE as f32 - 0.5);
+                chunks.sort_by(|(a, _), (b, _)| {
+                    let dist = |pos: &ChunkPosition| {
+                        (0..2)
+                            .map(|i| (pos.position[i] as f32 - center[i]).powi(2))
+                            .sum::<f32>()
+                    };
+                    dist(a).total_cmp(&dist(b))
+                });
+                chunks.truncate(render_state.max_chunks());
+            }
             let (pos, data) = chunks.into_iter().unzip();
-            render_state.update_chunks(pos, data);
+            match layer {
+                Layer::Ground => render_state.update_chunks(pos, data),
+                Layer::Overlay => render_state.update_overlay_chunks(pos, data),
+            }
         }
     }
 
-    pub fn set_balls_to_draw(&mut self, balls: Vec<(BallPosition, (bool, Direction))>) {
+    pub fn set_balls_to_draw(&mut self, mut balls: Vec<(BallPosition, (bool, Direction))>) {
         if let Some(ref mut render_state) = &mut self.render_state {
+            balls.truncate(render_state.max_balls());
             let (pos, data) = balls.into_iter().unzip();
             render_state.update_balls(pos, data);
         }
     }
+
+    pub fn set_highlighted_cell(&mut self, cell: Option<[i32; 2]>) {
+        if let Some(ref mut render_state) = &mut self.render_state {
+            render_state.set_highlighted_cell(cell);
+        }
+    }
+
+    pub fn set_selection(&mut self, selection: Option<([i32; 2], [i32; 2])>) {
+        if let Some(ref mut render_state) = &mut self.render_state {
+            render_state.set_selection(selection);
+        }
+    }
+
+    pub fn set_text_to_draw(&mut self, labels: Vec<TextLabel>) {
+        if let Some(ref mut render_state) = &mut self.render_state {
+            render_state.update_text(&labels);
+        }
+    }
+}
+
+fn load_layout(ctx: &Context) {
+    let path = config_path(LAYOUT_FILE);
+    let Ok(text) = fs::read_to_string(&path) else {
+        return;
+    };
+    match ron::from_str::<egui::Memory>(&text) {
+        Ok(memory) => ctx.memory_mut(|current| *current = memory),
+        Err(e) => log::warn!("ignoring invalid {}: {e}", path.display()),
+    }
+}
+
+fn save_layout(ctx: &Context) -> anyhow::Result<()> {
+    let path = config_path(LAYOUT_FILE);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = ctx.memory(ron::to_string)?;
+    fs::write(path, text)?;
+    Ok(())
 }
 
 impl ApplicationHandler<RenderState> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attributes = Window::default_attributes();
+        let window_attributes = Window::default_attributes().with_title(&self.title);
 
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
 
         self.render_state = Some(pollster::block_on(RenderState::new(window)).unwrap());
+        if let Some(state) = &self.render_state {
+            load_layout(&state.egui_platform.context());
+        }
 
         //default chunk
         self.render_state.as_mut().unwrap().update_chunks(
@@ -178,8 +485,14 @@ impl ApplicationHandler<RenderState> for App {
                 data: from_fn(|_| Into::<u8>::into(Tile::Down)),
             }],
         );
+        self.supported_msaa_samples = self
+            .render_state
+            .as_ref()
+            .unwrap()
+            .supported_sample_counts()
+            .to_vec();
         //updating camera
-        let size = self.render_state.as_ref().unwrap().window.inner_size();
+        let size = self.render_state.as_ref().unwrap().window().inner_size();
         self.camera.screensize = [size.width as f32, size.height as f32];
         self.render_state
             .as_mut()
@@ -187,6 +500,14 @@ impl ApplicationHandler<RenderState> for App {
             .update_camera(self.camera);
     }
 
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = &self.render_state {
+            if let Err(e) = save_layout(&state.egui_platform.context()) {
+                log::error!("couldn't save the window layout: {e}");
+            }
+        }
+    }
+
     #[allow(unused_mut)]
     fn user_event(&mut self, _event_loop: &ActiveEventLoop, mut event: RenderState) {
         self.render_state = Some(event);
@@ -207,6 +528,9 @@ impl ApplicationHandler<RenderState> for App {
             return;
         }
 
+        if !matches!(event, WindowEvent::RedrawRequested) {
+            self.input_since_update = true;
+        }
         self.try_update();
 
         let mut state = match self.render_state.take() {
@@ -220,15 +544,30 @@ impl ApplicationHandler<RenderState> for App {
             return;
         }
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            //without a state there is nothing to lose
+            WindowEvent::CloseRequested if self.state.is_none() => event_loop.exit(),
+            WindowEvent::CloseRequested => self.close_requested = true,
+            WindowEvent::DroppedFile(path) => self.dropped_files.push(path),
             WindowEvent::Resized(size) => {
                 state.resize(size.width, size.height);
-                let size = state.window.inner_size();
+                let size = state.window().inner_size();
                 self.camera.screensize = [size.width as f32, size.height as f32];
             }
             WindowEvent::RedrawRequested => {
+                self.perf.frame();
                 profiling::scope!("rendering");
+                self.redraw_requested = false;
                 state.update_camera(self.camera);
+                state.update_post_effects(self.settings.post_effects);
+                state.set_shader_hot_reload(self.settings.shader_hot_reload);
+                state.set_debug_overlay(self.settings.debug_overlay);
+                state.set_msaa_samples(self.settings.msaa_samples);
+                state.set_vsync(self.settings.vsync);
+                state.set_clear_color(self.settings.background);
+                state.set_animate(self.settings.animations);
+                state.set_palette(self.settings.render_palette());
+                self.uploads = state.upload_stats();
+                self.perf.set_gpu_timings(state.gpu_timings());
 
                 match state.render(|ctx| {
                     self.ui(ctx);
@@ -237,10 +576,11 @@ impl ApplicationHandler<RenderState> for App {
                         self.last_render_time = Instant::now();
                     }
                     // Reconfigure the surface if it's lost or outdated
-                    Err(SurfaceError::Lost | SurfaceError::Outdated) => {
-                        let size = state.window.inner_size();
+                    Err(e @ (SurfaceError::Lost | SurfaceError::Outdated)) => {
+                        log::warn!("reconfiguring the surface: {e}");
+                        let size = state.window().inner_size();
                         state.resize(size.width, size.height);
-                        state.window.request_redraw();
+                        state.window().request_redraw();
                     }
                     Err(e) => {
                         log::error!("Unable to render {e}");
@@ -263,11 +603,23 @@ impl ApplicationHandler<RenderState> for App {
                     *match button {
                         winit::event::MouseButton::Left => &mut self.mouse_buttons.0,
                         winit::event::MouseButton::Right => &mut self.mouse_buttons.1,
+                        winit::event::MouseButton::Middle => &mut self.mouse_buttons.2,
                         _ => {
                             return;
                         }
                     } = state.is_pressed();
                 })();
+                if button == winit::event::MouseButton::Left && state.is_pressed() {
+                    let now = Instant::now();
+                    let pos = self.mouse_position;
+                    self.double_clicked = self.last_click.is_some_and(|(time, last_pos)| {
+                        now - time < DOUBLE_CLICK_TIME
+                            && (pos[0] - last_pos[0]).hypot(pos[1] - last_pos[1])
+                                < DOUBLE_CLICK_DISTANCE
+                    });
+                    //a third click starts a new pair instead of chaining
+                    self.last_click = (!self.double_clicked).then_some((now, pos));
+                }
             }
             WindowEvent::MouseWheel {
                 device_id: _,
@@ -283,6 +635,10 @@ impl ApplicationHandler<RenderState> for App {
                     }
                 };
             }
+            WindowEvent::Touch(touch) => self.handle_touch(touch),
+            WindowEvent::PinchGesture { delta, .. } => {
+                self.scroll_level += (1.0 + delta as f32).max(0.01).log2() * SCROLL_SPEED;
+            }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -292,16 +648,36 @@ impl ApplicationHandler<RenderState> for App {
                     },
                 ..
             } => match (code, key_state.is_pressed()) {
+                (keycode, true) if self.rebinding.is_some() => {
+                    //escape cancels instead of binding
+                    if let Some(action) =
+                        self.rebinding.take().filter(|_| keycode != KeyCode::Escape)
+                    {
+                        self.settings.keybinds.set(action, keycode);
+                        if let Err(e) = self.settings.save() {
+                            log::error!("couldn't save settings: {e}");
+                        }
+                    }
+                }
                 (keycode, true) => {
                     self.keys_down.insert(keycode).consume();
-                    if keycode == KeyCode::F11 {
+                    self.keys_pressed.insert(keycode).consume();
+                    if keycode == self.settings.keybinds.key(Action::ToggleFullscreen)
+                        && !self.keyboard_captured()
+                    {
                         state
-                            .window
-                            .set_fullscreen(match state.window.fullscreen() {
+                            .window()
+                            .set_fullscreen(match state.window().fullscreen() {
                                 Some(_) => None,
                                 None => Some(winit::window::Fullscreen::Borderless(None)),
                             });
                     }
+                    if keycode == self.settings.keybinds.key(Action::TogglePerfHud)
+                        && !self.keyboard_captured()
+                    {
+                        self.perf.visible = !self.perf.visible;
+                        self.request_redraw();
+                    }
                 }
                 (keycode, false) => self.keys_down.remove(&keycode).consume(),
             },
@@ -312,8 +688,23 @@ impl ApplicationHandler<RenderState> for App {
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait);
+        if !self.needs_redraw() {
+            return;
+        }
+        if let Some(fps) = self.settings.fps_cap {
+            let next_frame = self.last_render_time + Duration::from_secs_f32(1.0 / fps as f32);
+            let remaining = next_frame.saturating_duration_since(Instant::now());
+            if remaining > MAX_FRAME_SLEEP {
+                //wake up again close to the next frame instead of blocking input handling
+                event_loop.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(
+                    next_frame - MAX_FRAME_SLEEP,
+                ));
+                return;
+            }
+            thread::sleep(remaining);
+        }
         if let Some(state) = self.render_state.as_mut() {
-            state.window.request_redraw()
+            state.window().request_redraw()
         }
     }
 }
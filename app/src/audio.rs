@@ -0,0 +1,185 @@
+#[cfg(feature = "audio")]
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use shared::egui::{Slider, Ui};
+#[cfg(feature = "audio")]
+use shared::log;
+
+//how long a note tile rings after a ball enters it
+#[cfg(feature = "audio")]
+const NOTE_LENGTH: Duration = Duration::from_millis(150);
+//keeps a few notes at full volume from clipping
+#[cfg(feature = "audio")]
+const NOTE_AMPLITUDE: f32 = 0.2;
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Effect {
+    Destroy,
+    Duplicate,
+    //the user changing the world with a tool
+    Place,
+    Click,
+}
+
+impl Effect {
+    pub const ALL: [Effect; 4] = [
+        Effect::Destroy,
+        Effect::Duplicate,
+        Effect::Place,
+        Effect::Click,
+    ];
+
+    //short blips so busy machines don't drown out note tiles
+    #[cfg(feature = "audio")]
+    fn tone(&self) -> (f32, Duration) {
+        match self {
+            Effect::Destroy => (110.0, Duration::from_millis(120)),
+            Effect::Duplicate => (880.0, Duration::from_millis(60)),
+            Effect::Place => (440.0, Duration::from_millis(30)),
+            Effect::Click => (1320.0, Duration::from_millis(15)),
+        }
+    }
+}
+
+//scaled by the master volume
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EffectVolumes {
+    pub destroy: f32,
+    pub duplicate: f32,
+    pub place: f32,
+    pub click: f32,
+}
+
+impl Default for EffectVolumes {
+    fn default() -> Self {
+        Self {
+            destroy: 0.5,
+            duplicate: 0.5,
+            place: 0.5,
+            click: 0.3,
+        }
+    }
+}
+
+impl EffectVolumes {
+    pub fn get(&self, effect: Effect) -> f32 {
+        match effect {
+            Effect::Destroy => self.destroy,
+            Effect::Duplicate => self.duplicate,
+            Effect::Place => self.place,
+            Effect::Click => self.click,
+        }
+    }
+
+    fn get_mut(&mut self, effect: Effect) -> &mut f32 {
+        match effect {
+            Effect::Destroy => &mut self.destroy,
+            Effect::Duplicate => &mut self.duplicate,
+            Effect::Place => &mut self.place,
+            Effect::Click => &mut self.click,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Effect::ALL.into_iter().for_each(|effect| {
+            ui.add(
+                Slider::new(self.get_mut(effect), 0.0..=1.0)
+                    .text(format!("{effect:?}").to_lowercase()),
+            );
+        });
+    }
+}
+
+//sound output for note tiles and effects, silent without the `audio` feature or an output device
+pub struct Audio {
+    //the stream has to stay alive for the handle to play anything
+    #[cfg(feature = "audio")]
+    output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Audio {
+    #[cfg(feature = "audio")]
+    pub fn new() -> Self {
+        let output = rodio::OutputStream::try_default()
+            .map_err(|e| log::warn!("no audio output, note tiles will be silent: {e}"))
+            .ok();
+        Self { output }
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    //never opens a device
+    pub fn silent() -> Self {
+        Self {
+            #[cfg(feature = "audio")]
+            output: None,
+        }
+    }
+
+    pub fn available(&self) -> bool {
+        #[cfg(feature = "audio")]
+        return self.output.is_some();
+        #[cfg(not(feature = "audio"))]
+        false
+    }
+
+    //`pitch` is a midi note number, 60 being middle C
+    #[cfg(feature = "audio")]
+    pub fn play(&self, pitch: u8, volume: f32) {
+        self.tone(frequency(pitch), NOTE_LENGTH, volume);
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn play(&self, _pitch: u8, _volume: f32) {}
+
+    #[cfg(feature = "audio")]
+    pub fn play_effect(&self, effect: Effect, volume: f32) {
+        let (frequency, length) = effect.tone();
+        self.tone(frequency, length, volume);
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn play_effect(&self, _effect: Effect, _volume: f32) {}
+
+    #[cfg(feature = "audio")]
+    fn tone(&self, frequency: f32, length: Duration, volume: f32) {
+        use rodio::{source::SineWave, Source};
+
+        let Some((_, handle)) = &self.output else {
+            return;
+        };
+        let tone = SineWave::new(frequency)
+            .take_duration(length)
+            .amplify(volume * NOTE_AMPLITUDE);
+        if let Err(e) = handle.play_raw(tone) {
+            log::warn!("failed to play a {frequency}hz tone: {e}");
+        }
+    }
+}
+
+//equal temperament tuned to A4 at 440hz
+pub fn frequency(pitch: u8) -> f32 {
+    440.0 * 2f32.powf((pitch as f32 - 69.0) / 12.0)
+}
+
+pub fn note_name(pitch: u8) -> String {
+    format!(
+        "{}{}",
+        NOTE_NAMES[pitch as usize % 12],
+        pitch as i32 / 12 - 1
+    )
+}
@@ -0,0 +1,126 @@
+use std::{fs, path::Path};
+
+use engine::{ball::Direction, tiles::Tile};
+use serde::{Deserialize, Serialize};
+use shared::{anyhow, log};
+
+pub const BLUEPRINT_DIR: &str = "blueprints";
+pub const BLUEPRINT_EXTENSION: &str = "blueprint";
+
+//a piece of a world that can be stamped down elsewhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub name: String,
+    //cells relative to the bottom left corner, empty tiles are left out
+    pub tiles: Vec<([i32; 2], Tile)>,
+    pub balls: Vec<([i32; 2], (bool, Direction))>,
+    //for finding it in the browser, lowercase
+    pub tags: Vec<String>,
+}
+
+//saved before blueprints had tags, the files carry no version so the layout is tried instead
+#[derive(Deserialize)]
+struct BlueprintV1 {
+    name: String,
+    tiles: Vec<([i32; 2], Tile)>,
+    balls: Vec<([i32; 2], (bool, Direction))>,
+}
+
+fn rotate_direction(dir: Direction) -> Direction {
+    match dir {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+impl Blueprint {
+    //a quarter turn clockwise, keeping the bottom left corner at the origin
+    pub fn rotated(&self) -> Blueprint {
+        let turn = |pos: [i32; 2]| [pos[1], -pos[0]];
+        let min_y = self
+            .tiles
+            .iter()
+            .map(|(pos, _)| pos)
+            .chain(self.balls.iter().map(|(pos, _)| pos))
+            .map(|pos| -pos[0])
+            .min()
+            .unwrap_or(0);
+        let shift = |pos: [i32; 2]| [pos[0], pos[1] - min_y];
+        Blueprint {
+            name: self.name.clone(),
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(pos, tile)| (shift(turn(*pos)), tile.rotated()))
+                .collect(),
+            balls: self
+                .balls
+                .iter()
+                .map(|(pos, (on, dir))| (shift(turn(*pos)), (*on, rotate_direction(*dir))))
+                .collect(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    //cells across and up, at least one each
+    pub fn size(&self) -> [i32; 2] {
+        self.tiles
+            .iter()
+            .map(|(pos, _)| pos)
+            .chain(self.balls.iter().map(|(pos, _)| pos))
+            .fold([1, 1], |size, pos| {
+                [size[0].max(pos[0] + 1), size[1].max(pos[1] + 1)]
+            })
+    }
+
+    pub fn matches(&self, search: &str) -> bool {
+        let search = search.to_lowercase();
+        self.name.to_lowercase().contains(&search)
+            || self.tags.iter().any(|tag| tag.contains(&search))
+    }
+
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(&self.name).with_extension(BLUEPRINT_EXTENSION);
+        fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Blueprint> {
+        let bytes = fs::read(path)?;
+        if let Ok(blueprint) = bincode::deserialize(&bytes) {
+            return Ok(blueprint);
+        }
+        let old: BlueprintV1 = bincode::deserialize(&bytes)?;
+        Ok(Blueprint {
+            name: old.name,
+            tiles: old.tiles,
+            balls: old.balls,
+            tags: Vec::new(),
+        })
+    }
+
+    //unreadable files are skipped so one bad blueprint doesn't hide the rest
+    pub fn load_all(dir: &Path) -> Vec<Blueprint> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+        let mut blueprints: Vec<Blueprint> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == BLUEPRINT_EXTENSION)
+            })
+            .filter_map(|path| {
+                Blueprint::load(&path)
+                    .inspect_err(|e| log::warn!("skipping blueprint {}: {e}", path.display()))
+                    .ok()
+            })
+            .collect();
+        blueprints.sort_by(|a, b| a.name.cmp(&b.name));
+        blueprints
+    }
+}
@@ -0,0 +1,179 @@
+use std::{collections::BTreeSet, path::Path};
+
+use shared::{
+    egui::{self, Color32, Grid, Id, LayerId, Order, Painter, Rect, Sense, TextEdit, Ui},
+    log,
+};
+
+use crate::{
+    blueprint::{Blueprint, BLUEPRINT_DIR},
+    tile_image::tile_color,
+};
+
+//the longer side of a thumbnail in points
+const PREVIEW_SIZE: f32 = 48.0;
+
+pub enum BlueprintAction {
+    //make it the stamp
+    Pick(usize),
+    //dropped over the world, placed with its bottom left corner at the cell under the pointer
+    Drop(usize),
+}
+
+//the saved blueprints with previews, narrowed down by a search and a tag
+#[derive(Default)]
+pub struct BlueprintBrowser {
+    search: String,
+    tag: Option<String>,
+    //the blueprint whose tags are being edited and the text typed so far, comma separated
+    editing: Option<(String, String)>,
+}
+
+fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = text
+        .split(',')
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn preview_size(blueprint: &Blueprint) -> egui::Vec2 {
+    let size = blueprint.size();
+    let scale = PREVIEW_SIZE / size[0].max(size[1]) as f32;
+    egui::vec2(size[0] as f32 * scale, size[1] as f32 * scale)
+}
+
+//tiles in their palette colors with balls over them, y goes up in the world and down on the screen
+fn paint_preview(painter: &Painter, rect: Rect, blueprint: &Blueprint) {
+    let cell = rect.width() / blueprint.size()[0] as f32;
+    let cell_rect = |[x, y]: [i32; 2]| {
+        Rect::from_min_size(
+            egui::pos2(
+                rect.left() + x as f32 * cell,
+                rect.bottom() - (y + 1) as f32 * cell,
+            ),
+            egui::vec2(cell, cell),
+        )
+    };
+    painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+    blueprint.tiles.iter().for_each(|(pos, tile)| {
+        let [r, g, b, _] = tile_color(*tile);
+        painter.rect_filled(cell_rect(*pos), 0.0, Color32::from_rgb(r, g, b));
+    });
+    blueprint.balls.iter().for_each(|(pos, (on, _))| {
+        let fill = if *on {
+            Color32::WHITE
+        } else {
+            Color32::from_gray(70)
+        };
+        painter.circle_filled(cell_rect(*pos).center(), cell * 0.35, fill);
+    });
+}
+
+impl BlueprintBrowser {
+    //`selected` is the name of the blueprint being stamped
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        blueprints: &mut [Blueprint],
+        selected: Option<&str>,
+    ) -> Option<BlueprintAction> {
+        let mut action = None;
+        ui.add(TextEdit::singleline(&mut self.search).hint_text("search names and tags"));
+        let tags: BTreeSet<&str> = blueprints
+            .iter()
+            .flat_map(|blueprint| blueprint.tags.iter().map(String::as_str))
+            .collect();
+        if !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                tags.into_iter().for_each(|tag| {
+                    let on = self.tag.as_deref() == Some(tag);
+                    if ui.selectable_label(on, tag).clicked() {
+                        self.tag = (!on).then(|| tag.to_owned());
+                    }
+                });
+            });
+        }
+        let shown: Vec<usize> = (0..blueprints.len())
+            .filter(|&i| {
+                blueprints[i].matches(&self.search)
+                    && self
+                        .tag
+                        .as_ref()
+                        .is_none_or(|tag| blueprints[i].tags.contains(tag))
+            })
+            .collect();
+        if blueprints.is_empty() {
+            ui.label(format!("no blueprints saved in {BLUEPRINT_DIR}/ yet"));
+        } else if shown.is_empty() {
+            ui.label("no blueprint matches");
+        }
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .show(ui, |ui| {
+                Grid::new("blueprint list").striped(true).show(ui, |ui| {
+                    shown.into_iter().for_each(|i| {
+                        let blueprint = &blueprints[i];
+                        let (response, painter) =
+                            ui.allocate_painter(preview_size(blueprint), Sense::click_and_drag());
+                        paint_preview(&painter, response.rect, blueprint);
+                        //the preview follows the pointer until it is let go of
+                        if response.dragged() {
+                            if let Some(pointer) = ui.ctx().pointer_latest_pos() {
+                                let painter = ui.ctx().layer_painter(LayerId::new(
+                                    Order::Tooltip,
+                                    Id::new("dragged blueprint"),
+                                ));
+                                let rect = Rect::from_min_size(pointer, preview_size(blueprint));
+                                paint_preview(&painter, rect, blueprint);
+                            }
+                        }
+                        if response.drag_stopped() && !ui.ctx().is_pointer_over_area() {
+                            action = Some(BlueprintAction::Drop(i));
+                        }
+                        if response
+                            .on_hover_text("click to stamp it, or drag it into the world")
+                            .clicked()
+                        {
+                            action = Some(BlueprintAction::Pick(i));
+                        }
+                        ui.vertical(|ui| {
+                            let on = selected == Some(blueprint.name.as_str());
+                            if ui.selectable_label(on, &blueprint.name).clicked() {
+                                action = Some(BlueprintAction::Pick(i));
+                            }
+                            if !blueprint.tags.is_empty() {
+                                ui.weak(blueprint.tags.join(", "));
+                            }
+                        });
+                        match &mut self.editing {
+                            Some((name, text)) if *name == blueprint.name => {
+                                ui.add(
+                                    TextEdit::singleline(text).hint_text("tags, comma separated"),
+                                );
+                                if ui.button("done").clicked() {
+                                    blueprints[i].tags = parse_tags(text);
+                                    if let Err(e) = blueprints[i].save(Path::new(BLUEPRINT_DIR)) {
+                                        log::error!("couldn't save blueprint: {e}");
+                                    }
+                                    self.editing = None;
+                                }
+                            }
+                            _ => {
+                                ui.label("");
+                                if ui.button("tags").clicked() {
+                                    self.editing =
+                                        Some((blueprint.name.clone(), blueprint.tags.join(", ")));
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    });
+                });
+            });
+        action
+    }
+}
@@ -0,0 +1,329 @@
+//a tiny command language for the palette and the console, one command per line with its
+//arguments separated by spaces
+use std::collections::VecDeque;
+
+use engine::tiles::Tile;
+use shared::{
+    anyhow,
+    egui::{self, Align2, Context, Key, TextEdit},
+};
+
+//output lines the console keeps
+const MAX_LINES: usize = 200;
+//suggestions the palette lists at once
+const MAX_SUGGESTIONS: usize = 8;
+
+//name, arguments and what it does, in the order the palette lists them
+pub const COMMANDS: [(&str, &str, &str); 13] = [
+    ("goto", "<x> <y>", "moves the camera to a cell"),
+    (
+        "fill",
+        "[<x1> <y1> <x2> <y2>] <tile|on|off>",
+        "fills a rectangle, or the selection, with a tile or balls",
+    ),
+    (
+        "run",
+        "<updates>",
+        "runs that many updates, stopping at breakpoints",
+    ),
+    ("play", "", "keeps updating"),
+    ("pause", "", "stops updating"),
+    ("reset", "", "goes back to the world before it was stepped"),
+    ("load", "<path>", "opens a world"),
+    (
+        "save",
+        "[<path>]",
+        "saves the world, where it was opened from by default",
+    ),
+    ("tool", "<tile|on|off>", "picks a tile or ball tool"),
+    ("frame", "", "fits everything on screen"),
+    ("undo", "", "undoes the last edit"),
+    ("redo", "", "redoes the last undone edit"),
+    ("help", "", "lists the commands"),
+];
+
+//what fill and tool take, matched without caring about case
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    Tile(Tile),
+    Ball(bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Goto([i32; 2]),
+    //None fills the selection
+    Fill(Option<([i32; 2], [i32; 2])>, Paint),
+    Run(u32),
+    Play,
+    Pause,
+    Reset,
+    Load(String),
+    Save(Option<String>),
+    Tool(Paint),
+    Frame,
+    Undo,
+    Redo,
+    Help,
+}
+
+fn parse_paint(word: &str) -> anyhow::Result<Paint> {
+    match word.to_lowercase().as_str() {
+        "on" => return Ok(Paint::Ball(true)),
+        "off" => return Ok(Paint::Ball(false)),
+        _ => {}
+    }
+    (0..Tile::COUNT)
+        .filter_map(|value| Tile::try_from(value).ok())
+        .find(|tile| tile.name().eq_ignore_ascii_case(word))
+        .map(Paint::Tile)
+        .ok_or_else(|| anyhow::anyhow!("{word} isn't a tile, on or off"))
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str) -> anyhow::Result<T> {
+    word.parse()
+        .map_err(|_| anyhow::anyhow!("{word} isn't a number"))
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> anyhow::Result<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = words.split_first() else {
+            anyhow::bail!("nothing to run");
+        };
+        let command = match (name.to_lowercase().as_str(), args) {
+            ("goto", [x, y]) => Self::Goto([parse_number(x)?, parse_number(y)?]),
+            ("fill", [paint]) => Self::Fill(None, parse_paint(paint)?),
+            ("fill", [x1, y1, x2, y2, paint]) => {
+                let (a, b): ([i32; 2], [i32; 2]) = (
+                    [parse_number(x1)?, parse_number(y1)?],
+                    [parse_number(x2)?, parse_number(y2)?],
+                );
+                let min = [a[0].min(b[0]), a[1].min(b[1])];
+                let max = [a[0].max(b[0]), a[1].max(b[1])];
+                Self::Fill(Some((min, max)), parse_paint(paint)?)
+            }
+            ("run", [count]) => Self::Run(parse_number(count)?),
+            ("play", []) => Self::Play,
+            ("pause", []) => Self::Pause,
+            ("reset", []) => Self::Reset,
+            //paths may have spaces in them
+            ("load", [_, ..]) => Self::Load(args.join(" ")),
+            ("save", []) => Self::Save(None),
+            ("save", [_, ..]) => Self::Save(Some(args.join(" "))),
+            ("tool", [paint]) => Self::Tool(parse_paint(paint)?),
+            ("frame", []) => Self::Frame,
+            ("undo", []) => Self::Undo,
+            ("redo", []) => Self::Redo,
+            ("help", []) => Self::Help,
+            (name, _) => match COMMANDS.iter().find(|(command, _, _)| *command == name) {
+                Some((command, usage, _)) => anyhow::bail!("usage: {command} {usage}"),
+                None => anyhow::bail!("there is no {name} command, try help"),
+            },
+        };
+        Ok(command)
+    }
+}
+
+//the ctrl+p palette and the console window, both hand back the lines entered for the
+//simulation to run
+#[derive(Default)]
+pub struct Console {
+    pub palette_open: bool,
+    //the palette has its own line so opening it never picks up a half typed console command
+    palette_input: String,
+    //the highlighted suggestion
+    selected: usize,
+    //why the last line entered into the palette failed, it stays open to fix it
+    palette_error: Option<String>,
+    input: String,
+    lines: VecDeque<String>,
+    //lines entered before, oldest first, and how far back the up key has gone
+    entered: Vec<String>,
+    recall: Option<usize>,
+}
+
+impl Console {
+    pub fn print(&mut self, line: impl Into<String>) {
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.into());
+    }
+
+    pub fn open_palette(&mut self) {
+        self.palette_open = true;
+        self.palette_input.clear();
+        self.palette_error = None;
+        self.selected = 0;
+    }
+
+    //how the line the palette handed out went
+    pub fn palette_result(&mut self, result: anyhow::Result<String>) {
+        match result {
+            Ok(output) => {
+                self.palette_open = false;
+                if !output.is_empty() {
+                    self.print(output);
+                }
+            }
+            Err(e) => self.palette_error = Some(e.to_string()),
+        }
+    }
+
+    fn remember(&mut self, line: &str) {
+        if self.entered.last().map(String::as_str) != Some(line) {
+            self.entered.push(line.to_owned());
+        }
+        self.recall = None;
+    }
+
+    pub fn help(&mut self) {
+        COMMANDS.iter().for_each(|(name, usage, about)| {
+            self.print(format!("{name} {usage}: {about}"));
+        });
+    }
+
+    fn suggestions(&self) -> Vec<&'static (&'static str, &'static str, &'static str)> {
+        let typed = self
+            .palette_input
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        COMMANDS
+            .iter()
+            .filter(|(name, _, _)| name.contains(typed.as_str()))
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+
+    //Some with the line once enter is pressed
+    pub fn palette_ui(&mut self, ctx: &Context) -> Option<String> {
+        if !self.palette_open {
+            return None;
+        }
+        let mut entered = None;
+        egui::Window::new("command palette")
+            .anchor(Align2::CENTER_TOP, [0.0, 40.0])
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.palette_input)
+                        .hint_text("type a command, tab completes it")
+                        .desired_width(320.0),
+                );
+                response.request_focus();
+                let suggestions = self.suggestions();
+                self.selected = self.selected.min(suggestions.len().saturating_sub(1));
+                let (up, down, tab, enter, escape) = ctx.input(|input| {
+                    (
+                        input.key_pressed(Key::ArrowUp),
+                        input.key_pressed(Key::ArrowDown),
+                        input.key_pressed(Key::Tab),
+                        input.key_pressed(Key::Enter),
+                        input.key_pressed(Key::Escape),
+                    )
+                });
+                if up {
+                    self.selected = self.selected.saturating_sub(1);
+                }
+                if down && self.selected + 1 < suggestions.len() {
+                    self.selected += 1;
+                }
+                suggestions
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, (name, usage, about))| {
+                        let text = format!("{name} {usage}");
+                        if ui
+                            .selectable_label(i == self.selected, text)
+                            .on_hover_text(*about)
+                            .clicked()
+                        {
+                            self.selected = i;
+                            self.palette_input = format!("{name} ");
+                        }
+                    });
+                let typed_name = self.palette_input.split_whitespace().next().unwrap_or("");
+                let complete = COMMANDS.iter().any(|(name, _, _)| *name == typed_name);
+                //commands without arguments run straight from the list
+                let picked = suggestions.get(self.selected).copied();
+                if tab || (enter && !complete) {
+                    if let Some((name, usage, _)) = picked {
+                        self.palette_input = if usage.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{name} ")
+                        };
+                    }
+                }
+                if enter && (complete || picked.is_some_and(|(_, usage, _)| usage.is_empty())) {
+                    let line = self.palette_input.trim().to_owned();
+                    self.remember(&line);
+                    self.print(format!("> {line}"));
+                    entered = Some(line);
+                }
+                if let Some(error) = &self.palette_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if escape {
+                    self.palette_open = false;
+                }
+            });
+        entered
+    }
+
+    pub fn console_ui(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                self.lines.iter().for_each(|line| {
+                    ui.monospace(line);
+                });
+            });
+        let response = ui.add(
+            TextEdit::singleline(&mut self.input)
+                .hint_text("help lists the commands")
+                .desired_width(f32::INFINITY),
+        );
+        if !response.has_focus() {
+            if response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+                let line = std::mem::take(&mut self.input).trim().to_owned();
+                response.request_focus();
+                if !line.is_empty() {
+                    self.remember(&line);
+                    self.print(format!("> {line}"));
+                    return Some(line);
+                }
+            }
+            return None;
+        }
+        //going through the lines entered before
+        let (up, down) = ui.input(|input| {
+            (
+                input.key_pressed(Key::ArrowUp),
+                input.key_pressed(Key::ArrowDown),
+            )
+        });
+        if up && !self.entered.is_empty() {
+            let back = self
+                .recall
+                .map_or(self.entered.len() - 1, |i| i.saturating_sub(1));
+            self.recall = Some(back);
+            self.input = self.entered[back].clone();
+        }
+        if down {
+            if let Some(i) = self.recall {
+                self.recall = (i + 1 < self.entered.len()).then_some(i + 1);
+                self.input = self
+                    .recall
+                    .map_or(String::new(), |i| self.entered[i].clone());
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+use rfd::FileDialog;
+
+use crate::world::{WORLD_DIR, WORLD_EXTENSION};
+
+//native file pickers, these block until closed and return None when cancelled
+
+//starts next to `current` so repeated saves land in the same place
+fn dialog_near(current: &str) -> FileDialog {
+    let current = Path::new(current);
+    let dialog = FileDialog::new();
+    let dialog = match current.parent().filter(|dir| dir.is_dir()) {
+        Some(dir) => dialog.set_directory(dir),
+        None if Path::new(WORLD_DIR).is_dir() => dialog.set_directory(WORLD_DIR),
+        None => dialog,
+    };
+    match current.file_name() {
+        Some(name) => dialog.set_file_name(name.to_string_lossy()),
+        None => dialog,
+    }
+}
+
+pub fn open_world(current: &str) -> Option<PathBuf> {
+    dialog_near(current)
+        .add_filter("world", &[WORLD_EXTENSION])
+        .pick_file()
+}
+
+pub fn save_world(current: &str) -> Option<PathBuf> {
+    dialog_near(current)
+        .add_filter("world", &[WORLD_EXTENSION])
+        .save_file()
+        .map(|path| path.with_extension(WORLD_EXTENSION))
+}
+
+pub fn open_pattern(current: &str) -> Option<PathBuf> {
+    dialog_near(current)
+        .add_filter("pattern", &["rle", "csv", "txt", "png"])
+        .pick_file()
+}
+
+pub fn save_image(current: &str) -> Option<PathBuf> {
+    dialog_near(current)
+        .add_filter("png", &["png"])
+        .save_file()
+        .map(|path| path.with_extension("png"))
+}
@@ -0,0 +1,137 @@
+use engine::{ball::Direction, tiles::Tile};
+use shared::egui::{ComboBox, Slider, Ui};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    //corridors carved out of Block walls, `size` counts corridor cells
+    Maze { size: [i32; 2] },
+    Noise { size: [i32; 2], density: f32 },
+    //duplicators loaded with a ball, each sends a ball right every update
+    Emitters { count: [i32; 2], spacing: i32 },
+}
+
+impl Generator {
+    const DEFAULTS: [Generator; 3] = [
+        Generator::Maze { size: [16, 16] },
+        Generator::Noise {
+            size: [32, 32],
+            density: 0.3,
+        },
+        Generator::Emitters {
+            count: [4, 4],
+            spacing: 8,
+        },
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Generator::Maze { .. } => "maze",
+            Generator::Noise { .. } => "block noise",
+            Generator::Emitters { .. } => "emitter grid",
+        }
+    }
+
+    //tiles and balls relative to the bottom left corner
+    #[allow(clippy::type_complexity)]
+    pub fn generate(
+        &self,
+        rng: &mut fastrand::Rng,
+    ) -> (Vec<([i32; 2], Tile)>, Vec<([i32; 2], (bool, Direction))>) {
+        match *self {
+            Generator::Maze { size } => (maze(size, rng), vec![]),
+            Generator::Noise { size, density } => (
+                (0..size[0])
+                    .flat_map(|x| (0..size[1]).map(move |y| [x, y]))
+                    .filter(|_| rng.f32() < density)
+                    .map(|pos| (pos, Tile::Block))
+                    .collect(),
+                vec![],
+            ),
+            Generator::Emitters { count, spacing } => {
+                let cells: Vec<[i32; 2]> = (0..count[0])
+                    .flat_map(|x| (0..count[1]).map(move |y| [x * spacing, y * spacing]))
+                    .collect();
+                (
+                    cells.iter().map(|pos| (*pos, Tile::DuplicateH)).collect(),
+                    cells
+                        .iter()
+                        .map(|pos| (*pos, (rng.bool(), Direction::Right)))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ComboBox::from_id_salt("generator")
+            .selected_text(self.name())
+            .show_ui(ui, |ui| {
+                Generator::DEFAULTS.iter().for_each(|generator| {
+                    if ui
+                        .selectable_label(self.name() == generator.name(), generator.name())
+                        .clicked()
+                    {
+                        *self = *generator;
+                    }
+                });
+            });
+        match self {
+            Generator::Maze { size } => {
+                ui.add(Slider::new(&mut size[0], 2..=128).text("width"));
+                ui.add(Slider::new(&mut size[1], 2..=128).text("height"));
+            }
+            Generator::Noise { size, density } => {
+                ui.add(Slider::new(&mut size[0], 1..=256).text("width"));
+                ui.add(Slider::new(&mut size[1], 1..=256).text("height"));
+                ui.add(Slider::new(density, 0.0..=1.0).text("density"));
+            }
+            Generator::Emitters { count, spacing } => {
+                ui.add(Slider::new(&mut count[0], 1..=32).text("columns"));
+                ui.add(Slider::new(&mut count[1], 1..=32).text("rows"));
+                ui.add(Slider::new(spacing, 2..=32).text("spacing"));
+            }
+        }
+    }
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::DEFAULTS[0]
+    }
+}
+
+//depth first backtracker, corridor cells sit on odd coordinates with walls between them
+fn maze(size: [i32; 2], rng: &mut fastrand::Rng) -> Vec<([i32; 2], Tile)> {
+    let (w, h) = (size[0], size[1]);
+    let mut visited = vec![false; (w * h) as usize];
+    let mut open = vec![false; ((2 * w + 1) * (2 * h + 1)) as usize];
+    let open_index = |pos: [i32; 2]| (pos[0] + pos[1] * (2 * w + 1)) as usize;
+
+    let mut stack = vec![[0, 0]];
+    visited[0] = true;
+    open[open_index([1, 1])] = true;
+    while let Some(&cell) = stack.last() {
+        let neighbours: Vec<[i32; 2]> = [[1, 0], [-1, 0], [0, 1], [0, -1]]
+            .iter()
+            .map(|d| [cell[0] + d[0], cell[1] + d[1]])
+            .filter(|n| (0..w).contains(&n[0]) && (0..h).contains(&n[1]))
+            .filter(|n| !visited[(n[0] + n[1] * w) as usize])
+            .collect();
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = neighbours[rng.usize(..neighbours.len())];
+        visited[(next[0] + next[1] * w) as usize] = true;
+        //the wall between the two cells and the new cell itself
+        open[open_index([cell[0] + next[0] + 1, cell[1] + next[1] + 1])] = true;
+        open[open_index([2 * next[0] + 1, 2 * next[1] + 1])] = true;
+        stack.push(next);
+    }
+
+    (0..2 * w + 1)
+        .flat_map(|x| (0..2 * h + 1).map(move |y| [x, y]))
+        .filter(|pos| !open[open_index(*pos)])
+        .map(|pos| (pos, Tile::Block))
+        .collect()
+}
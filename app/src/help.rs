@@ -0,0 +1,220 @@
+use std::time::{Duration, Instant};
+
+use engine::{
+    ball::Direction,
+    lane::step_pos,
+    rules::{Rules, StepMode},
+    tiles::Tile,
+};
+use shared::{
+    egui::{self, Color32, Sense, Stroke, StrokeKind, Ui},
+    log,
+};
+
+use crate::{
+    sim::{tile_combo_box, Simulation},
+    tile_image::tile_color,
+    tile_info::TileInfo,
+};
+
+//cells across and up in a preview, the tile sits in the middle
+const PREVIEW_SIZE: [i32; 2] = [8, 5];
+const TILE: [i32; 2] = [3, 2];
+//an on ball followed by an off ball, both coming in from the left
+const BALLS: [(i32, bool); 2] = [(2, true), (0, false)];
+//updates a preview runs before it starts over
+const PREVIEW_TICKS: u32 = 8;
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(500);
+const CELL_SIZE: f32 = 20.0;
+
+//the rules reference, with a tile picked out and shown working in a tiny world
+pub struct Help {
+    tile: Tile,
+    //built the first time a preview is shown and refilled whenever it starts over
+    preview: Option<Box<Simulation>>,
+    preview_tile: Option<Tile>,
+    preview_ticks: u32,
+    last_step: Instant,
+}
+
+impl Default for Help {
+    fn default() -> Self {
+        Self {
+            tile: Tile::Hold,
+            preview: None,
+            preview_tile: None,
+            preview_ticks: 0,
+            last_step: Instant::now(),
+        }
+    }
+}
+
+//the incoming balls plus whatever the tile needs to show off
+fn fill_preview(sim: &mut Simulation, tile: Tile) {
+    sim.set_tile(TILE, tile);
+    BALLS
+        .into_iter()
+        .for_each(|(x, on)| sim.set_ball([x, TILE[1]], (on, Direction::Right)));
+    if matches!(tile, Tile::Hold | Tile::Sticky) {
+        sim.set_ball(TILE, (false, Direction::Right));
+    }
+    if tile.portal_facing().is_some() {
+        let exit = [TILE[0] + 3, TILE[1]];
+        sim.set_tile(exit, tile);
+        sim.tile_data_mut(TILE).link = Some(exit);
+        sim.tile_data_mut(exit).link = Some(TILE);
+    }
+    if let Some(dir) = tile.piston_facing() {
+        sim.set_ball(step_pos(TILE, dir), (false, Direction::Right));
+    }
+    if tile == Tile::PressurePlate {
+        //the plate opens the wall just past it
+        sim.set_tile([TILE[0] + 1, TILE[1]], Tile::Block);
+    }
+}
+
+impl Help {
+    fn reset_preview(&mut self, rules: &Rules) {
+        let sim = self
+            .preview
+            .get_or_insert_with(|| Box::new(Simulation::practice([0.0; 2])));
+        let balls: Vec<[i32; 2]> = sim.balls().map(|(pos, _)| pos).collect();
+        balls.into_iter().for_each(|pos| sim.remove_ball(pos));
+        (0..PREVIEW_SIZE[0])
+            .flat_map(|x| (0..PREVIEW_SIZE[1]).map(move |y| [x, y]))
+            .for_each(|pos| sim.set_tile(pos, Tile::Empty));
+        if let Err(e) = sim.set_rules(rules.clone()) {
+            log::error!("couldn't preview with the world rules: {e}");
+        }
+        fill_preview(sim, self.tile);
+        self.preview_tile = Some(self.tile);
+        self.preview_ticks = 0;
+        self.last_step = Instant::now();
+    }
+
+    fn preview_ui(&mut self, ui: &mut Ui, rules: &Rules) {
+        if self.preview_tile != Some(self.tile) || self.preview_ticks == PREVIEW_TICKS {
+            self.reset_preview(rules);
+        }
+        let Some(sim) = &mut self.preview else {
+            return;
+        };
+        if self.last_step.elapsed() >= PREVIEW_INTERVAL {
+            sim.full_step();
+            self.preview_ticks += 1;
+            self.last_step = Instant::now();
+        }
+        ui.ctx()
+            .request_repaint_after(PREVIEW_INTERVAL.saturating_sub(self.last_step.elapsed()));
+
+        let size = egui::vec2(
+            PREVIEW_SIZE[0] as f32 * CELL_SIZE,
+            PREVIEW_SIZE[1] as f32 * CELL_SIZE,
+        );
+        let (response, painter) = ui.allocate_painter(size, Sense::hover());
+        let rect = response.rect;
+        //y goes up in the world and down on the screen
+        let cell_rect = |[x, y]: [i32; 2]| {
+            egui::Rect::from_min_size(
+                egui::pos2(
+                    rect.left() + x as f32 * CELL_SIZE,
+                    rect.bottom() - (y + 1) as f32 * CELL_SIZE,
+                ),
+                egui::vec2(CELL_SIZE, CELL_SIZE),
+            )
+        };
+        painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+        (0..PREVIEW_SIZE[0])
+            .flat_map(|x| (0..PREVIEW_SIZE[1]).map(move |y| [x, y]))
+            .for_each(|pos| {
+                let tile = sim.get_tile(pos);
+                let cell = cell_rect(pos).shrink(1.0);
+                if tile != Tile::Empty {
+                    let [r, g, b, _] = tile_color(tile);
+                    painter.rect_filled(cell, 2.0, Color32::from_rgb(r, g, b));
+                } else {
+                    painter.rect_stroke(
+                        cell,
+                        2.0,
+                        Stroke::new(1.0, Color32::from_gray(40)),
+                        StrokeKind::Inside,
+                    );
+                }
+            });
+        sim.balls()
+            .filter(|(pos, _)| {
+                (0..PREVIEW_SIZE[0]).contains(&pos[0]) && (0..PREVIEW_SIZE[1]).contains(&pos[1])
+            })
+            .for_each(|(pos, (on, _))| {
+                let fill = if on {
+                    Color32::WHITE
+                } else {
+                    Color32::from_gray(70)
+                };
+                painter.circle(
+                    cell_rect(pos).center(),
+                    CELL_SIZE * 0.3,
+                    fill,
+                    Stroke::new(1.0, Color32::WHITE),
+                );
+            });
+        //naming the tile under the pointer, the colors alone don't say much
+        if let Some(pointer) = response.hover_pos() {
+            let x = ((pointer.x - rect.left()) / CELL_SIZE) as i32;
+            let y = ((rect.bottom() - pointer.y) / CELL_SIZE) as i32;
+            let tile = sim.get_tile([x, y]);
+            response.on_hover_text(format!("{} at [{x}, {y}]", tile.name()));
+        }
+        ui.label(format!(
+            "update {} of {PREVIEW_TICKS}, on balls are white",
+            self.preview_ticks
+        ));
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, rules: &Rules) {
+        ui.collapsing("updates", |ui| {
+            if rules.step_mode == StepMode::Synchronous {
+                ui.label(
+                    "this world updates synchronously: every ball is steered by the tile it \
+                     was on at the end of the last update and all of them move at once, \
+                     so the phase order doesn't matter",
+                );
+            } else {
+                ui.label(format!(
+                    "an update runs four phases, in this world's order {:?}. in each phase every \
+                     ball turns to the heading its tile gives it, and moves one cell if that \
+                     heading is the phase's direction. balls furthest along go first, so a line \
+                     of balls moves together, and a ball that moved sits out the rest of the \
+                     update",
+                    rules.phase_order
+                ));
+            }
+            ui.label(
+                "after the moves balls on diagonals and portals jump in position order, \
+                 pistons that are due push, pressure plates swap their neighbours, and balls \
+                 age and are capped by the world rules",
+            );
+        });
+        ui.collapsing("holds and collisions", |ui| {
+            ui.label(format!(
+                "a ball moving into a ball that stays put collides with it, in this world \
+                 by the {:?} rule",
+                rules.collision
+            ));
+            ui.label(
+                "a ball sitting on a hold is shoved instead: it moves first, one cell the same \
+                 way, then the ball behind takes its place. if the next cell holds another held \
+                 ball that one goes first, so a whole chain of holds moves at once when the cell \
+                 past the last one is free",
+            );
+            ui.label(
+                "when the end of a chain is blocked the held balls collide in turn from the \
+                 front, and a hold that failed isn't tried again for the rest of the phase",
+            );
+        });
+        ui.separator();
+        tile_combo_box(ui, "help tile", &mut self.tile);
+        TileInfo::of(self.tile).ui(ui, self.tile);
+        self.preview_ui(ui, rules);
+    }
+}
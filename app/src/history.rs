@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use engine::{
+    ball::Direction,
+    tile_data::TileData,
+    tiles::{Layer, Tile},
+};
+
+//what a set of cells held before an edit, enough to put them back
+#[derive(Debug, Clone, Default)]
+pub struct Edit {
+    pub description: String,
+    pub tiles: HashMap<(Layer, [i32; 2]), Tile>,
+    pub balls: HashMap<[i32; 2], Option<(bool, Direction)>>,
+    pub labels: HashMap<[i32; 2], Option<String>>,
+    pub tile_data: HashMap<[i32; 2], Option<TileData>>,
+}
+
+impl Edit {
+    fn is_empty(&self) -> bool {
+        self.cells() == 0
+    }
+
+    //how many things it changed, a cell with a tile and a ball counts twice
+    pub fn cells(&self) -> usize {
+        self.tiles.len() + self.balls.len() + self.labels.len() + self.tile_data.len()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    //the edit changes are recorded into, nothing is recorded without one
+    current: Option<Edit>,
+    //set when an edit is committed, undone or redone, until taken
+    changed: bool,
+}
+
+impl History {
+    pub fn begin(&mut self, description: impl Into<String>) {
+        self.commit();
+        self.current = Some(Edit {
+            description: description.into(),
+            ..Default::default()
+        });
+    }
+
+    pub fn commit(&mut self) {
+        if let Some(edit) = self.current.take().filter(|edit| !edit.is_empty()) {
+            self.undo.push(edit);
+            self.redo.clear();
+            self.changed = true;
+        }
+    }
+
+    //only the first value seen for a cell matters, later ones were made by this edit
+    pub fn record_tile(&mut self, layer: Layer, pos: [i32; 2], old: Tile) {
+        if let Some(edit) = &mut self.current {
+            edit.tiles.entry((layer, pos)).or_insert(old);
+        }
+    }
+
+    pub fn record_ball(&mut self, pos: [i32; 2], old: Option<(bool, Direction)>) {
+        if let Some(edit) = &mut self.current {
+            edit.balls.entry(pos).or_insert(old);
+        }
+    }
+
+    pub fn record_label(&mut self, pos: [i32; 2], old: Option<String>) {
+        if let Some(edit) = &mut self.current {
+            edit.labels.entry(pos).or_insert(old);
+        }
+    }
+
+    pub fn record_tile_data(&mut self, pos: [i32; 2], old: Option<TileData>) {
+        if let Some(edit) = &mut self.current {
+            edit.tile_data.entry(pos).or_insert(old);
+        }
+    }
+
+    //cells recorded into the current edit so far
+    pub fn pending(&self) -> usize {
+        self.current.as_ref().map_or(0, Edit::cells)
+    }
+
+    //oldest first, the last one is what undo takes back next
+    pub fn undo_list(&self) -> &[Edit] {
+        &self.undo
+    }
+
+    //the next one to redo last
+    pub fn redo_list(&self) -> &[Edit] {
+        &self.redo
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Edit> {
+        self.commit();
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Edit> {
+        self.commit();
+        self.redo.pop()
+    }
+
+    //the inverse of an undone edit, so it can be redone
+    pub fn push_redo(&mut self, edit: Edit) {
+        self.redo.push(edit);
+        self.changed = true;
+    }
+
+    //the inverse of a redone edit, unlike commit this keeps the redo stack
+    pub fn push_undo(&mut self, edit: Edit) {
+        self.undo.push(edit);
+        self.changed = true;
+    }
+
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
@@ -0,0 +1,403 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use engine::{ball::Direction, tiles::Tile};
+use image::RgbaImage;
+use shared::{
+    anyhow::{self, anyhow, bail},
+    egui::{Color32, ComboBox, Ui},
+};
+
+use crate::{
+    blueprint::Blueprint,
+    dialogs,
+    sim::tile_combo_box,
+    tile_image::{nearest_tile, ALPHA_THRESHOLD},
+};
+
+//keeps a typo in a header or a run count from allocating the whole memory
+const MAX_CELLS: i64 = 1 << 22;
+//more colors than this is a photo rather than a drawing
+const MAX_COLORS: usize = 256;
+
+//cell states of a pattern, state 0 being the background
+#[derive(Debug, Clone, Default)]
+pub struct Pattern {
+    pub size: [i32; 2],
+    //only the cells that aren't 0, with y flipped so the bottom row is 0
+    pub cells: BTreeMap<[i32; 2], u32>,
+    //the color each state had, only for images
+    pub colors: BTreeMap<u32, [u8; 4]>,
+}
+
+impl Pattern {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        if path.extension().is_some_and(|ext| ext == "png") {
+            return Self::from_image(&image::open(path)?.to_rgba8());
+        }
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("rle") => Self::from_rle(&text),
+            Some("csv" | "txt") => Self::from_csv(&text),
+            _ => bail!("{} isn't a .rle, .csv or .png file", path.display()),
+        }
+    }
+
+    //rows listed from the top like the source formats do
+    fn from_rows(rows: Vec<Vec<u32>>) -> anyhow::Result<Self> {
+        let height = rows.len() as i32;
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0) as i32;
+        if width as i64 * height as i64 > MAX_CELLS {
+            bail!("{width}x{height} is too big to import");
+        }
+        let cells = rows
+            .into_iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.into_iter()
+                    .enumerate()
+                    .filter(|(_, state)| *state != 0)
+                    .map(move |(x, state)| ([x as i32, height - 1 - y as i32], state))
+            })
+            .collect();
+        Ok(Self {
+            size: [width, height],
+            cells,
+            colors: BTreeMap::new(),
+        })
+    }
+
+    //each distinct color is a state numbered in the order they are first seen,
+    //transparent pixels are the background
+    pub fn from_image(image: &RgbaImage) -> anyhow::Result<Self> {
+        let mut colors: Vec<[u8; 4]> = vec![];
+        let rows = image
+            .rows()
+            .map(|row| {
+                row.map(|pixel| {
+                    if pixel.0[3] < ALPHA_THRESHOLD {
+                        return Ok(0);
+                    }
+                    let index = match colors.iter().position(|color| *color == pixel.0) {
+                        Some(index) => index,
+                        None if colors.len() < MAX_COLORS => {
+                            colors.push(pixel.0);
+                            colors.len() - 1
+                        }
+                        None => bail!("the image has more than {MAX_COLORS} colors"),
+                    };
+                    Ok(index as u32 + 1)
+                })
+                .collect()
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let mut pattern = Self::from_rows(rows)?;
+        pattern.colors = (1..).zip(colors).collect();
+        Ok(pattern)
+    }
+
+    //one row per line, states separated by commas
+    pub fn from_csv(text: &str) -> anyhow::Result<Self> {
+        let rows = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(y, line)| {
+                line.split(',')
+                    .map(|cell| {
+                        let cell = cell.trim();
+                        if cell.is_empty() {
+                            return Ok(0);
+                        }
+                        cell.parse()
+                            .map_err(|_| anyhow!("{cell:?} on line {} isn't a cell state", y + 1))
+                    })
+                    .collect()
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Self::from_rows(rows)
+    }
+
+    //golly's run length encoding, with the multi-state letters
+    pub fn from_rle(text: &str) -> anyhow::Result<Self> {
+        let mut rows = vec![vec![]];
+        //every cell read so far plus every row, empty rows still take room
+        let mut total = 1;
+        let mut count = String::new();
+        //the p to y prefix of states above 24
+        let mut prefix = None;
+        //the header is the only line with an =
+        let body = text
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.contains('='));
+        for c in body.flat_map(str::chars).filter(|c| !c.is_whitespace()) {
+            match c {
+                '0'..='9' => {
+                    count.push(c);
+                    continue;
+                }
+                '!' => break,
+                'p'..='y' => {
+                    prefix = Some(c as u32 - 'p' as u32 + 1);
+                    continue;
+                }
+                _ => {}
+            }
+            let run = match count.as_str() {
+                "" => 1,
+                count => count
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|run| *run <= MAX_CELLS)
+                    .ok_or_else(|| anyhow!("the run count {count} is too big to import"))?,
+            };
+            count.clear();
+            total += run;
+            if total > MAX_CELLS {
+                bail!("the pattern is too big to import");
+            }
+            let state = match c {
+                '$' => {
+                    rows.extend((0..run).map(|_| vec![]));
+                    continue;
+                }
+                'b' | '.' => 0,
+                'o' => 1,
+                'A'..='X' => prefix.take().unwrap_or(0) * 24 + c as u32 - 'A' as u32 + 1,
+                _ => bail!("unexpected {c:?} in the pattern"),
+            };
+            let row = rows.last_mut().expect("there is always a row");
+            row.extend(std::iter::repeat_n(state, run as usize));
+        }
+        //a trailing $ doesn't start a real row
+        while rows.len() > 1 && rows.last().is_some_and(Vec::is_empty) {
+            rows.pop();
+        }
+        Self::from_rows(rows)
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = u32> {
+        let mut states: Vec<u32> = self.cells.values().copied().collect();
+        states.sort();
+        states.dedup();
+        std::iter::once(0).chain(states)
+    }
+}
+
+//what one source state becomes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateMapping {
+    pub tile: Tile,
+    pub ball: Option<bool>,
+}
+
+impl StateMapping {
+    //live cells become blocks so still lifes come out as walls
+    fn default_for(state: u32) -> Self {
+        Self {
+            tile: if state == 0 { Tile::Empty } else { Tile::Block },
+            ball: None,
+        }
+    }
+}
+
+//the pattern file being imported and how its states turn into tiles
+#[derive(Debug, Clone, Default)]
+pub struct Importer {
+    pub path: String,
+    pattern: Option<Pattern>,
+    mapping: BTreeMap<u32, StateMapping>,
+    summary: String,
+}
+
+impl Importer {
+    //image colors start as the tile the pixel export gives the closest color
+    fn mapping(&self, state: u32) -> StateMapping {
+        let color = self
+            .pattern
+            .as_ref()
+            .and_then(|pattern| pattern.colors.get(&state));
+        self.mapping
+            .get(&state)
+            .copied()
+            .unwrap_or_else(|| match color {
+                Some(color) => StateMapping {
+                    tile: nearest_tile(*color),
+                    ball: None,
+                },
+                None => StateMapping::default_for(state),
+            })
+    }
+
+    //the whole rectangle is only filled in when the background state places something
+    fn blueprint(&self, name: &str, pattern: &Pattern) -> Blueprint {
+        let background = self.mapping(0);
+        let cells: Vec<([i32; 2], StateMapping)> =
+            if background.tile == Tile::Empty && background.ball.is_none() {
+                pattern
+                    .cells
+                    .iter()
+                    .map(|(pos, state)| (*pos, self.mapping(*state)))
+                    .collect()
+            } else {
+                (0..pattern.size[0])
+                    .flat_map(|x| (0..pattern.size[1]).map(move |y| [x, y]))
+                    .map(|pos| (pos, self.mapping(*pattern.cells.get(&pos).unwrap_or(&0))))
+                    .collect()
+            };
+        Blueprint {
+            name: name.to_owned(),
+            tiles: cells
+                .iter()
+                .filter(|(_, mapping)| mapping.tile != Tile::Empty)
+                .map(|(pos, mapping)| (*pos, mapping.tile))
+                .collect(),
+            balls: cells
+                .iter()
+                .filter_map(|(pos, mapping)| Some((*pos, (mapping.ball?, Direction::Right))))
+                .collect(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn read(&mut self) {
+        match Pattern::read(Path::new(&self.path)) {
+            Ok(pattern) => {
+                self.summary = format!(
+                    "{}x{} with {} live cells",
+                    pattern.size[0],
+                    pattern.size[1],
+                    pattern.cells.len()
+                );
+                //states mean something else in every file
+                self.mapping.clear();
+                self.pattern = Some(pattern);
+            }
+            Err(e) => {
+                self.summary = format!("couldn't read {}: {e}", self.path);
+                self.pattern = None;
+            }
+        }
+    }
+
+    //the pattern with the current mapping, named after its file
+    pub fn stamp(&self) -> Option<Blueprint> {
+        let name = Path::new(&self.path)
+            .file_stem()
+            .map_or("pattern".to_owned(), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+        self.pattern
+            .as_ref()
+            .map(|pattern| self.blueprint(&name, pattern))
+    }
+
+    //returns a blueprint to stamp once the user is happy with the mapping
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<Blueprint> {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.path);
+            let mut read = ui.button("read").clicked();
+            if ui.button("browse...").clicked() {
+                if let Some(path) = dialogs::open_pattern(&self.path) {
+                    self.path = path.to_string_lossy().into_owned();
+                    read = true;
+                }
+            }
+            if read {
+                self.read();
+            }
+        });
+        if !self.summary.is_empty() {
+            ui.label(&self.summary);
+        }
+        let pattern = self.pattern.as_ref()?;
+        let states: Vec<(u32, Option<[u8; 4]>)> = pattern
+            .states()
+            .map(|state| (state, pattern.colors.get(&state).copied()))
+            .collect();
+        states.into_iter().for_each(|(state, color)| {
+            let mut mapping = self.mapping(state);
+            ui.horizontal(|ui| {
+                ui.label(format!("state {state}"));
+                if let Some([r, g, b, a]) = color {
+                    ui.colored_label(Color32::from_rgba_unmultiplied(r, g, b, a), "■");
+                }
+                tile_combo_box(ui, &format!("state {state} tile"), &mut mapping.tile);
+                ComboBox::from_id_salt(format!("state {state} ball"))
+                    .selected_text(match mapping.ball {
+                        None => "no ball",
+                        Some(true) => "on ball",
+                        Some(false) => "off ball",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut mapping.ball, None, "no ball");
+                        ui.selectable_value(&mut mapping.ball, Some(true), "on ball");
+                        ui.selectable_value(&mut mapping.ball, Some(false), "off ball");
+                    });
+            });
+            if mapping != self.mapping(state) {
+                self.mapping.insert(state, mapping);
+            }
+        });
+        if !ui.button("stamp").clicked() {
+            return None;
+        }
+        self.stamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(pattern: &Pattern) -> Vec<([i32; 2], u32)> {
+        pattern
+            .cells
+            .iter()
+            .map(|(pos, state)| (*pos, *state))
+            .collect()
+    }
+
+    #[test]
+    fn rle_glider() {
+        let pattern =
+            Pattern::from_rle("#C a glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.size, [3, 3]);
+        assert_eq!(
+            cells(&pattern),
+            [
+                ([0, 0], 1),
+                ([1, 0], 1),
+                ([1, 2], 1),
+                ([2, 0], 1),
+                ([2, 1], 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn rle_states_and_rows() {
+        let pattern = Pattern::from_rle("2.A$pA2$!").unwrap();
+        assert_eq!(pattern.size, [3, 2]);
+        assert_eq!(cells(&pattern), [([0, 0], 25), ([2, 1], 1)]);
+        assert!(Pattern::from_rle("o?!").is_err());
+    }
+
+    #[test]
+    fn rle_counts_are_bounded() {
+        [
+            "2000000000$!",
+            "99999999999999999999o!",
+            "4000000o$4000000o!",
+        ]
+        .into_iter()
+        .for_each(|text| assert!(Pattern::from_rle(text).is_err(), "{text}"));
+    }
+
+    #[test]
+    fn csv() {
+        let pattern = Pattern::from_csv("0, 1\n\n2,\n").unwrap();
+        assert_eq!(pattern.size, [2, 2]);
+        assert_eq!(cells(&pattern), [([0, 0], 2), ([1, 1], 1)]);
+        assert!(Pattern::from_csv("1,x").is_err());
+    }
+}
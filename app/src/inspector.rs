@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+use engine::ball::Direction;
+use shared::egui::{ScrollArea, Ui};
+
+//moves kept in the log, older ones are dropped
+const LOG_LENGTH: usize = 100;
+
+//a ball picked with the inspect tool, tracked from cell to cell as it moves
+pub struct BallInspector {
+    pub pos: [i32; 2],
+    //cleared once the ball is destroyed, the last known state stays visible
+    pub alive: bool,
+    //keeps the camera gliding after the ball
+    pub follow: bool,
+    last_seen: (bool, Direction),
+    log: VecDeque<String>,
+}
+
+impl BallInspector {
+    pub fn new(pos: [i32; 2], ball: (bool, Direction)) -> Self {
+        Self {
+            pos,
+            alive: true,
+            follow: false,
+            last_seen: ball,
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, entry: String) {
+        if self.log.len() == LOG_LENGTH {
+            self.log.pop_front();
+        }
+        self.log.push_back(format!("tick {tick}: {entry}"));
+    }
+
+    pub fn moved(&mut self, tick: u64, to: [i32; 2]) {
+        let vertical = match (to[1] - self.pos[1]).signum() {
+            1 => "up ",
+            -1 => "down ",
+            _ => "",
+        };
+        let horizontal = match (to[0] - self.pos[0]).signum() {
+            1 => "right ",
+            -1 => "left ",
+            _ => "",
+        };
+        self.pos = to;
+        self.record(tick, format!("moved {vertical}{horizontal}to {to:?}"));
+    }
+
+    pub fn destroyed(&mut self, tick: u64) {
+        self.alive = false;
+        self.follow = false;
+        self.record(tick, format!("destroyed at {:?}", self.pos));
+    }
+
+    //returns false once the panel is closed
+    pub fn ui(&mut self, ui: &mut Ui, ball: Option<(bool, Direction)>, age: Option<u32>) -> bool {
+        if let Some(ball) = ball.filter(|_| self.alive) {
+            self.last_seen = ball;
+        }
+        let (on, dir) = self.last_seen;
+        ui.label(format!("position {:?}", self.pos));
+        ui.label(format!("state {}", if on { "on" } else { "off" }));
+        ui.label(format!("last direction {dir:?}"));
+        match age {
+            Some(age) => ui.label(format!("age {age}")),
+            None => ui
+                .label("age unknown")
+                .on_hover_text("ages are only kept with a ball lifetime or population cap"),
+        };
+        if !self.alive {
+            ui.label("destroyed");
+        }
+        let mut open = true;
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.alive, |ui| ui.checkbox(&mut self.follow, "follow"));
+            if ui.button("close").clicked() {
+                open = false;
+            }
+        });
+        ui.separator();
+        ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                self.log.iter().for_each(|entry| {
+                    ui.label(entry);
+                });
+            });
+        open
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shared::{
+    egui::{Color32, Grid, Ui},
+    winit::keyboard::KeyCode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    //held together with the left button to drag the camera
+    PanModifier,
+    PlaySim,
+    StepSim,
+    //ten full updates at once
+    StepSimTen,
+    //back to the world as it was before it was first stepped
+    ResetSim,
+    SlowerSim,
+    FasterSim,
+    FrameAll,
+    ToggleFullscreen,
+    //the frame time overlay
+    TogglePerfHud,
+    BallOnTool,
+    BallOffTool,
+    LabelTool,
+    //swaps between the last used ball and tile tools
+    ToggleToolKind,
+    PlaceSymmetryAxis,
+    Rotate,
+    //held with undo, redo and the command palette
+    CommandModifier,
+    Undo,
+    Redo,
+    CommandPalette,
+    //saves to the next of the rotating quicksave slots and loads the newest
+    QuickSave,
+    QuickLoad,
+    //tiles in palette order
+    Tile1,
+    Tile2,
+    Tile3,
+    Tile4,
+    Tile5,
+    Tile6,
+    Tile7,
+    Tile8,
+    Tile9,
+    Tile10,
+}
+
+impl Action {
+    pub const ALL: [Action; 32] = [
+        Action::PanModifier,
+        Action::PlaySim,
+        Action::StepSim,
+        Action::StepSimTen,
+        Action::ResetSim,
+        Action::SlowerSim,
+        Action::FasterSim,
+        Action::FrameAll,
+        Action::ToggleFullscreen,
+        Action::TogglePerfHud,
+        Action::BallOnTool,
+        Action::BallOffTool,
+        Action::LabelTool,
+        Action::ToggleToolKind,
+        Action::PlaceSymmetryAxis,
+        Action::Rotate,
+        Action::CommandModifier,
+        Action::Undo,
+        Action::Redo,
+        Action::CommandPalette,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::Tile1,
+        Action::Tile2,
+        Action::Tile3,
+        Action::Tile4,
+        Action::Tile5,
+        Action::Tile6,
+        Action::Tile7,
+        Action::Tile8,
+        Action::Tile9,
+        Action::Tile10,
+    ];
+
+    pub const TILE_SLOTS: [Action; 10] = [
+        Action::Tile1,
+        Action::Tile2,
+        Action::Tile3,
+        Action::Tile4,
+        Action::Tile5,
+        Action::Tile6,
+        Action::Tile7,
+        Action::Tile8,
+        Action::Tile9,
+        Action::Tile10,
+    ];
+
+    pub fn default_key(&self) -> KeyCode {
+        match self {
+            Action::PanModifier => KeyCode::ShiftLeft,
+            Action::PlaySim => KeyCode::Space,
+            Action::StepSim => KeyCode::Period,
+            Action::StepSimTen => KeyCode::Slash,
+            Action::ResetSim => KeyCode::Backspace,
+            Action::SlowerSim => KeyCode::BracketLeft,
+            Action::FasterSim => KeyCode::BracketRight,
+            Action::FrameAll => KeyCode::Home,
+            Action::ToggleFullscreen => KeyCode::F11,
+            Action::TogglePerfHud => KeyCode::F3,
+            Action::BallOnTool => KeyCode::KeyB,
+            Action::BallOffTool => KeyCode::KeyN,
+            Action::LabelTool => KeyCode::KeyT,
+            Action::ToggleToolKind => KeyCode::Tab,
+            Action::PlaceSymmetryAxis => KeyCode::KeyM,
+            Action::Rotate => KeyCode::KeyR,
+            Action::CommandModifier => KeyCode::ControlLeft,
+            Action::Undo => KeyCode::KeyZ,
+            Action::Redo => KeyCode::KeyY,
+            Action::CommandPalette => KeyCode::KeyP,
+            Action::QuickSave => KeyCode::F5,
+            Action::QuickLoad => KeyCode::F9,
+            Action::Tile1 => KeyCode::Digit1,
+            Action::Tile2 => KeyCode::Digit2,
+            Action::Tile3 => KeyCode::Digit3,
+            Action::Tile4 => KeyCode::Digit4,
+            Action::Tile5 => KeyCode::Digit5,
+            Action::Tile6 => KeyCode::Digit6,
+            Action::Tile7 => KeyCode::Digit7,
+            Action::Tile8 => KeyCode::Digit8,
+            Action::Tile9 => KeyCode::Digit9,
+            Action::Tile10 => KeyCode::Digit0,
+        }
+    }
+}
+
+//actions missing from the config file fall back to their default key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keybinds {
+    bindings: BTreeMap<Action, KeyCode>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_key()))
+                .collect(),
+        }
+    }
+}
+
+impl Keybinds {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or(action.default_key())
+    }
+
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        self.bindings.insert(action, key);
+    }
+
+    //other actions bound to the same key
+    fn conflicts(&self, action: Action) -> impl Iterator<Item = Action> + '_ {
+        let key = self.key(action);
+        Action::ALL
+            .into_iter()
+            .filter(move |other| *other != action && self.key(*other) == key)
+    }
+
+    //`rebinding` is the action waiting for its next key press, which the app feeds back through `set`
+    pub fn ui(&mut self, ui: &mut Ui, rebinding: &mut Option<Action>) {
+        Grid::new("keybinds").show(ui, |ui| {
+            Action::ALL.iter().for_each(|action| {
+                ui.label(format!("{action:?}"));
+                let text = if *rebinding == Some(*action) {
+                    "press a key...".to_owned()
+                } else {
+                    format!("{:?}", self.key(*action))
+                };
+                if ui.button(text).clicked() {
+                    *rebinding = Some(*action);
+                }
+                let conflicts: Vec<String> = self
+                    .conflicts(*action)
+                    .map(|other| format!("{other:?}"))
+                    .collect();
+                if !conflicts.is_empty() {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        format!("also bound to {}", conflicts.join(", ")),
+                    );
+                }
+                ui.end_row();
+            });
+        });
+        if ui.button("reset to defaults").clicked() {
+            *self = Self::default();
+            *rebinding = None;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use app::App;
+use shared::{anyhow, winit::event_loop::EventLoop};
+use sim::Simulation;
+use tutorial::Tutorial;
+
+mod analysis;
+mod app;
+mod audio;
+mod blueprint;
+mod blueprint_browser;
+mod console;
+mod dialogs;
+mod generators;
+mod help;
+mod history;
+mod import;
+mod inspector;
+mod keybinds;
+mod log_viewer;
+mod macros;
+mod midi;
+mod online;
+mod perf;
+mod remote;
+mod rest;
+mod rules;
+mod settings;
+pub mod sim;
+mod spectate;
+pub mod stats;
+mod templates;
+mod tile_data;
+mod tile_image;
+mod tile_info;
+mod tutorial;
+mod world;
+mod world_browser;
+pub const LINE_HEIGHT: f32 = 1.;
+//scroll level change that halves or doubles the zoom
+pub const SCROLL_SPEED: f32 = 5.0;
+
+pub fn run() -> anyhow::Result<()> {
+    log_viewer::init();
+    let event_loop = EventLoop::with_user_event().build()?;
+    let mut app = App::new(None);
+    let sim = Simulation::new(app.get_mouse_position_world());
+    if app.settings().tutorial_done {
+        app.set_update_loop(Box::new(sim));
+    } else {
+        let tutorial = Tutorial::new(&app, sim);
+        app.set_update_loop(Box::new(tutorial));
+    }
+    event_loop.run_app(&mut app)?;
+
+    Ok(())
+}
+
+//the simulation without a window, driven through http
+pub fn serve(port: u16, world: Option<&Path>) -> anyhow::Result<()> {
+    log_viewer::init();
+    rest::serve(port, world)
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use shared::{
+    egui::{self, Color32, ComboBox, TextEdit, Ui},
+    env_logger,
+    log::{self, Level, LevelFilter, Log, Metadata, Record},
+};
+
+//records kept for the viewer, the oldest are dropped first
+const LOG_CAPACITY: usize = 1000;
+//kept whatever RUST_LOG says, so problems show up without a terminal
+const CAPTURE_LEVEL: LevelFilter = LevelFilter::Info;
+
+pub struct LogEntry {
+    //seconds since the logger was set up
+    pub time: f32,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+static ENTRIES: OnceLock<Mutex<VecDeque<LogEntry>>> = OnceLock::new();
+
+fn entries() -> &'static Mutex<VecDeque<LogEntry>> {
+    ENTRIES.get_or_init(|| Mutex::new(VecDeque::with_capacity(LOG_CAPACITY)))
+}
+
+//env_logger still prints to the terminal as before, a copy of each record goes to the viewer
+struct CaptureLogger {
+    inner: env_logger::Logger,
+    start: Instant,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= CAPTURE_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.inner.log(record);
+        if record.level() > CAPTURE_LEVEL {
+            return;
+        }
+        let Ok(mut entries) = entries().lock() else {
+            return;
+        };
+        if entries.len() == LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(LogEntry {
+            time: self.start.elapsed().as_secs_f32(),
+            level: record.level(),
+            target: record.target().to_owned(),
+            //one line each so the viewer can scroll by rows
+            message: record.args().to_string().replace('\n', " | "),
+        });
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+//in place of env_logger::init
+pub fn init() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter().max(CAPTURE_LEVEL);
+    let logger = CaptureLogger {
+        inner,
+        start: Instant::now(),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::Error => Color32::from_rgb(255, 90, 90),
+        Level::Warn => Color32::from_rgb(255, 200, 80),
+        Level::Info => Color32::LIGHT_GRAY,
+        Level::Debug | Level::Trace => Color32::GRAY,
+    }
+}
+
+pub struct LogViewer {
+    level: LevelFilter,
+    search: String,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            search: String::new(),
+        }
+    }
+}
+
+impl LogViewer {
+    //errors logged so far, for the window title
+    pub fn errors(&self) -> usize {
+        entries().lock().map_or(0, |entries| {
+            entries
+                .iter()
+                .filter(|entry| entry.level == Level::Error)
+                .count()
+        })
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("log level")
+                .selected_text(format!("{}", self.level))
+                .show_ui(ui, |ui| {
+                    [LevelFilter::Error, LevelFilter::Warn, LevelFilter::Info]
+                        .into_iter()
+                        .for_each(|level| {
+                            ui.selectable_value(&mut self.level, level, format!("{level}"));
+                        });
+                });
+            ui.add(TextEdit::singleline(&mut self.search).hint_text("search"));
+            if ui.button("clear").clicked() {
+                if let Ok(mut entries) = entries().lock() {
+                    entries.clear();
+                }
+            }
+        });
+        let search = self.search.to_lowercase();
+        //formatted before drawing so nothing logged while drawing waits on the lock
+        let shown: Vec<(Level, String)> = match entries().lock() {
+            Ok(entries) => entries
+                .iter()
+                .filter(|entry| entry.level <= self.level)
+                .filter(|entry| {
+                    search.is_empty()
+                        || entry.message.to_lowercase().contains(&search)
+                        || entry.target.to_lowercase().contains(&search)
+                })
+                .map(|entry| {
+                    let line = format!(
+                        "{:9.3} {:5} {}: {}",
+                        entry.time, entry.level, entry.target, entry.message
+                    );
+                    (entry.level, line)
+                })
+                .collect(),
+            Err(_) => {
+                ui.label("the log is unavailable");
+                return;
+            }
+        };
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                shown[rows].iter().for_each(|(level, line)| {
+                    ui.colored_label(level_color(*level), egui::RichText::new(line).monospace());
+                });
+            });
+    }
+}
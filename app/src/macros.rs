@@ -0,0 +1,34 @@
+use engine::{
+    ball::Direction,
+    tiles::{Layer, Tile},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroOp {
+    SetTile(Layer, Tile),
+    SetBall((bool, Direction)),
+    RemoveBall,
+    SetLabel(String),
+}
+
+//a recorded sequence of edits that can be replayed anywhere
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditMacro {
+    pub name: String,
+    //positions are relative to the first edited cell
+    pub ops: Vec<([i32; 2], MacroOp)>,
+}
+
+impl EditMacro {
+    //ops are recorded with world positions and made relative once recording stops
+    pub fn from_recording(name: String, recording: Vec<([i32; 2], MacroOp)>) -> Option<Self> {
+        let anchor = recording.first()?.0;
+        Some(Self {
+            name,
+            ops: recording
+                .into_iter()
+                .map(|(pos, op)| ([pos[0] - anchor[0], pos[1] - anchor[1]], op))
+                .collect(),
+        })
+    }
+}
@@ -1,25 +1,14 @@
-use std::env;
-
-use app::App;
-use shared::{anyhow, env_logger, winit::event_loop::EventLoop};
-use sim::Simulation;
-
-mod app;
-mod tiles;
-mod sim;
-pub const LINE_HEIGHT: f32 = 1.;
+use std::{env, path::Path};
 
 fn main() {
     env::set_var("RUST_BACKTRACE", "1");
-    run().unwrap()
-}
-
-pub fn run() -> anyhow::Result<()> {
-    env_logger::init();
-    let event_loop = EventLoop::with_user_event().build()?;
-    let mut app = App::new(None);
-    app.set_update_loop(Box::new(Simulation::new(app.get_mouse_position_world())));
-    event_loop.run_app(&mut app)?;
-
-    Ok(())
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        //serve <port> [world]
+        [command, port, world @ ..] if command == "serve" => {
+            let port = port.parse().expect("the port should be a number");
+            app::serve(port, world.first().map(Path::new)).unwrap()
+        }
+        _ => app::run().unwrap(),
+    }
 }
@@ -0,0 +1,130 @@
+#[cfg(feature = "midi")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "midi")]
+use shared::log;
+
+//how long after a note on its note off is sent
+#[cfg(feature = "midi")]
+const NOTE_LENGTH: Duration = Duration::from_millis(150);
+#[cfg(feature = "midi")]
+const CLIENT_NAME: &str = "ball_sim";
+
+//names of the ports notes can be sent to, empty without the `midi` feature
+#[cfg(feature = "midi")]
+pub fn port_names() -> Vec<String> {
+    let Ok(output) = midir::MidiOutput::new(CLIENT_NAME) else {
+        return vec![];
+    };
+    output
+        .ports()
+        .iter()
+        .filter_map(|port| output.port_name(port).ok())
+        .collect()
+}
+
+#[cfg(not(feature = "midi"))]
+pub fn port_names() -> Vec<String> {
+    vec![]
+}
+
+//note tiles sent to an external synth, does nothing until connected to a port
+#[derive(Default)]
+pub struct Midi {
+    #[cfg(feature = "midi")]
+    connection: Option<(String, midir::MidiOutputConnection)>,
+    //channel and pitch of notes that still need their note off
+    #[cfg(feature = "midi")]
+    sounding: Vec<([u8; 2], Instant)>,
+    //last program sent on each channel, so it is only changed when a tile asks for another
+    #[cfg(feature = "midi")]
+    programs: [Option<u8>; 16],
+}
+
+impl Midi {
+    //keeps the current connection if it is already to `port`
+    #[cfg(feature = "midi")]
+    pub fn connect(&mut self, port: Option<&str>) {
+        if self.connection.as_ref().map(|(name, _)| name.as_str()) == port {
+            return;
+        }
+        self.release(true);
+        if let Some((_, connection)) = self.connection.take() {
+            connection.close();
+        }
+        self.programs = [None; 16];
+        let Some(port) = port else {
+            return;
+        };
+        let connect = || -> Result<_, String> {
+            let output = midir::MidiOutput::new(CLIENT_NAME).map_err(|e| e.to_string())?;
+            let found = output
+                .ports()
+                .into_iter()
+                .find(|p| output.port_name(p).is_ok_and(|name| name == port))
+                .ok_or("no port with that name")?;
+            output.connect(&found, "notes").map_err(|e| e.to_string())
+        };
+        match connect() {
+            Ok(connection) => self.connection = Some((port.to_owned(), connection)),
+            Err(e) => log::warn!("couldn't open midi port {port}: {e}"),
+        }
+    }
+
+    #[cfg(not(feature = "midi"))]
+    pub fn connect(&mut self, _port: Option<&str>) {}
+
+    pub fn connected(&self) -> bool {
+        #[cfg(feature = "midi")]
+        return self.connection.is_some();
+        #[cfg(not(feature = "midi"))]
+        false
+    }
+
+    //`channel` counts from 1 like synths show it
+    #[cfg(feature = "midi")]
+    pub fn play(&mut self, channel: u8, program: u8, pitch: u8, velocity: u8) {
+        let Some((_, connection)) = &mut self.connection else {
+            return;
+        };
+        let channel = channel.clamp(1, 16) - 1;
+        let mut messages = vec![];
+        if self.programs[channel as usize] != Some(program) {
+            self.programs[channel as usize] = Some(program);
+            messages.push(vec![0xC0 | channel, program & 0x7F]);
+        }
+        messages.push(vec![0x90 | channel, pitch & 0x7F, velocity.clamp(1, 127)]);
+        if let Err(e) = messages
+            .iter()
+            .try_for_each(|message| connection.send(message))
+        {
+            log::warn!("failed to send midi note: {e}");
+            return;
+        }
+        self.sounding.push(([channel, pitch], Instant::now()));
+    }
+
+    #[cfg(not(feature = "midi"))]
+    pub fn play(&mut self, _channel: u8, _program: u8, _pitch: u8, _velocity: u8) {}
+
+    //sends the note offs that are due, or all of them when `all` is set
+    #[cfg(feature = "midi")]
+    pub fn release(&mut self, all: bool) {
+        let Some((_, connection)) = &mut self.connection else {
+            self.sounding.clear();
+            return;
+        };
+        self.sounding.retain(|([channel, pitch], start)| {
+            if !all && start.elapsed() < NOTE_LENGTH {
+                return true;
+            }
+            if let Err(e) = connection.send(&[0x80 | channel, *pitch, 0]) {
+                log::warn!("failed to send midi note off: {e}");
+            }
+            false
+        });
+    }
+
+    #[cfg(not(feature = "midi"))]
+    pub fn release(&mut self, _all: bool) {}
+}
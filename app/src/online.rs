@@ -0,0 +1,235 @@
+//sharing worlds through a level server, which answers
+//GET {server}/levels with a json list of levels,
+//GET {server}/levels/{id} with the world file
+//and takes new worlds as the body of POST {server}/levels?name=..&author=..
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use engine::save::WorldSave;
+use serde::Deserialize;
+use shared::{
+    anyhow,
+    egui::{Button, Grid, Ui},
+};
+
+use crate::{world::WORLD_DIR, world_browser::free_world_path};
+
+//a level bigger than this is more likely a broken server than a machine
+#[cfg(feature = "online")]
+const MAX_LEVEL_BYTES: u64 = 16 << 20;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelInfo {
+    pub id: String,
+    pub name: String,
+    pub author: String,
+}
+
+enum Reply {
+    Levels(Vec<LevelInfo>),
+    //where the downloaded world was saved
+    Downloaded(PathBuf),
+    Uploaded(String),
+}
+
+pub enum OnlineAction {
+    Open(PathBuf),
+    //upload the current world under this name
+    Upload(String),
+}
+
+#[cfg(feature = "online")]
+fn fetch_levels(server: &str) -> anyhow::Result<Vec<LevelInfo>> {
+    Ok(ureq::get(&format!("{server}/levels")).call()?.into_json()?)
+}
+
+#[cfg(feature = "online")]
+fn fetch_level(server: &str, id: &str) -> anyhow::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut bytes = vec![];
+    ureq::get(&format!("{server}/levels/{id}"))
+        .call()?
+        .into_reader()
+        .take(MAX_LEVEL_BYTES)
+        .read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "online")]
+fn send_level(server: &str, name: &str, author: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    ureq::post(&format!("{server}/levels"))
+        .query("name", name)
+        .query("author", author)
+        .send_bytes(bytes)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "online"))]
+fn fetch_levels(_server: &str) -> anyhow::Result<Vec<LevelInfo>> {
+    anyhow::bail!("built without the online feature")
+}
+
+#[cfg(not(feature = "online"))]
+fn fetch_level(_server: &str, _id: &str) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("built without the online feature")
+}
+
+#[cfg(not(feature = "online"))]
+fn send_level(_server: &str, _name: &str, _author: &str, _bytes: &[u8]) -> anyhow::Result<()> {
+    anyhow::bail!("built without the online feature")
+}
+
+//names come from other people, so they can't be allowed to point outside the worlds directory
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || " -_".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim() {
+        "" => "level".to_owned(),
+        name => name.to_owned(),
+    }
+}
+
+//the list of community levels, requests run on a thread so a slow server doesn't freeze the window
+#[derive(Default)]
+pub struct LevelClient {
+    waiting: Option<Receiver<anyhow::Result<Reply>>>,
+    levels: Vec<LevelInfo>,
+    filter: String,
+    upload_name: String,
+    message: String,
+}
+
+impl LevelClient {
+    fn start(&mut self, request: impl FnOnce() -> anyhow::Result<Reply> + Send + 'static) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            //nobody is listening anymore if the app closed in the meantime
+            let _ = sender.send(request());
+        });
+        self.waiting = Some(receiver);
+        self.message = "waiting for the server...".to_owned();
+    }
+
+    pub fn refresh(&mut self, server: &str) {
+        let server = server.to_owned();
+        self.start(move || Ok(Reply::Levels(fetch_levels(&server)?)));
+    }
+
+    //downloads are checked and saved with the other worlds before being opened,
+    //under a name of their own so a level never replaces a world already there
+    fn download(&mut self, server: &str, level: &LevelInfo) {
+        let (server, level) = (server.to_owned(), level.clone());
+        self.start(move || {
+            let bytes = fetch_level(&server, &level.id)?;
+            WorldSave::from_bytes(bytes.clone())?;
+            fs::create_dir_all(WORLD_DIR)?;
+            let path = free_world_path(&file_name(&level.name));
+            File::create_new(&path)?.write_all(&bytes)?;
+            Ok(Reply::Downloaded(path))
+        });
+    }
+
+    pub fn upload(&mut self, server: &str, name: &str, author: &str, bytes: Vec<u8>) {
+        let (server, name, author) = (server.to_owned(), name.to_owned(), author.to_owned());
+        self.start(move || {
+            send_level(&server, &name, &author, &bytes)?;
+            Ok(Reply::Uploaded(name))
+        });
+    }
+
+    //picks up the reply once the request thread is done
+    fn poll(&mut self) -> Option<OnlineAction> {
+        let reply = match self.waiting.as_ref()?.try_recv() {
+            Ok(reply) => reply,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(anyhow::anyhow!("the request thread died")),
+        };
+        self.waiting = None;
+        match reply {
+            Ok(Reply::Levels(levels)) => {
+                self.message = format!("{} levels", levels.len());
+                self.levels = levels;
+                None
+            }
+            Ok(Reply::Downloaded(path)) => {
+                self.message = format!("saved to {}", path.display());
+                Some(OnlineAction::Open(path))
+            }
+            Ok(Reply::Uploaded(name)) => {
+                self.message = format!("uploaded {name}");
+                None
+            }
+            Err(e) => {
+                self.message = format!("failed: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, server: &str) -> Option<OnlineAction> {
+        let mut action = self.poll();
+        if self.waiting.is_some() {
+            //nothing else wakes the window up when the reply arrives
+            ui.ctx().request_repaint();
+        }
+        let idle = self.waiting.is_none();
+        ui.horizontal(|ui| {
+            ui.label(server);
+            if ui.add_enabled(idle, Button::new("refresh")).clicked() {
+                self.refresh(server);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("filter");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        let filter = self.filter.to_lowercase();
+        let levels: Vec<LevelInfo> = self
+            .levels
+            .iter()
+            .filter(|level| {
+                level.name.to_lowercase().contains(&filter)
+                    || level.author.to_lowercase().contains(&filter)
+            })
+            .cloned()
+            .collect();
+        Grid::new("levels").striped(true).show(ui, |ui| {
+            levels.iter().for_each(|level| {
+                ui.label(&level.name);
+                ui.label(format!("by {}", level.author));
+                if ui.add_enabled(idle, Button::new("download")).clicked() {
+                    self.download(server, level);
+                }
+                ui.end_row();
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.upload_name);
+            let enabled = idle && !self.upload_name.trim().is_empty();
+            if ui
+                .add_enabled(enabled, Button::new("upload this world"))
+                .clicked()
+            {
+                action = Some(OnlineAction::Upload(self.upload_name.trim().to_owned()));
+            }
+        });
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+        action
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use shared::egui::{self, Align2, Color32, Context, Sense, Stroke, Ui};
+
+//frames the hud averages over and graphs
+const FRAME_HISTORY: usize = 120;
+//frames the profiler window merges its scopes over
+#[cfg(feature = "puffin")]
+const PROFILED_FRAMES: usize = 30;
+
+//a compact frame time overlay, and with the `puffin` feature a window with where the time went
+pub struct PerfHud {
+    pub visible: bool,
+    frame_times: VecDeque<Duration>,
+    last_frame: Instant,
+    //None when the adapter can't measure gpu time
+    gpu_timings: Option<Vec<(&'static str, Duration)>>,
+    #[cfg(feature = "puffin")]
+    view: puffin::GlobalFrameView,
+}
+
+impl Default for PerfHud {
+    fn default() -> Self {
+        //building with the feature is the opt in, so scopes are recorded from the start
+        #[cfg(feature = "puffin")]
+        puffin::set_scopes_on(true);
+        Self {
+            visible: false,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            last_frame: Instant::now(),
+            gpu_timings: None,
+            #[cfg(feature = "puffin")]
+            view: puffin::GlobalFrameView::default(),
+        }
+    }
+}
+
+impl PerfHud {
+    //called once per rendered frame
+    pub fn frame(&mut self) {
+        profiling::finish_frame!();
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(self.last_frame.elapsed());
+        self.last_frame = Instant::now();
+    }
+
+    pub fn set_gpu_timings(&mut self, timings: Option<Vec<(&'static str, Duration)>>) {
+        self.gpu_timings = timings;
+    }
+
+    fn graph(&self, ui: &mut Ui) {
+        //frame time at the top of the graph, two frames at 60 fps
+        const GRAPH_MAX: f32 = 1000.0 / 30.0;
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(FRAME_HISTORY as f32, 30.0), Sense::hover());
+        let rect = response.rect;
+        //a frame at 60 fps
+        let target = rect.bottom() - rect.height() * (1000.0 / 60.0) / GRAPH_MAX;
+        painter.hline(rect.x_range(), target, Stroke::new(1.0, Color32::DARK_GRAY));
+        self.frame_times.iter().enumerate().for_each(|(i, time)| {
+            let ms = time.as_secs_f32() * 1000.0;
+            let x = rect.left() + i as f32;
+            let top = rect.bottom() - rect.height() * (ms / GRAPH_MAX).min(1.0);
+            let color = if ms > 1000.0 / 30.0 {
+                Color32::RED
+            } else if ms > 1000.0 / 60.0 {
+                Color32::YELLOW
+            } else {
+                Color32::GREEN
+            };
+            painter.vline(x, top..=rect.bottom(), Stroke::new(1.0, color));
+        });
+    }
+
+    pub fn ui(&mut self, ctx: &Context) {
+        if self.visible && !self.frame_times.is_empty() {
+            let total: Duration = self.frame_times.iter().sum();
+            let average = total / self.frame_times.len() as u32;
+            let worst = self.frame_times.iter().max().copied().unwrap_or_default();
+            egui::Area::new(egui::Id::new("perf hud"))
+                .anchor(Align2::RIGHT_TOP, [-8.0, 8.0])
+                .interactable(false)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(format!(
+                            "{:.0} fps, {:.2} ms avg, {:.2} ms worst",
+                            1.0 / average.as_secs_f32(),
+                            average.as_secs_f64() * 1000.0,
+                            worst.as_secs_f64() * 1000.0
+                        ));
+                        self.graph(ui);
+                        match &self.gpu_timings {
+                            Some(timings) if !timings.is_empty() => {
+                                let total: Duration = timings.iter().map(|(_, time)| *time).sum();
+                                let parts: Vec<String> = timings
+                                    .iter()
+                                    .map(|(name, time)| {
+                                        format!("{name} {:.2}", time.as_secs_f64() * 1000.0)
+                                    })
+                                    .collect();
+                                ui.label(format!(
+                                    "gpu {:.2} ms: {}",
+                                    total.as_secs_f64() * 1000.0,
+                                    parts.join(", ")
+                                ));
+                            }
+                            Some(_) => {
+                                ui.label("gpu timings pending");
+                            }
+                            None => {
+                                ui.label("no gpu timestamps on this adapter");
+                            }
+                        }
+                    });
+                });
+        }
+        #[cfg(feature = "puffin")]
+        egui::Window::new("profiler")
+            .default_open(false)
+            .show(ctx, |ui| self.profiler_ui(ui));
+    }
+
+    #[cfg(feature = "puffin")]
+    fn profiler_ui(&self, ui: &mut Ui) {
+        let view = self.view.lock();
+        let frames: Vec<_> = view
+            .latest_frames(PROFILED_FRAMES)
+            .filter_map(|frame| frame.unpacked().ok())
+            .collect();
+        let Some(last) = frames.last() else {
+            ui.label("no frames recorded yet");
+            return;
+        };
+        ui.label(format!("averaged over the last {} frames", frames.len()));
+        let threads: Vec<_> = last.thread_streams.keys().cloned().collect();
+        threads.iter().for_each(|thread| {
+            match puffin::merge_scopes_for_thread(view.scope_collection(), &frames, thread) {
+                Ok(scopes) => {
+                    ui.collapsing(&thread.name, |ui| {
+                        scopes
+                            .iter()
+                            .for_each(|scope| scope_ui(ui, view.scope_collection(), scope));
+                    });
+                }
+                Err(e) => {
+                    ui.label(format!("couldn't read {}: {e:?}", thread.name));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(feature = "puffin")]
+fn scope_ui(ui: &mut Ui, scopes: &puffin::ScopeCollection, scope: &puffin::MergeScope<'_>) {
+    let name = scopes
+        .fetch_by_id(&scope.id)
+        .map(|details| details.name().to_string())
+        .unwrap_or_else(|| "unknown".to_owned());
+    let text = format!(
+        "{name} {:.2} ms/frame, {}x, {:.2} ms max",
+        scope.duration_per_frame_ns as f64 / 1e6,
+        scope.num_pieces,
+        scope.max_duration_ns as f64 / 1e6
+    );
+    if scope.children.is_empty() {
+        ui.label(text);
+    } else {
+        egui::CollapsingHeader::new(text)
+            .id_salt(scope.id)
+            .show(ui, |ui| {
+                scope
+                    .children
+                    .iter()
+                    .for_each(|child| scope_ui(ui, scopes, child));
+            });
+    }
+}
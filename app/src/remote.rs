@@ -0,0 +1,203 @@
+//lets other programs drive the app over tcp, one json command per line answered by one json line,
+//either {"ok": ...} or {"error": "..."}, for example
+//{"command": "set_tile", "pos": [0, 0], "tile": "Block"}
+//{"command": "step", "count": 10}
+//{"command": "save", "path": "machine.world"}
+//a line that isn't json ends the connection, so a web page posting to the port gets nowhere
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use engine::{ball::Direction, tiles::Tile};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use shared::{
+    anyhow::{self, bail},
+    log,
+};
+
+use crate::world::{WORLD_DIR, WORLD_EXTENSION};
+
+//how often the listener checks whether it was stopped
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+fn default_count() -> u32 {
+    1
+}
+
+fn default_direction() -> Direction {
+    Direction::Right
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    SetTile {
+        pos: [i32; 2],
+        tile: Tile,
+    },
+    SetBall {
+        pos: [i32; 2],
+        on: bool,
+        #[serde(default = "default_direction")]
+        direction: Direction,
+    },
+    RemoveBall {
+        pos: [i32; 2],
+    },
+    Step {
+        #[serde(default = "default_count")]
+        count: u32,
+    },
+    //the tiles and balls between two corners, inclusive
+    Region {
+        min: [i32; 2],
+        max: [i32; 2],
+    },
+    Stats,
+    //paths are relative to the worlds directory
+    Load {
+        path: String,
+    },
+    Save {
+        path: String,
+    },
+}
+
+//a world file inside `WORLD_DIR`, anything else could read or overwrite any file the user can
+pub fn world_file(path: &str) -> anyhow::Result<PathBuf> {
+    let path = Path::new(path);
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        bail!("world paths are relative to {WORLD_DIR}/ and can't leave it");
+    }
+    if path.extension().is_none_or(|ext| ext != WORLD_EXTENSION) {
+        bail!("world paths end in .{WORLD_EXTENSION}");
+    }
+    Ok(Path::new(WORLD_DIR).join(path))
+}
+
+//commands waiting for the simulation, each with where its answer goes
+pub type Request = (Command, Sender<anyhow::Result<Value>>);
+
+//only listens on localhost, anything else would let the whole network edit files
+pub struct RemoteServer {
+    port: u16,
+    requests: Receiver<Request>,
+    running: Arc<AtomicBool>,
+}
+
+impl RemoteServer {
+    pub fn start(port: u16) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        let (sender, requests) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let still_running = running.clone();
+        thread::spawn(move || {
+            while still_running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, address)) => {
+                        log::info!("remote control connection from {address}");
+                        let sender = sender.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = serve(stream, sender) {
+                                log::warn!("remote control connection closed: {e}");
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL);
+                    }
+                    Err(e) => log::warn!("remote control couldn't accept a connection: {e}"),
+                }
+            }
+        });
+        log::info!("remote control listening on port {port}");
+        Ok(Self {
+            port,
+            requests,
+            running,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    //the commands that arrived since the last call
+    pub fn requests(&self) -> impl Iterator<Item = Request> + '_ {
+        self.requests.try_iter()
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn serve(stream: TcpStream, sender: Sender<Request>) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = match serde_json::from_str(&line) {
+            Ok(value) => value,
+            Err(e) => {
+                writeln!(writer, "{}", json!({ "error": e.to_string() }))?;
+                bail!("not a json line: {e}");
+            }
+        };
+        let result = match serde_json::from_value(value) {
+            Ok(command) => {
+                let (reply, answer) = mpsc::channel();
+                sender.send((command, reply))?;
+                answer.recv()?
+            }
+            Err(e) => Err(e.into()),
+        };
+        let reply = match result {
+            Ok(value) => json!({ "ok": value }),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+        writeln!(writer, "{reply}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_files_stay_in_the_worlds_directory() {
+        assert_eq!(
+            world_file("machines/adder.world").unwrap(),
+            Path::new(WORLD_DIR).join("machines/adder.world")
+        );
+        [
+            "../adder.world",
+            "/tmp/adder.world",
+            "./adder.world",
+            "adder.txt",
+            "adder",
+        ]
+        .into_iter()
+        .for_each(|path| assert!(world_file(path).is_err(), "{path}"));
+    }
+}
@@ -0,0 +1,99 @@
+//a simulation without a window behind a small http api, for dashboards and ci
+//POST /world        the body is a world file, replaces the simulation
+//POST /step?count=N runs N updates and answers with the stats
+//GET  /stats
+//GET  /region?min_x=..&min_y=..&max_x=..&max_y=..
+use std::{collections::HashMap, io::Read, net::Ipv4Addr, path::Path};
+
+use serde_json::{json, Value};
+use shared::{
+    anyhow::{self, anyhow, bail},
+    log,
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::sim::Simulation;
+
+//the server answers one request at a time, so a huge count would block everyone else
+const MAX_STEPS: u32 = 100_000;
+//a world bigger than this is more likely a mistake than a machine
+const MAX_WORLD_BYTES: u64 = 64 << 20;
+
+fn query(url: &str) -> HashMap<&str, &str> {
+    url.split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect()
+}
+
+fn number<T: std::str::FromStr>(query: &HashMap<&str, &str>, name: &str) -> anyhow::Result<T> {
+    let value = query
+        .get(name)
+        .ok_or_else(|| anyhow!("missing the {name} parameter"))?;
+    value
+        .parse()
+        .map_err(|_| anyhow!("{name}={value} isn't a number"))
+}
+
+fn handle(sim: &mut Simulation, request: &mut Request) -> anyhow::Result<Value> {
+    let url = request.url().to_owned();
+    let path = url.split('?').next().unwrap_or_default();
+    let query = query(&url);
+    match (request.method(), path) {
+        (Method::Post, "/world") => {
+            let mut bytes = vec![];
+            request
+                .as_reader()
+                .take(MAX_WORLD_BYTES)
+                .read_to_end(&mut bytes)?;
+            sim.load_world_bytes(bytes)?;
+            Ok(sim.stats().to_json())
+        }
+        (Method::Post, "/step") => {
+            let count = if query.contains_key("count") {
+                number(&query, "count")?
+            } else {
+                1
+            };
+            if count > MAX_STEPS {
+                bail!("at most {MAX_STEPS} updates can be run at once");
+            }
+            (0..count).for_each(|_| sim.full_step());
+            Ok(sim.stats().to_json())
+        }
+        (Method::Get, "/stats") => Ok(sim.stats().to_json()),
+        (Method::Get, "/region") => {
+            let min = [number(&query, "min_x")?, number(&query, "min_y")?];
+            let max = [number(&query, "max_x")?, number(&query, "max_y")?];
+            sim.region_json(min, max)
+        }
+        (method, path) => bail!("no {method} {path}, see the top of rest.rs"),
+    }
+}
+
+//only on localhost like the remote control, runs until the process is killed
+pub fn serve(port: u16, world: Option<&Path>) -> anyhow::Result<()> {
+    let mut sim = Simulation::headless();
+    if let Some(world) = world {
+        sim.load_world_bytes(std::fs::read(world)?)?;
+    }
+    let server = Server::http((Ipv4Addr::LOCALHOST, port)).map_err(|e| anyhow!("{e}"))?;
+    log::info!("serving the simulation on port {port}");
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+    for mut request in server.incoming_requests() {
+        let (status, body) = match handle(&mut sim, &mut request) {
+            Ok(value) => (200, value),
+            Err(e) => (400, json!({ "error": e.to_string() })),
+        };
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            log::warn!("couldn't answer a request: {e}");
+        }
+    }
+    Ok(())
+}
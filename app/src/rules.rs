@@ -0,0 +1,88 @@
+use engine::{
+    ball::Direction,
+    rules::{CapPolicy, Collision, Rules, StepMode, PHASE_PRESETS},
+};
+use shared::egui::{Slider, Ui};
+
+//the world rules window, synchronous stepping is kept to Block collisions since nothing else passes `Rules::check`
+pub fn ui(rules: &mut Rules, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.label("stepping");
+        [StepMode::Sequential, StepMode::Synchronous]
+            .into_iter()
+            .for_each(|mode| {
+                ui.selectable_value(&mut rules.step_mode, mode, format!("{mode:?}"));
+            });
+    });
+    if rules.step_mode == StepMode::Synchronous {
+        rules.collision = Collision::Block;
+    }
+    ui.horizontal(|ui| {
+        ui.label("gravity");
+        ui.selectable_value(&mut rules.gravity, None, "none");
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .into_iter()
+        .for_each(|dir| {
+            ui.selectable_value(&mut rules.gravity, Some(dir), format!("{dir:?}"));
+        });
+    });
+    ui.horizontal(|ui| {
+        let mut limited = rules.lifetime.is_some();
+        ui.checkbox(&mut limited, "ball lifetime");
+        match (limited, &mut rules.lifetime) {
+            (true, Some(lifetime)) => {
+                ui.add(Slider::new(lifetime, 1..=10000).logarithmic(true));
+            }
+            (true, None) => rules.lifetime = Some(100),
+            (false, _) => rules.lifetime = None,
+        }
+    });
+    ui.horizontal(|ui| {
+        let mut capped = rules.population_cap.is_some();
+        ui.checkbox(&mut capped, "population cap");
+        match (capped, &mut rules.population_cap) {
+            (true, Some(cap)) => {
+                ui.add(Slider::new(cap, 1..=1_000_000).logarithmic(true));
+            }
+            (true, None) => rules.population_cap = Some(10_000),
+            (false, _) => rules.population_cap = None,
+        }
+    });
+    if rules.population_cap.is_some() {
+        ui.horizontal(|ui| {
+            ui.label("when full");
+            CapPolicy::ALL.iter().for_each(|policy| {
+                ui.selectable_value(&mut rules.cap_policy, *policy, format!("{policy:?}"));
+            });
+        });
+    }
+    if rules.step_mode == StepMode::Synchronous {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label("collisions");
+        Collision::ALL.iter().for_each(|collision| {
+            ui.selectable_value(&mut rules.collision, *collision, format!("{collision:?}"));
+        });
+    });
+    ui.horizontal(|ui| {
+        ui.label("phase order");
+        PHASE_PRESETS.iter().for_each(|(name, order)| {
+            ui.selectable_value(&mut rules.phase_order, *order, *name);
+        });
+    });
+    //swapping neighbours keeps every direction in the order exactly once
+    ui.horizontal(|ui| {
+        (0..rules.phase_order.len()).for_each(|i| {
+            ui.label(format!("{:?}", rules.phase_order[i]));
+            if i + 1 < rules.phase_order.len() && ui.small_button("<>").clicked() {
+                rules.phase_order.swap(i, i + 1);
+            }
+        });
+    });
+}
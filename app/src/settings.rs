@@ -0,0 +1,388 @@
+use std::{fs, path::PathBuf};
+
+use renderer::{post::PostEffects, state::Palette};
+use serde::{Deserialize, Serialize};
+use shared::{
+    anyhow,
+    egui::{Color32, ComboBox, DragValue, Rgba, Slider, Ui, Visuals},
+    log,
+};
+
+use crate::{
+    audio::EffectVolumes,
+    keybinds::{Action, Keybinds},
+    midi::port_names,
+};
+
+const SETTINGS_FILE: &str = "settings.toml";
+//what the remote control port starts at when turned on
+const REMOTE_PORT: u16 = 7878;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    Ocean,
+    Midnight,
+    Paper,
+    Custom,
+}
+
+impl Theme {
+    const PRESETS: [Theme; 3] = [Theme::Ocean, Theme::Midnight, Theme::Paper];
+
+    fn background(&self) -> Option<[f32; 3]> {
+        match self {
+            Theme::Ocean => Some([0.1, 0.2, 0.3]),
+            Theme::Midnight => Some([0.02, 0.02, 0.04]),
+            Theme::Paper => Some([0.85, 0.83, 0.78]),
+            Theme::Custom => None,
+        }
+    }
+
+    //whether the windows go dark over it
+    fn dark_ui(&self) -> Option<bool> {
+        match self {
+            Theme::Ocean | Theme::Midnight => Some(true),
+            Theme::Paper => Some(false),
+            Theme::Custom => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorPalette {
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast,
+}
+
+impl ColorPalette {
+    const ALL: [ColorPalette; 5] = [
+        ColorPalette::Standard,
+        ColorPalette::Deuteranopia,
+        ColorPalette::Protanopia,
+        ColorPalette::Tritanopia,
+        ColorPalette::HighContrast,
+    ];
+
+    //on and off colors, picked from the okabe-ito palette where possible
+    fn colors(&self) -> Option<([f32; 3], [f32; 3])> {
+        match self {
+            ColorPalette::Standard => None,
+            ColorPalette::Deuteranopia => Some(([0.90, 0.62, 0.0], [0.0, 0.45, 0.70])),
+            ColorPalette::Protanopia => Some(([0.94, 0.89, 0.26], [0.0, 0.45, 0.70])),
+            ColorPalette::Tritanopia => Some(([0.84, 0.37, 0.0], [0.0, 0.62, 0.45])),
+            ColorPalette::HighContrast => Some(([1.0, 1.0, 1.0], [0.15, 0.15, 0.15])),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    pub background: [f32; 3],
+    pub dark_ui: bool,
+    //None keeps egui's own selection and highlight color
+    pub accent: Option<[f32; 3]>,
+    pub palette: ColorPalette,
+    pub ball_patterns: bool,
+    //smallest and largest number of cells across the screen
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub post_effects: PostEffects,
+    pub msaa_samples: u32,
+    //pulsing holds, on balls and the hovered cell, the window keeps redrawing while any are in view
+    pub animations: bool,
+    pub vsync: bool,
+    //None renders as fast as possible, for benchmarking
+    pub fps_cap: Option<u32>,
+    pub shader_hot_reload: bool,
+    pub debug_overlay: bool,
+    //traces simulation updates at debug level under the `sim` target
+    pub sim_debug_log: bool,
+    //None keeps the remote control server off
+    pub remote_port: Option<u16>,
+    //zstd on top of the run length encoded chunks
+    pub compress_saves: bool,
+    //None only saves when asked to
+    pub autosave_minutes: Option<u32>,
+    //where community levels are listed and uploaded to
+    pub level_server: String,
+    //shown next to uploaded levels
+    pub level_author: String,
+    pub keybinds: Keybinds,
+    //note tile volume from 0 to 1
+    pub volume: f32,
+    pub muted: bool,
+    //None keeps midi output off
+    pub midi_port: Option<String>,
+    pub effect_volumes: EffectVolumes,
+    //the tutorial starts on its own until it was finished or skipped once
+    pub tutorial_done: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Ocean,
+            background: [0.1, 0.2, 0.3],
+            dark_ui: true,
+            accent: None,
+            palette: ColorPalette::Standard,
+            ball_patterns: false,
+            min_zoom: 8.0,
+            max_zoom: 64.0,
+            post_effects: PostEffects::default(),
+            msaa_samples: 1,
+            animations: false,
+            vsync: true,
+            fps_cap: Some(60),
+            shader_hot_reload: false,
+            debug_overlay: false,
+            sim_debug_log: false,
+            remote_port: None,
+            compress_saves: true,
+            autosave_minutes: Some(5),
+            level_server: "http://localhost:8080".to_owned(),
+            level_author: String::new(),
+            keybinds: Keybinds::default(),
+            volume: 0.5,
+            muted: false,
+            midi_port: None,
+            effect_volumes: EffectVolumes::default(),
+            tutorial_done: false,
+        }
+    }
+}
+
+//in the platform config dir, or the working directory where there is none
+pub fn config_path(file: &str) -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("ball_sim").join(file))
+        .unwrap_or_else(|| PathBuf::from(file))
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let path = config_path(SETTINGS_FILE);
+        //older versions kept them in the working directory, they move over on the next save
+        let Ok(text) = fs::read_to_string(&path).or_else(|_| fs::read_to_string(SETTINGS_FILE))
+        else {
+            return Self::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("ignoring invalid {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = config_path(SETTINGS_FILE);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = if self.dark_ui {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+        if let Some([r, g, b]) = self.accent {
+            let accent: Color32 = Rgba::from_rgb(r, g, b).into();
+            visuals.selection.bg_fill = accent;
+            visuals.hyperlink_color = accent;
+            visuals.widgets.active.bg_fill = accent;
+            visuals.widgets.hovered.bg_stroke.color = accent;
+        }
+        visuals
+    }
+
+    pub fn render_palette(&self) -> Option<Palette> {
+        match self.palette.colors() {
+            Some((on, off)) => Some(Palette {
+                on,
+                off,
+                patterns: self.ball_patterns,
+            }),
+            //patterns still need the recoloring path, it just keeps the original colors
+            None if self.ball_patterns => Some(Palette {
+                on: [172.0 / 255.0, 50.0 / 255.0, 50.0 / 255.0],
+                off: [91.0 / 255.0, 110.0 / 255.0, 225.0 / 255.0],
+                patterns: true,
+            }),
+            None => None,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        supported_msaa_samples: &[u32],
+        rebinding: &mut Option<Action>,
+    ) {
+        ui.heading("theme");
+        ui.horizontal(|ui| {
+            Theme::PRESETS.iter().for_each(|theme| {
+                if ui
+                    .selectable_label(self.theme == *theme, format!("{theme:?}"))
+                    .clicked()
+                {
+                    self.theme = *theme;
+                    self.background = theme.background().unwrap_or(self.background);
+                    self.dark_ui = theme.dark_ui().unwrap_or(self.dark_ui);
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("windows");
+            let old = self.dark_ui;
+            ui.selectable_value(&mut self.dark_ui, true, "dark");
+            ui.selectable_value(&mut self.dark_ui, false, "light");
+            //a preset goes along with the windows so the world doesn't clash with them
+            if self.dark_ui != old && self.theme.dark_ui().is_some() {
+                if let Some(theme) = Theme::PRESETS
+                    .into_iter()
+                    .find(|theme| theme.dark_ui() == Some(self.dark_ui))
+                {
+                    self.theme = theme;
+                    self.background = theme.background().unwrap_or(self.background);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut enabled = self.accent.is_some();
+            ui.checkbox(&mut enabled, "accent color");
+            match (enabled, &mut self.accent) {
+                (true, Some(accent)) => {
+                    ui.color_edit_button_rgb(accent);
+                }
+                (true, None) => self.accent = Some([0.9, 0.6, 0.1]),
+                (false, _) => self.accent = None,
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("background");
+            if ui.color_edit_button_rgb(&mut self.background).changed() {
+                self.theme = Theme::Custom;
+            }
+        });
+        ui.heading("accessibility");
+        ui.horizontal_wrapped(|ui| {
+            ui.label("palette");
+            ColorPalette::ALL.iter().for_each(|palette| {
+                ui.selectable_value(&mut self.palette, *palette, format!("{palette:?}"));
+            });
+        });
+        ui.checkbox(&mut self.ball_patterns, "hollow off balls");
+        ui.heading("camera");
+        ui.add(
+            Slider::new(&mut self.min_zoom, 2.0..=64.0)
+                .logarithmic(true)
+                .text("closest zoom"),
+        );
+        ui.add(
+            Slider::new(&mut self.max_zoom, 16.0..=2048.0)
+                .logarithmic(true)
+                .text("farthest zoom"),
+        )
+        .on_hover_text("capped at what the renderer can show all of");
+        self.max_zoom = self.max_zoom.max(self.min_zoom);
+        ui.heading("graphics");
+        ui.horizontal(|ui| {
+            ui.label("msaa");
+            supported_msaa_samples.iter().for_each(|samples| {
+                ui.selectable_value(&mut self.msaa_samples, *samples, format!("{samples}x"));
+            });
+        });
+        ui.checkbox(&mut self.animations, "animations")
+            .on_hover_text("redraws continuously while anything animated is in view");
+        ui.checkbox(&mut self.vsync, "vsync");
+        ui.horizontal(|ui| {
+            let mut capped = self.fps_cap.is_some();
+            ui.checkbox(&mut capped, "fps cap");
+            match (capped, &mut self.fps_cap) {
+                (true, Some(fps)) => {
+                    ui.add(Slider::new(fps, 10..=240));
+                }
+                (true, None) => self.fps_cap = Some(60),
+                (false, _) => self.fps_cap = None,
+            }
+        });
+        ui.heading("effects");
+        ui.checkbox(&mut self.post_effects.crt, "crt");
+        ui.checkbox(&mut self.post_effects.bloom, "bloom");
+        ui.checkbox(&mut self.post_effects.vignette, "vignette");
+        ui.heading("saves");
+        ui.checkbox(&mut self.compress_saves, "compress saved worlds");
+        ui.horizontal(|ui| {
+            let mut enabled = self.autosave_minutes.is_some();
+            ui.checkbox(&mut enabled, "autosave every");
+            match (enabled, &mut self.autosave_minutes) {
+                (true, Some(minutes)) => {
+                    ui.add(Slider::new(minutes, 1..=60).suffix(" min"));
+                }
+                (true, None) => self.autosave_minutes = Some(5),
+                (false, _) => self.autosave_minutes = None,
+            }
+        });
+        if cfg!(feature = "online") {
+            ui.horizontal(|ui| {
+                ui.label("level server");
+                ui.text_edit_singleline(&mut self.level_server);
+            });
+            ui.horizontal(|ui| {
+                ui.label("author name");
+                ui.text_edit_singleline(&mut self.level_author);
+            });
+        }
+        ui.heading("audio");
+        if !cfg!(feature = "audio") {
+            ui.label("built without the audio feature, note tiles are silent");
+        }
+        ui.add_enabled(
+            !self.muted,
+            Slider::new(&mut self.volume, 0.0..=1.0).text("volume"),
+        );
+        ui.checkbox(&mut self.muted, "mute");
+        ui.collapsing("sound effects", |ui| self.effect_volumes.ui(ui));
+        if cfg!(feature = "midi") {
+            ui.horizontal(|ui| {
+                ui.label("midi output");
+                ComboBox::from_id_salt("midi port")
+                    .selected_text(self.midi_port.as_deref().unwrap_or("off"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.midi_port, None, "off");
+                        //only listed while the menu is open
+                        port_names().into_iter().for_each(|port| {
+                            let text = port.clone();
+                            ui.selectable_value(&mut self.midi_port, Some(port), text);
+                        });
+                    });
+            });
+        }
+        ui.collapsing("keybinds", |ui| self.keybinds.ui(ui, rebinding));
+        ui.heading("developer");
+        ui.checkbox(&mut self.debug_overlay, "chunk debug overlay");
+        ui.checkbox(&mut self.sim_debug_log, "log simulation updates");
+        ui.horizontal(|ui| {
+            let mut enabled = self.remote_port.is_some();
+            ui.checkbox(&mut enabled, "remote control on localhost port");
+            match (enabled, &mut self.remote_port) {
+                (true, Some(port)) => {
+                    ui.add(DragValue::new(port).range(1024..=65535));
+                }
+                (true, None) => self.remote_port = Some(REMOTE_PORT),
+                (false, _) => self.remote_port = None,
+            }
+        });
+        if cfg!(debug_assertions) {
+            ui.checkbox(&mut self.shader_hot_reload, "shader hot reload");
+        }
+    }
+}
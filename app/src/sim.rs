@@ -1,45 +1,359 @@
 use std::{
     array::from_fn,
-    collections::{HashMap, HashSet},
+    cell::RefCell,
+    fs,
     ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
 };
 
-use renderer::{
+use engine::{
     ball::{BallPosition, Direction},
     chunk::{Chunk, ChunkPosition, CHUNK_SIZE},
+    events::{SimEvent, SubscriptionId},
+    hash::{HashMap, HashSet},
+    rules::Rules,
+    save::{self, CameraSave, WorldSave},
+    stats::SimStats,
+    tile_data::{Probe, TileData},
+    tiles::{Layer, Tile},
+    world::{chunk_of, write_tile, Followed, GetTile, World},
 };
+use renderer::text::{TextLabel, GLYPH_ASPECT};
+use serde_json::{json, Value};
 use shared::{
+    anyhow,
     egui::{self},
-    winit::keyboard::KeyCode,
+    log,
 };
 
 use crate::{
-    app::{App, State},
-    tiles::Tile,
+    analysis::{probe_name, TruthTable, MAX_INPUTS},
+    app::{App, State, TITLE},
+    audio::{Audio, Effect},
+    blueprint::{Blueprint, BLUEPRINT_DIR, BLUEPRINT_EXTENSION},
+    blueprint_browser::{BlueprintAction, BlueprintBrowser},
+    console::{Console, ConsoleCommand, Paint},
+    dialogs,
+    generators::Generator,
+    help::Help,
+    history::{Edit, History},
+    import::Importer,
+    inspector::BallInspector,
+    keybinds::Action,
+    macros::{EditMacro, MacroOp},
+    midi::Midi,
+    online::{LevelClient, OnlineAction},
+    remote::{self, Command, RemoteServer, Request},
+    rules,
+    spectate::{Broadcaster, ChangedCells, Frame, Spectator, BROADCAST_PORT},
+    stats::{self, StatsHistory},
+    templates::Template,
+    tile_data,
+    tile_image::{self, ImageMode},
+    tile_info::TileInfo,
+    tutorial::Tutorial,
+    world::{self, DEFAULT_WORLD_PATH, WORLD_EXTENSION},
+    world_browser::{age_text, BrowserAction, WorldBrowser},
+    SCROLL_SPEED,
 };
 
+//updates remote and console commands can run at once, the window is frozen while they run
+const MAX_STEPS: u32 = 10_000;
+//cells a console fill can cover
+const MAX_FILL_CELLS: i64 = 1 << 20;
+
+//full updates per second the speed selector and its hotkeys pick from
+const SPEEDS: [f32; 8] = [1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Tool {
     BallTool(bool),
     TileTool(Tile),
+    LabelTool,
+    //removes balls, labels and tiles in one stroke
+    Eraser,
+    Select,
+    //places the current blueprint at each click
+    Stamp,
+    //picks a ball up on press and drops it where the button is released
+    MoveBall,
+    //drops random balls into the dragged rectangle once released
+    Scatter,
+    //fills the dragged rectangle with on or off balls once released
+    Fill(bool),
+    //shows the distance between where the drag started and the cursor
+    Measure,
+    //opens panels for the clicked tile and the ball on it, which is tracked as it moves
+    Inspect,
+    //replays the selected macro anchored at each click
+    PlayMacro,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Symmetry {
+    Off,
+    //mirrored left to right across a vertical line
+    Horizontal,
+    //mirrored top to bottom across a horizontal line
+    Vertical,
+    Quad,
+}
+
+impl Symmetry {
+    const ALL: [Symmetry; 4] = [
+        Symmetry::Off,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Quad,
+    ];
+
+    //every copy of pos, with whether it was flipped on x and y
+    fn mirror(&self, pos: [i32; 2], axis: [i32; 2]) -> Vec<([i32; 2], bool, bool)> {
+        let flipped = [2 * axis[0] - pos[0], 2 * axis[1] - pos[1]];
+        let mut out = vec![(pos, false, false)];
+        if matches!(self, Symmetry::Horizontal | Symmetry::Quad) {
+            out.push(([flipped[0], pos[1]], true, false));
+        }
+        if matches!(self, Symmetry::Vertical | Symmetry::Quad) {
+            out.push(([pos[0], flipped[1]], false, true));
+        }
+        if *self == Symmetry::Quad {
+            out.push((flipped, true, true));
+        }
+        out
+    }
 }
 
+//what is waiting on the user to decide what happens to the unsaved changes
+enum Pending {
+    Open(PathBuf),
+    Paste(String),
+    //the address of the instance to watch
+    Watch(String),
+    Exit,
+}
+
+//the world being stepped and everything around it that is only there for editing and watching
 pub struct Simulation {
-    chunks: HashMap<ChunkPosition, Chunk>,
-    balls: HashMap<BallPosition, (bool, Direction)>,
+    world: World,
+    overlay: HashMap<ChunkPosition, Chunk>,
+    //the layer tile tools and the eraser work on
+    layer: Layer,
+    labels: HashMap<[i32; 2], String>,
+    label_text: String,
     current_tool: Tool,
     last_mouse_pos: [f32; 2],
+    //where the camera is gliding to after a double click
+    camera_target: Option<[f32; 2]>,
+    world_path: String,
+    //edits made since the world was last saved or loaded
+    dirty: bool,
+    //edits made since the last autosave
+    autosave_pending: bool,
+    last_autosave: Instant,
+    //the tutorial's world, thrown away afterwards so it is never saved or asked about
+    practice: bool,
+    //swapped for the tutorial on the next update
+    start_tutorial: bool,
+    pending: Option<Pending>,
+    //whether the left button was painting during the previous update
+    painting: bool,
+    //what the tool kind toggle switches back to
+    last_ball_tool: bool,
+    last_tile_tool: Tile,
+    //side length of the square painted around the cursor
+    brush_size: u32,
+    symmetry: Symmetry,
+    //the cell every mirror line passes through the center of
+    symmetry_axis: [i32; 2],
+    //inclusive corners of the selected cells
+    selection: Option<([i32; 2], [i32; 2])>,
+    selection_anchor: [i32; 2],
+    blueprints: Vec<Blueprint>,
+    blueprint_name: String,
+    blueprint_browser: BlueprintBrowser,
+    importer: Importer,
+    world_browser: WorldBrowser,
+    level_client: LevelClient,
+    remote: Option<RemoteServer>,
+    //a port that couldn't be listened on isn't tried again until the setting changes
+    remote_failed: Option<u16>,
+    broadcaster: Option<Broadcaster>,
+    broadcast_port: u16,
+    //listening on every interface instead of only localhost
+    broadcast_public: bool,
+    //while watching another instance nothing can be edited
+    spectator: Option<Spectator>,
+    spectate_address: String,
+    spectate_message: String,
+    //the blueprint the stamp tool places, already rotated
+    stamp: Option<Blueprint>,
+    //ball picked up by the move tool and the cell it came from
+    held_ball: Option<((bool, Direction), [i32; 2])>,
+    scatter_count: u32,
+    history: History,
+    replace_from: Tile,
+    replace_to: Tile,
+    replace_in_selection: bool,
+    //result of the last find and replace
+    replace_summary: String,
+    image_path: String,
+    image_mode: ImageMode,
+    //result of the last image export
+    image_summary: String,
+    //the level code being pasted, or the last one copied
+    level_code: String,
+    level_code_summary: String,
+    //updates each truth table run lasts
+    analysis_updates: u32,
+    //result of the last truth table export
+    analysis_summary: String,
+    //start and end cell of the last measurement
+    measurement: Option<([i32; 2], [i32; 2])>,
+    //the cell and position the right button went down on, until the pointer leaves that cell
+    right_press: Option<([i32; 2], [f32; 2])>,
+    //the cell a right click opened the menu for, and where it is shown once the ui placed it
+    cell_menu: Option<([i32; 2], Option<egui::Pos2>)>,
+    bookmarks: Vec<[i32; 2]>,
+    //playing stops once a ball arrives on one of these
+    breakpoints: HashSet<[i32; 2]>,
+    breakpoint_message: String,
+    macros: Vec<EditMacro>,
+    //edits made while this is set are captured into it
+    macro_recording: Option<Vec<([i32; 2], MacroOp)>>,
+    macro_name: String,
+    current_macro: Option<usize>,
+    generator: Generator,
+    templates: Vec<Template>,
+    help: Help,
+    console: Console,
+    //set when an update was refused because of the population cap
+    cap_warning: bool,
+    //running full updates on its own, `speed` of them per second
+    playing: bool,
+    speed: f32,
+    //time in ms played that hasn't been turned into updates yet
+    play_time: f32,
+    //updates a turbo run does and how many of them go by between frames
+    turbo_ticks: u64,
+    turbo_frame_ticks: u64,
+    //updates the running turbo run has left, nothing can be edited until it's done
+    turbo_left: u64,
+    //the world before it was first stepped and its tick, what resetting goes back to
+    edit_state: Option<(WorldSave, u64)>,
+    stats_history: StatsHistory,
+    ball_inspector: Option<BallInspector>,
+    //cell shown in the tile inspector
+    tile_inspector: Option<[i32; 2]>,
+    audio: Audio,
+    midi: Midi,
+    //events with a sound since the last frame, filled by an event subscription
+    sounds: Rc<RefCell<Vec<SimEvent>>>,
+    sound_subscription: Option<SubscriptionId>,
+    //effects from editing and the ui waiting for the next frame
+    effects: Vec<Effect>,
 }
 
 impl Simulation {
     pub fn new(mouse_pos: [f32; 2]) -> Self {
+        let mut s = Self::headless();
+        s.last_mouse_pos = mouse_pos;
+        s.blueprints = Blueprint::load_all(Path::new(BLUEPRINT_DIR));
+        s.audio = Audio::new();
+        s.world_browser.refresh();
+        s
+    }
+
+    //touches no files and no audio device, for scripts, servers and throwaway runs
+    pub fn headless() -> Self {
         let mut s = Self {
-            chunks: HashMap::new(),
-            last_mouse_pos: mouse_pos,
+            world: World::default(),
+            overlay: HashMap::default(),
+            layer: Layer::Ground,
+            last_mouse_pos: [0.0; 2],
             current_tool: Tool::TileTool(Tile::Block),
-            balls: HashMap::new(),
+            labels: HashMap::default(),
+            label_text: String::new(),
+            camera_target: None,
+            world_path: DEFAULT_WORLD_PATH.to_owned(),
+            dirty: false,
+            autosave_pending: false,
+            last_autosave: Instant::now(),
+            practice: false,
+            start_tutorial: false,
+            pending: None,
+            painting: false,
+            last_ball_tool: true,
+            last_tile_tool: Tile::Block,
+            brush_size: 1,
+            symmetry: Symmetry::Off,
+            symmetry_axis: [0, 0],
+            selection: None,
+            selection_anchor: [0, 0],
+            blueprints: vec![],
+            blueprint_name: String::new(),
+            blueprint_browser: BlueprintBrowser::default(),
+            importer: Importer::default(),
+            world_browser: WorldBrowser::default(),
+            level_client: LevelClient::default(),
+            remote: None,
+            remote_failed: None,
+            broadcaster: None,
+            broadcast_port: BROADCAST_PORT,
+            broadcast_public: false,
+            spectator: None,
+            spectate_address: format!("127.0.0.1:{BROADCAST_PORT}"),
+            spectate_message: String::new(),
+            stamp: None,
+            held_ball: None,
+            scatter_count: 100,
+            history: History::default(),
+            replace_from: Tile::DuplicateH,
+            replace_to: Tile::DuplicateV,
+            replace_in_selection: false,
+            replace_summary: String::new(),
+            image_path: "selection.png".to_owned(),
+            image_mode: ImageMode::Pixels,
+            image_summary: String::new(),
+            level_code: String::new(),
+            level_code_summary: String::new(),
+            analysis_updates: 64,
+            analysis_summary: String::new(),
+            measurement: None,
+            right_press: None,
+            cell_menu: None,
+            bookmarks: vec![],
+            breakpoints: HashSet::default(),
+            breakpoint_message: String::new(),
+            macros: vec![],
+            macro_recording: None,
+            macro_name: String::new(),
+            current_macro: None,
+            generator: Generator::default(),
+            templates: Template::all(),
+            help: Help::default(),
+            console: Console::default(),
+            cap_warning: false,
+            playing: false,
+            speed: 10.0,
+            play_time: 0.0,
+            turbo_ticks: 10_000,
+            turbo_frame_ticks: 1000,
+            turbo_left: 0,
+            edit_state: None,
+            stats_history: StatsHistory::default(),
+            ball_inspector: None,
+            tile_inspector: None,
+            audio: Audio::silent(),
+            midi: Midi::default(),
+            sounds: Rc::default(),
+            sound_subscription: None,
+            effects: vec![],
         };
-        s.chunks.insert(
+        s.world.chunks.insert(
             ChunkPosition { position: [0; 2] },
             Chunk {
                 data: from_fn(|_| Into::<u8>::into(Tile::Empty)),
@@ -48,22 +362,1032 @@ impl Simulation {
         s
     }
 
-    fn update_zoom(app: &mut App) {
-        const SCROLL_SPEED: f32 = 5.0;
+    fn update_zoom(app: &mut App, delta_time: f32) {
+        //time in ms for the zoom to cover most of the way to the target
+        const ZOOM_SMOOTHING: f32 = 60.0;
 
         let prev = app.get_mouse_position_world();
-        //clamping the zoom between 64 and 8
-        *app.scroll_level_mut() = app
-            .scroll_level()
-            .clamp(-6.0 * SCROLL_SPEED, -3.0 * SCROLL_SPEED);
-        app.camera_mut().width = 2.0_f32.powf(-app.scroll_level() / SCROLL_SPEED);
+        let (min_zoom, max_zoom) = (app.settings().min_zoom, app.max_zoom());
+        *app.scroll_level_mut() = app.scroll_level().clamp(
+            -max_zoom.log2() * SCROLL_SPEED,
+            -min_zoom.log2() * SCROLL_SPEED,
+        );
+        //easing in log space so zooming in and out feel the same
+        let target = -app.scroll_level() / SCROLL_SPEED;
+        let current = app.camera().width.log2();
+        let t = 1.0 - (-delta_time / ZOOM_SMOOTHING).exp();
+        let next = if (target - current).abs() < 0.001 {
+            target
+        } else {
+            app.request_redraw();
+            current + (target - current) * t
+        };
+        app.camera_mut().width = 2.0_f32.powf(next);
         let curr = app.get_mouse_position_world();
         let pos = &mut app.camera_mut().pos;
         pos[0] += prev[0] - curr[0];
         pos[1] += prev[1] - curr[1];
     }
 
-    fn drag_camera(&self, app: &mut App) {
+    pub fn practice(mouse_pos: [f32; 2]) -> Self {
+        let mut s = Self::new(mouse_pos);
+        s.practice = true;
+        s
+    }
+
+    //every tile that isn't empty, the base layer before the overlay
+    fn placed_tiles(&self) -> impl Iterator<Item = ([i32; 2], Tile)> + '_ {
+        self.world
+            .chunks
+            .iter()
+            .chain(&self.overlay)
+            .flat_map(|(chunk_pos, chunk)| {
+                (0..CHUNK_SIZE as u32)
+                    .flat_map(|x| (0..CHUNK_SIZE as u32).map(move |y| [x, y]))
+                    .filter_map(|pos| {
+                        let tile = Tile::try_from(chunk.get_tile(pos)).ok()?;
+                        let pos = [
+                            chunk_pos.position[0] * CHUNK_SIZE as i32 + pos[0] as i32,
+                            chunk_pos.position[1] * CHUNK_SIZE as i32 + pos[1] as i32,
+                        ];
+                        (tile != Tile::Empty).then_some((pos, tile))
+                    })
+            })
+    }
+
+    pub fn has_tiles(&self) -> bool {
+        self.placed_tiles().next().is_some()
+    }
+
+    //smallest and largest cell holding a tile or ball
+    fn content_bounds(&self) -> Option<([i32; 2], [i32; 2])> {
+        let tiles = self.placed_tiles().map(|(pos, _)| pos);
+        let balls = self.world.balls.keys().map(|ball| ball.position);
+        tiles.chain(balls).fold(None, |bounds, pos| match bounds {
+            None => Some((pos, pos)),
+            Some((min, max)) => Some((
+                [min[0].min(pos[0]), min[1].min(pos[1])],
+                [max[0].max(pos[0]), max[1].max(pos[1])],
+            )),
+        })
+    }
+
+    fn frame_all(&mut self, app: &mut App) {
+        //empty cells left around the content
+        const MARGIN: f32 = 2.0;
+
+        let Some((min, max)) = self.content_bounds() else {
+            return;
+        };
+        self.camera_target = None;
+        let size = [
+            (max[0] - min[0] + 1) as f32 + MARGIN * 2.0,
+            (max[1] - min[1] + 1) as f32 + MARGIN * 2.0,
+        ];
+        let camera = app.camera();
+        let fit = camera.screensize[0].min(camera.screensize[1] * camera.min_ratio);
+        let width = (size[0] * fit / camera.screensize[0])
+            .max(size[1] * fit / camera.screensize[1])
+            .clamp(app.settings().min_zoom, app.max_zoom());
+
+        let camera = app.camera_mut();
+        camera.pos = [
+            (min[0] + max[0] + 1) as f32 / 2.0,
+            (min[1] + max[1] + 1) as f32 / 2.0,
+        ];
+        camera.width = width;
+        *app.scroll_level_mut() = -width.log2() * SCROLL_SPEED;
+        app.request_redraw();
+    }
+
+    fn update_pan(&mut self, app: &mut App, delta_time: f32) {
+        //time in ms for the camera to cover most of the way to the target
+        const PAN_SMOOTHING: f32 = 80.0;
+
+        if app.double_clicked() && !app.in_ui() {
+            let pos = app.get_mouse_position_world();
+            self.camera_target = Some([pos[0].floor() + 0.5, pos[1].floor() + 0.5]);
+        }
+        let Some(target) = self.camera_target else {
+            return;
+        };
+        let t = 1.0 - (-delta_time / PAN_SMOOTHING).exp();
+        let pos = &mut app.camera_mut().pos;
+        if (target[0] - pos[0]).hypot(target[1] - pos[1]) < 0.01 {
+            *pos = target;
+            self.camera_target = None;
+        } else {
+            pos[0] += (target[0] - pos[0]) * t;
+            pos[1] += (target[1] - pos[1]) * t;
+        }
+        app.request_redraw();
+    }
+
+    fn save_world(&self, app: &App, path: &Path) -> anyhow::Result<()> {
+        self.world_save(app)
+            .write(path, app.settings().compress_saves)?;
+        //a world without its preview is still saved
+        if let Err(e) = self.save_thumbnail(path) {
+            log::warn!("couldn't save a thumbnail for {}: {e}", path.display());
+        }
+        Ok(())
+    }
+
+    fn world_save(&self, app: &App) -> WorldSave {
+        let camera = app.camera();
+        WorldSave::new(
+            CameraSave {
+                pos: camera.pos,
+                width: camera.width,
+            },
+            self.original_chunks()
+                .iter()
+                .map(|(pos, chunk)| (*pos, chunk.data.to_vec()))
+                .collect(),
+            self.overlay
+                .iter()
+                .map(|(pos, chunk)| (*pos, chunk.data.to_vec()))
+                .collect(),
+            self.world
+                .balls
+                .iter()
+                .map(|(pos, ball)| (*pos, *ball))
+                .collect(),
+            self.labels
+                .iter()
+                .map(|(pos, text)| (*pos, text.clone()))
+                .collect(),
+            self.world.rules(),
+            self.world
+                .tile_data
+                .iter()
+                .map(|(pos, data)| (*pos, data))
+                .collect(),
+        )
+    }
+
+    fn save_thumbnail(&self, path: &Path) -> anyhow::Result<()> {
+        let (min, max) = self.content_bounds().unwrap_or(([0; 2], [0; 2]));
+        let relative = |pos: [i32; 2]| [pos[0] - min[0], pos[1] - min[1]];
+        let blueprint = Blueprint {
+            name: String::new(),
+            tiles: self
+                .placed_tiles()
+                .map(|(pos, tile)| (relative(pos), tile))
+                .collect(),
+            balls: self
+                .world
+                .balls
+                .iter()
+                .map(|(pos, ball)| (relative(pos.position), *ball))
+                .collect(),
+            tags: Vec::new(),
+        };
+        tile_image::thumbnail(
+            &blueprint,
+            [max[0] - min[0] + 1, max[1] - min[1] + 1],
+            &world::thumbnail_path(path),
+        )
+    }
+
+    //the world's file name, starred while it has unsaved changes
+    fn window_title(&self) -> String {
+        let name = Path::new(&self.world_path)
+            .file_name()
+            .map_or(self.world_path.clone(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+        let marker = if self.dirty { "*" } else { "" };
+        format!("{TITLE} — {name}{marker}")
+    }
+
+    //saving somewhere makes that the world's file
+    fn save_as(&mut self, app: &App, path: &Path) -> bool {
+        match self.save_world(app, path) {
+            Ok(()) => {
+                self.world_path = path.to_string_lossy().into_owned();
+                self.dirty = false;
+                true
+            }
+            Err(e) => {
+                log::error!("couldn't save {}: {e}", path.display());
+                false
+            }
+        }
+    }
+
+    fn open_world(&mut self, app: &mut App, path: &Path) {
+        match self.load_world(app, path) {
+            Ok(()) => self.world_path = path.to_string_lossy().into_owned(),
+            Err(e) => log::error!("couldn't load {}: {e}", path.display()),
+        }
+    }
+
+    //starts, moves or stops the server to match the settings and runs what it received
+    fn update_remote(&mut self, app: &mut App) {
+        let port = app.settings().remote_port;
+        if self.remote.as_ref().map(RemoteServer::port) != port && self.remote_failed != port {
+            self.remote = None;
+            self.remote_failed = None;
+            if let Some(port) = port {
+                match RemoteServer::start(port) {
+                    Ok(server) => self.remote = Some(server),
+                    Err(e) => {
+                        log::error!("couldn't start remote control on port {port}: {e}");
+                        self.remote_failed = Some(port);
+                    }
+                }
+            }
+        }
+        let Some(remote) = &self.remote else {
+            return;
+        };
+        let requests: Vec<Request> = remote.requests().collect();
+        requests.into_iter().for_each(|(command, reply)| {
+            let result = self.run_command(app, command);
+            //the connection may have closed while waiting
+            let _ = reply.send(result);
+        });
+    }
+
+    //edits are undoable like ones made by hand, loading is refused while there are unsaved changes
+    fn run_command(&mut self, app: &mut App, command: Command) -> anyhow::Result<Value> {
+        match command {
+            Command::SetTile { pos, tile } => {
+                self.history.begin("remote set tile");
+                self.set_tile(pos, tile);
+                self.history.commit();
+            }
+            Command::SetBall { pos, on, direction } => {
+                self.history.begin("remote set ball");
+                self.set_ball(pos, (on, direction));
+                self.history.commit();
+            }
+            Command::RemoveBall { pos } => {
+                self.history.begin("remote remove ball");
+                self.remove_ball(pos);
+                self.history.commit();
+            }
+            Command::Step { count } => {
+                if count > MAX_STEPS {
+                    anyhow::bail!("at most {MAX_STEPS} updates can be run at once");
+                }
+                (0..count).for_each(|_| self.full_step());
+            }
+            Command::Region { min, max } => return self.region_json(min, max),
+            Command::Stats => return Ok(self.world.stats.to_json()),
+            Command::Load { path } => {
+                let path = remote::world_file(&path)?;
+                if self.dirty {
+                    anyhow::bail!("the world has unsaved changes, save it first");
+                }
+                self.load_world(app, &path)?;
+                self.world_path = path.to_string_lossy().into_owned();
+            }
+            Command::Save { path } => {
+                let path = remote::world_file(&path)?;
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                self.save_world(app, &path)?;
+            }
+        }
+        app.request_redraw();
+        Ok(Value::Null)
+    }
+
+    //a line from the palette or the console, what it says back is printed to the console
+    fn run_console_line(&mut self, app: &mut App, line: &str) -> anyhow::Result<String> {
+        let output = match ConsoleCommand::parse(line)? {
+            ConsoleCommand::Goto(pos) => {
+                self.camera_target = Some([pos[0] as f32 + 0.5, pos[1] as f32 + 0.5]);
+                String::new()
+            }
+            ConsoleCommand::Fill(corners, paint) => {
+                let Some((min, max)) = corners.or(self.selection) else {
+                    anyhow::bail!("nothing is selected, give the corners");
+                };
+                let cells =
+                    (max[0] as i64 - min[0] as i64 + 1) * (max[1] as i64 - min[1] as i64 + 1);
+                if cells > MAX_FILL_CELLS {
+                    anyhow::bail!("at most {MAX_FILL_CELLS} cells can be filled at once");
+                }
+                self.history.begin(format!("fill with {paint:?}"));
+                (min[0]..=max[0])
+                    .flat_map(|x| (min[1]..=max[1]).map(move |y| [x, y]))
+                    .for_each(|pos| match paint {
+                        Paint::Tile(tile) => self.set_layer_tile(self.layer, pos, tile),
+                        Paint::Ball(on) if !self.get_tile(pos).is_solid() => {
+                            self.set_ball(pos, (on, Direction::Right))
+                        }
+                        Paint::Ball(_) => {}
+                    });
+                self.history.commit();
+                format!("filled {cells} cells")
+            }
+            ConsoleCommand::Run(count) => {
+                if count > MAX_STEPS {
+                    anyhow::bail!("at most {MAX_STEPS} updates can be run at once");
+                }
+                let start = self.world.stats.tick;
+                self.step(app, count);
+                format!("ran {} updates", self.world.stats.tick - start)
+            }
+            ConsoleCommand::Play => {
+                self.playing = true;
+                String::new()
+            }
+            ConsoleCommand::Pause => {
+                self.playing = false;
+                String::new()
+            }
+            ConsoleCommand::Reset => {
+                self.reset_to_edit_state(app);
+                String::new()
+            }
+            ConsoleCommand::Load(path) => {
+                if self.dirty {
+                    self.pending = Some(Pending::Open(PathBuf::from(&path)));
+                    "asking about the unsaved changes first".to_owned()
+                } else {
+                    self.load_world(app, Path::new(&path))?;
+                    self.world_path = path;
+                    format!("opened {}", self.world_path)
+                }
+            }
+            ConsoleCommand::Save(path) => {
+                let path = path.unwrap_or_else(|| self.world_path.clone());
+                self.save_world(app, Path::new(&path))?;
+                self.world_path = path;
+                self.dirty = false;
+                format!("saved {}", self.world_path)
+            }
+            ConsoleCommand::Tool(paint) => {
+                self.current_tool = match paint {
+                    Paint::Tile(tile) => Tool::TileTool(tile),
+                    Paint::Ball(on) => Tool::BallTool(on),
+                };
+                String::new()
+            }
+            ConsoleCommand::Frame => {
+                self.frame_all(app);
+                String::new()
+            }
+            ConsoleCommand::Undo => {
+                self.undo();
+                String::new()
+            }
+            ConsoleCommand::Redo => {
+                self.redo();
+                String::new()
+            }
+            ConsoleCommand::Help => {
+                self.console.help();
+                String::new()
+            }
+        };
+        app.request_redraw();
+        Ok(output)
+    }
+
+    //the ground tiles that aren't empty and the balls, with positions relative to `min`
+    pub fn region_json(&self, min: [i32; 2], max: [i32; 2]) -> anyhow::Result<Value> {
+        const MAX_REGION_CELLS: i64 = 1 << 20;
+        let size = [0, 1].map(|i| max[i] as i64 - min[i] as i64 + 1);
+        if size.iter().any(|side| *side <= 0) || size[0] * size[1] > MAX_REGION_CELLS {
+            anyhow::bail!("regions have to be between 1 and {MAX_REGION_CELLS} cells");
+        }
+        let inside = |pos: &[i32; 2]| (0..2).all(|i| (min[i]..=max[i]).contains(&pos[i]));
+        let relative = |pos: [i32; 2]| [pos[0] - min[0], pos[1] - min[1]];
+        let tiles: Vec<([i32; 2], Tile)> = (min[0]..=max[0])
+            .flat_map(|x| (min[1]..=max[1]).map(move |y| [x, y]))
+            .map(|pos| (pos, self.get_tile(pos)))
+            .filter(|(_, tile)| *tile != Tile::Empty)
+            .map(|(pos, tile)| (relative(pos), tile))
+            .collect();
+        let mut balls: Vec<([i32; 2], bool, Direction)> = self
+            .world
+            .balls
+            .iter()
+            .filter(|(pos, _)| inside(&pos.position))
+            .map(|(pos, (on, dir))| (relative(pos.position), *on, *dir))
+            .collect();
+        balls.sort_by_key(|(pos, _, _)| *pos);
+        Ok(json!({ "size": size, "tiles": tiles, "balls": balls }))
+    }
+
+    //what spectators are sent next, nothing is kept unless broadcasting
+    fn changed(&mut self) -> Option<&mut ChangedCells> {
+        self.broadcaster
+            .as_mut()
+            .map(|broadcaster| &mut broadcaster.changed)
+    }
+
+    fn labels_list(&self) -> Vec<([i32; 2], String)> {
+        self.labels
+            .iter()
+            .map(|(pos, text)| (*pos, text.clone()))
+            .collect()
+    }
+
+    //starts spectators over with the whole world
+    fn everything_frame(&self) -> Frame {
+        Frame {
+            reset: true,
+            chunks: Layer::ALL
+                .into_iter()
+                .flat_map(|layer| {
+                    self.layer_chunks(layer)
+                        .iter()
+                        .map(move |(pos, chunk)| (layer, *pos, Some(chunk.data.to_vec())))
+                })
+                .collect(),
+            removed_balls: vec![],
+            balls: self.balls().collect(),
+            labels: Some(self.labels_list()),
+            tick: self.world.stats.tick,
+        }
+    }
+
+    fn changed_frame(&self, changed: ChangedCells) -> Frame {
+        if changed.everything {
+            return self.everything_frame();
+        }
+        let mut frame = Frame {
+            chunks: changed
+                .chunks
+                .into_iter()
+                .map(|(layer, pos)| {
+                    let chunk = self.layer_chunks(layer).get(&pos);
+                    (layer, pos, chunk.map(|chunk| chunk.data.to_vec()))
+                })
+                .collect(),
+            labels: changed.labels.then(|| self.labels_list()),
+            tick: self.world.stats.tick,
+            ..Default::default()
+        };
+        changed
+            .balls
+            .into_iter()
+            .for_each(|pos| match self.get_ball(pos) {
+                Some(ball) => frame.balls.push((pos, ball)),
+                None => frame.removed_balls.push(pos),
+            });
+        frame
+    }
+
+    //after every update, and every frame for edits made in between
+    fn broadcast_changes(&mut self) {
+        //the world keeps its own ground and ball changes while broadcasting
+        if let Some(world) = self.world.take_changes() {
+            if let Some(changed) = self.changed() {
+                changed.everything |= world.everything;
+                changed
+                    .chunks
+                    .extend(world.chunks.into_iter().map(|chunk| (Layer::Ground, chunk)));
+                changed.balls.extend(world.balls);
+            }
+        }
+        let Some(changed) = self.changed().map(std::mem::take) else {
+            return;
+        };
+        let frame = self.changed_frame(changed);
+        if let Some(Err(e)) = self
+            .broadcaster
+            .as_mut()
+            .map(|broadcaster| broadcaster.send(&frame))
+        {
+            log::error!("couldn't broadcast: {e}");
+        }
+    }
+
+    //the whole world is only put together for spectators who join or fall behind
+    fn broadcast(&mut self) {
+        self.broadcast_changes();
+        if !self
+            .broadcaster
+            .as_mut()
+            .is_some_and(Broadcaster::wants_everything)
+        {
+            return;
+        }
+        let everything = self.everything_frame();
+        if let Some(Err(e)) = self
+            .broadcaster
+            .as_mut()
+            .map(|broadcaster| broadcaster.resync(&everything))
+        {
+            log::error!("couldn't broadcast: {e}");
+        }
+    }
+
+    //the world shown until the first frame arrives is left over, it isn't saved anywhere
+    fn start_watching(&mut self, address: &str) {
+        self.spectator = Some(Spectator::connect(address));
+        self.broadcaster = None;
+        self.world.track_changes(false);
+        self.world_path = DEFAULT_WORLD_PATH.to_owned();
+        self.history.clear();
+        self.dirty = false;
+        self.playing = false;
+        self.edit_state = None;
+        self.selection = None;
+        self.held_ball = None;
+        self.ball_inspector = None;
+        self.world.follow(None);
+        self.painting = false;
+        self.spectate_message.clear();
+    }
+
+    fn apply_frame(&mut self, frame: Frame) -> anyhow::Result<()> {
+        if frame.reset {
+            self.world.clear();
+            self.overlay.clear();
+            self.labels.clear();
+        }
+        for (layer, pos, data) in frame.chunks {
+            let chunks = match layer {
+                Layer::Ground => &mut self.world.chunks,
+                Layer::Overlay => &mut self.overlay,
+            };
+            match data {
+                Some(data) => {
+                    let data = data.try_into().map_err(|data: Vec<u8>| {
+                        anyhow::anyhow!("chunk {pos:?} has {} tiles", data.len())
+                    })?;
+                    chunks.insert(pos, Chunk { data });
+                }
+                None => {
+                    chunks.remove(&pos);
+                }
+            }
+        }
+        frame.removed_balls.iter().for_each(|pos| {
+            self.world.balls.remove(&BallPosition { position: *pos });
+        });
+        frame.balls.into_iter().for_each(|(pos, ball)| {
+            self.world
+                .balls
+                .insert(BallPosition { position: pos }, ball);
+        });
+        if let Some(labels) = frame.labels {
+            self.labels = labels.into_iter().collect();
+        }
+        self.world.stats.tick = frame.tick;
+        self.world.stats.balls = self.world.balls.len();
+        Ok(())
+    }
+
+    //only the camera moves while watching
+    fn watch(&mut self, app: &mut App, delta_time: f32) {
+        let frames = self.spectator.as_mut().map(Spectator::frames);
+        let applied = frames.unwrap_or(Ok(vec![])).and_then(|frames| {
+            if !frames.is_empty() {
+                app.request_redraw();
+            }
+            frames
+                .into_iter()
+                .try_for_each(|frame| self.apply_frame(frame))
+        });
+        if let Err(e) = applied {
+            self.spectate_message = format!("stopped watching: {e}");
+            self.spectator = None;
+        }
+        self.look_around(app, delta_time);
+        self.draw(app);
+    }
+
+    fn spectator_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("spectating").show(ctx, |ui| {
+            if let Some(spectator) = &self.spectator {
+                if spectator.connected() {
+                    ui.label(format!("watching {}", spectator.address()));
+                } else {
+                    ui.label(format!("connecting to {}...", spectator.address()));
+                }
+                //nothing else wakes the window up when frames arrive
+                ui.ctx().request_repaint();
+            }
+            ui.label(format!(
+                "tick {}, {} balls",
+                self.world.stats.tick, self.world.stats.balls
+            ));
+            ui.label("drag to look around, nothing can be edited");
+            if ui.button("stop watching").clicked() {
+                self.spectator = None;
+            }
+        });
+    }
+
+    //the turbo run or watching leave only the camera to move
+    fn look_around(&mut self, app: &mut App, delta_time: f32) {
+        Simulation::update_zoom(app, delta_time);
+        self.update_pan(app, delta_time);
+        let (left, _, middle) = app.mouse_buttons();
+        if middle || (left && !app.in_ui()) {
+            self.drag_camera(app);
+        }
+    }
+
+    //asks about unsaved changes before going ahead with what's pending
+    fn ask_to_save(&mut self, app: &mut App, ctx: &egui::Context) {
+        let Some(pending) = self.pending.take() else {
+            return;
+        };
+        let question = match &pending {
+            Pending::Open(path) => format!("opening {}", path.display()),
+            Pending::Paste(_) => "pasting the level code".to_owned(),
+            Pending::Watch(address) => format!("watching {address}"),
+            Pending::Exit => "quitting".to_owned(),
+        };
+        //stays None while the user hasn't decided whether to go ahead
+        let mut go_ahead = None;
+        let response = egui::Modal::new(egui::Id::new("save changes")).show(ctx, |ui| {
+            ui.label(format!(
+                "save the changes to {} before {question}?",
+                self.world_path
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("save").clicked() {
+                    let current = self.world_path.clone();
+                    //a failed save keeps asking rather than losing the changes
+                    if self.save_as(app, Path::new(&current)) {
+                        go_ahead = Some(true);
+                    }
+                }
+                if ui.button("discard").clicked() {
+                    go_ahead = Some(true);
+                }
+                if ui.button("cancel").clicked() {
+                    go_ahead = Some(false);
+                }
+            });
+        });
+        //escape or clicking outside is a cancel
+        if response.should_close() {
+            go_ahead.get_or_insert(false);
+        }
+        match (go_ahead, pending) {
+            (None, pending) => self.pending = Some(pending),
+            (Some(false), _) => {}
+            (Some(true), Pending::Open(path)) => self.open_world(app, &path),
+            (Some(true), Pending::Paste(code)) => self.paste_level_code(app, &code),
+            (Some(true), Pending::Watch(address)) => self.start_watching(&address),
+            (Some(true), Pending::Exit) => app.exit(),
+        }
+    }
+
+    //hands everything visible to the renderer
+    fn draw(&mut self, app: &mut App) {
+        let mut animated = false;
+        Layer::ALL.into_iter().for_each(|layer| {
+            let chunks = self.get_visible_chunks(app, layer);
+            animated |= layer == Layer::Ground
+                && chunks
+                    .iter()
+                    .any(|(_, chunk)| chunk.data.contains(&u8::from(Tile::Hold)));
+            app.set_chunk_to_draw(layer, chunks);
+        });
+        let mut balls = self.get_visible_balls(app);
+        animated |= balls.iter().any(|(_, (on, _))| *on);
+        //the held ball follows the cursor until it is dropped
+        if let Some((ball, _)) = self.held_ball {
+            let pos = app.get_mouse_position_world();
+            balls.push((
+                BallPosition {
+                    position: [pos[0].floor() as i32, pos[1].floor() as i32],
+                },
+                ball,
+            ));
+        }
+        app.set_balls_to_draw(balls);
+        let mut labels = self.get_visible_labels(app);
+        if let Some((start, end)) = self.measurement {
+            const MEASURE_SIZE: f32 = 0.5;
+            labels.push(TextLabel {
+                position: [end[0] as f32 + 1.0, end[1] as f32 + 1.0],
+                size: MEASURE_SIZE,
+                text: measure_text(start, end),
+            });
+        }
+        //bookmarks are marked in the top left corner of their cell and breakpoints in the top right
+        const MARK_SIZE: f32 = 0.4;
+        let marks = self.bookmarks.iter().map(|pos| (pos, 0.0, "#")).chain(
+            self.breakpoints
+                .iter()
+                .map(|pos| (pos, 1.0 - MARK_SIZE * GLYPH_ASPECT, "!")),
+        );
+        labels.extend(marks.map(|(pos, x, mark)| TextLabel {
+            position: [pos[0] as f32 + x, pos[1] as f32 + 1.0 - MARK_SIZE],
+            size: MARK_SIZE,
+            text: mark.to_owned(),
+        }));
+        if self.symmetry != Symmetry::Off {
+            //marking the axis cell so mirrored strokes aren't a surprise
+            const MARKER_SIZE: f32 = 0.8;
+            labels.push(TextLabel {
+                position: [
+                    self.symmetry_axis[0] as f32 + (1.0 - MARKER_SIZE * GLYPH_ASPECT) / 2.0,
+                    self.symmetry_axis[1] as f32 + (1.0 - MARKER_SIZE) / 2.0,
+                ],
+                size: MARKER_SIZE,
+                text: "+".to_owned(),
+            });
+        }
+        app.set_text_to_draw(labels);
+        app.set_selection(self.selection);
+        let pos = app.get_mouse_position_world();
+        let hovered = (!app.in_ui()).then_some([pos[0].floor() as i32, pos[1].floor() as i32]);
+        app.set_highlighted_cell(hovered);
+        app.set_animating(animated || hovered.is_some());
+        self.last_mouse_pos = app.get_mouse_position_world();
+        app.set_title(self.window_title());
+    }
+
+    //asks about unsaved changes first
+    fn request_open(&mut self, app: &mut App, path: PathBuf) {
+        if self.dirty {
+            self.pending = Some(Pending::Open(path));
+        } else {
+            self.open_world(app, &path);
+        }
+    }
+
+    fn upload_world(&mut self, app: &App, name: &str) {
+        let settings = app.settings();
+        let author = match settings.level_author.trim() {
+            "" => "anonymous",
+            author => author,
+        };
+        match self.world_save(app).to_bytes(true) {
+            Ok(bytes) => self
+                .level_client
+                .upload(&settings.level_server, name, author, bytes),
+            Err(e) => log::error!("couldn't save the world for uploading: {e}"),
+        }
+    }
+
+    //worlds replace the current one once the changes are dealt with,
+    //blueprints and patterns become the stamp
+    fn open_dropped(&mut self, app: &mut App, path: PathBuf) {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(WORLD_EXTENSION) => self.request_open(app, path),
+            Some(BLUEPRINT_EXTENSION) => match Blueprint::load(&path) {
+                Ok(blueprint) => {
+                    self.stamp = Some(blueprint);
+                    self.current_tool = Tool::Stamp;
+                }
+                Err(e) => log::error!("couldn't load {}: {e}", path.display()),
+            },
+            Some("rle" | "csv" | "txt" | "png") => {
+                self.importer.path = path.to_string_lossy().into_owned();
+                self.importer.read();
+                if let Some(blueprint) = self.importer.stamp() {
+                    self.stamp = Some(blueprint);
+                    self.current_tool = Tool::Stamp;
+                }
+            }
+            _ => log::warn!("don't know what to do with {}", path.display()),
+        }
+    }
+
+    //the world path is left alone so the next normal save still goes to the real file
+    fn quicksave(&mut self, app: &App) {
+        match world::next_quicksave_path().and_then(|path| {
+            self.save_world(app, &path)?;
+            Ok(path)
+        }) {
+            Ok(path) => log::info!("quicksaved to {}", path.display()),
+            Err(e) => log::error!("couldn't quicksave: {e}"),
+        }
+    }
+
+    //into its own file, the world's own file is only written when asked
+    fn autosave(&mut self, app: &App) {
+        let Some(minutes) = app.settings().autosave_minutes else {
+            return;
+        };
+        if !self.autosave_pending || self.last_autosave.elapsed().as_secs() < minutes as u64 * 60 {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        self.autosave_pending = false;
+        let path = world::autosave_path();
+        match path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| self.save_world(app, &path))
+        {
+            Ok(()) => log::info!("autosaved to {}", path.display()),
+            Err(e) => log::error!("couldn't autosave: {e}"),
+        }
+    }
+
+    fn quickload(&mut self, app: &mut App, path: &Path) {
+        match self.load_world(app, path) {
+            Ok(()) => log::info!("quickloaded {}", path.display()),
+            Err(e) => log::error!("couldn't quickload {}: {e}", path.display()),
+        }
+    }
+
+    //cells held by pressure plates go back to what they were before
+    fn original_chunks(&self) -> HashMap<ChunkPosition, Chunk> {
+        let mut chunks = self.world.chunks.clone();
+        self.world
+            .pressed
+            .iter()
+            .for_each(|(pos, tile)| write_tile(&mut chunks, *pos, *tile));
+        chunks
+    }
+
+    //runs a copy of the world once for every combination of the marked inputs
+    fn truth_table(&self, updates: u32) -> anyhow::Result<TruthTable> {
+        let probes = |kind: Probe| {
+            let mut cells: Vec<([i32; 2], String)> = self
+                .world
+                .tile_data
+                .iter()
+                .filter(|(_, data)| data.probe == Some(kind))
+                .map(|(pos, data)| (*pos, probe_name(*pos, &data.note)))
+                .collect();
+            cells.sort();
+            cells
+        };
+        let (inputs, outputs) = (probes(Probe::Input), probes(Probe::Output));
+        if outputs.is_empty() {
+            anyhow::bail!("no cells are marked as outputs");
+        }
+        if inputs.len() > MAX_INPUTS {
+            anyhow::bail!(
+                "{} cells are marked as inputs but at most {MAX_INPUTS} can be swept",
+                inputs.len()
+            );
+        }
+        let rows = (0..1u32 << inputs.len())
+            .map(|combination| {
+                let values: Vec<bool> = (0..inputs.len())
+                    .map(|i| (combination >> (inputs.len() - 1 - i)) & 1 == 1)
+                    .collect();
+                let mut run = World::default();
+                run.set_rules(self.world.rules().clone())?;
+                run.chunks = self.original_chunks();
+                run.balls = self.world.balls.clone();
+                run.ages = self.world.ages.clone();
+                run.tile_data = self.world.tile_data.clone();
+                inputs
+                    .iter()
+                    .zip(&values)
+                    .for_each(|((pos, _), on)| run.set_ball(*pos, (*on, Direction::Right)));
+                let mut seen = vec![None; outputs.len()];
+                (0..updates).for_each(|_| {
+                    run.full_step();
+                    outputs
+                        .iter()
+                        .zip(&mut seen)
+                        .filter(|(_, seen)| seen.is_none())
+                        .for_each(|((pos, _), seen)| *seen = run.get_ball(*pos).map(|(on, _)| on));
+                });
+                Ok((values, seen))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(TruthTable {
+            inputs: inputs.into_iter().map(|(_, name)| name).collect(),
+            outputs: outputs.into_iter().map(|(_, name)| name).collect(),
+            rows,
+        })
+    }
+
+    //written next to the world file with the given extension
+    fn export_truth_table(&mut self, netlist: bool) {
+        let (extension, kind) = if netlist {
+            ("net", "netlist")
+        } else {
+            ("csv", "truth table")
+        };
+        let path = Path::new(&self.world_path).with_extension(extension);
+        self.analysis_summary = match self.truth_table(self.analysis_updates).and_then(|table| {
+            let text = if netlist {
+                table.to_netlist()
+            } else {
+                table.to_csv()
+            };
+            std::fs::write(&path, text)?;
+            Ok(table.rows.len())
+        }) {
+            Ok(rows) => format!("wrote the {kind} for {rows} runs to {}", path.display()),
+            Err(e) => format!("couldn't export the {kind}: {e}"),
+        };
+    }
+
+    fn load_world(&mut self, app: &mut App, path: &Path) -> anyhow::Result<()> {
+        self.apply_save(app, WorldSave::read(path)?)
+    }
+
+    //a pasted world has no file yet, so saving it doesn't overwrite the previous one
+    fn load_code(&mut self, app: &mut App, code: &str) -> anyhow::Result<()> {
+        self.apply_save(app, WorldSave::from_code(code)?)?;
+        self.world_path = DEFAULT_WORLD_PATH.to_owned();
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn copy_level_code(&mut self, app: &App, ctx: &egui::Context) {
+        self.level_code_summary = match self.world_save(app).to_code() {
+            Ok(code) => {
+                ctx.copy_text(code.clone());
+                self.level_code = code;
+                format!("copied {} characters", self.level_code.len())
+            }
+            Err(e) => format!("couldn't make a level code: {e}"),
+        };
+    }
+
+    fn paste_level_code(&mut self, app: &mut App, code: &str) {
+        self.level_code_summary = match self.load_code(app, code) {
+            Ok(()) => "loaded the level code".to_owned(),
+            Err(e) => format!("couldn't load the level code: {e}"),
+        };
+    }
+
+    fn apply_save(&mut self, app: &mut App, save: WorldSave) -> anyhow::Result<()> {
+        let camera = self.replace_world(save)?;
+        self.bookmarks.clear();
+        self.breakpoints.clear();
+        //putting the camera back where it was when saved
+        self.camera_target = None;
+        let camera_uniform = app.camera_mut();
+        camera_uniform.pos = camera.pos;
+        camera_uniform.width = camera.width;
+        *app.scroll_level_mut() = -camera.width.log2() * SCROLL_SPEED;
+        app.request_redraw();
+        Ok(())
+    }
+
+    //everything a save holds except where the camera was, which is returned,
+    //all of it is checked first so a broken save leaves the world as it was
+    fn replace_world(&mut self, mut save: WorldSave) -> anyhow::Result<CameraSave> {
+        let overlay = save::to_chunks(std::mem::take(&mut save.overlay))?;
+        let (labels, camera) = (std::mem::take(&mut save.labels), save.camera);
+        self.world.load(save)?;
+        self.history.clear();
+        self.dirty = false;
+        self.playing = false;
+        self.turbo_left = 0;
+        self.edit_state = None;
+        self.overlay = overlay;
+        if let Some(changed) = self.changed() {
+            changed.everything = true;
+        }
+        self.stats_history.clear();
+        self.ball_inspector = None;
+        self.labels = labels.into_iter().collect();
+        Ok(camera)
+    }
+
+    //for running without a window, the camera the world was saved with is ignored
+    pub fn load_world_bytes(&mut self, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.replace_world(WorldSave::from_bytes(bytes)?)?;
+        Ok(())
+    }
+
+    //replaces the whole world, so there is nothing left to undo
+    fn new_world(&mut self, app: &mut App, template: &Template) -> anyhow::Result<()> {
+        let tiles = template.tiles()?;
+        if let Some(changed) = self.changed() {
+            changed.everything = true;
+        }
+        self.world.clear();
+        self.overlay.clear();
+        self.stats_history.clear();
+        self.ball_inspector = None;
+        self.playing = false;
+        self.turbo_left = 0;
+        self.edit_state = None;
+        self.labels.clear();
+        self.selection = None;
+        self.bookmarks.clear();
+        self.breakpoints.clear();
+        self.set_tile([0, 0], Tile::Empty);
+        tiles
+            .into_iter()
+            .for_each(|(pos, tile)| self.set_tile(pos, tile));
+        template
+            .balls()
+            .into_iter()
+            .for_each(|(pos, ball)| self.set_ball(pos, ball));
+        template
+            .labels
+            .iter()
+            .for_each(|label| self.set_label(label.pos, &label.text));
+        self.history.clear();
+        self.dirty = false;
+        self.frame_all(app);
+        Ok(())
+    }
+
+    fn drag_camera(&mut self, app: &mut App) {
+        self.camera_target = None;
         let curr = app.get_mouse_position_world();
         if self.last_mouse_pos != curr {
             let pos = &mut app.camera_mut().pos;
@@ -72,7 +1396,7 @@ impl Simulation {
         }
     }
 
-    fn get_visible_chunks(&self, app: &App) -> Vec<(ChunkPosition, Chunk)> {
+    fn get_visible_chunks(&self, app: &App, layer: Layer) -> Vec<(ChunkPosition, Chunk)> {
         let view_size = app.camera().world_viewport_size();
         let center = app.camera().pos;
         let ranges: Vec<RangeInclusive<i32>> = center
@@ -87,7 +1411,7 @@ impl Simulation {
         ranges[0].clone().for_each(|x| {
             ranges[1].clone().for_each(|y| {
                 let pos = ChunkPosition { position: [x, y] };
-                if let Some(chunk) = self.chunks.get(&pos) {
+                if let Some(chunk) = self.layer_chunks(layer).get(&pos) {
                     out.push((pos, *chunk));
                 }
             });
@@ -96,6 +1420,10 @@ impl Simulation {
     }
 
     fn get_visible_balls(&self, app: &App) -> Vec<(BallPosition, (bool, Direction))> {
+        //balls are smaller than a pixel this far out, the tiles alone show the build
+        if app.camera().pixels_per_cell() < 1.0 {
+            return vec![];
+        }
         let view_size = app.camera().world_viewport_size();
         let center = app.camera().pos;
         let ranges: Vec<RangeInclusive<i32>> = center
@@ -118,206 +1446,1125 @@ impl Simulation {
         out
     }
 
-    fn set_tile(&mut self, pos: [i32; 2], tile: Tile) {
-        self.chunks
-            .entry(ChunkPosition {
-                position: [
-                    pos[0].div_euclid(CHUNK_SIZE as i32),
-                    pos[1].div_euclid(CHUNK_SIZE as i32),
-                ],
+    fn get_visible_labels(&self, app: &App) -> Vec<TextLabel> {
+        let view_size = app.camera().world_viewport_size();
+        let center = app.camera().pos;
+        self.labels
+            .iter()
+            .filter(|(pos, _)| {
+                (0..2).all(|i| (pos[i] as f32 + 0.5 - center[i]).abs() <= view_size[i] / 2.0 + 1.0)
             })
-            .or_insert(Chunk {
-                data: from_fn(|_| u8::from(Tile::Empty)),
+            .map(|(pos, text)| {
+                //shrink long labels so they stay inside their cell, one glyph is drawn per char
+                let size = (1.0 / (text.chars().count() as f32 * GLYPH_ASPECT)).min(0.6);
+                TextLabel {
+                    position: [pos[0] as f32, pos[1] as f32 + (1.0 - size) / 2.0],
+                    size,
+                    text: text.clone(),
+                }
             })
-            .set_tile(
-                [
-                    pos[0].rem_euclid(CHUNK_SIZE as i32) as u32,
-                    pos[1].rem_euclid(CHUNK_SIZE as i32) as u32,
-                ],
-                u8::from(tile),
-            );
+            .collect()
     }
 
-    fn get_tile(&self, pos: [i32; 2]) -> Tile {
-        self.chunks
-            .get(&ChunkPosition {
-                position: [
-                    pos[0].div_euclid(CHUNK_SIZE as i32),
-                    pos[1].div_euclid(CHUNK_SIZE as i32),
-                ],
-            })
-            .and_then(|chunk| {
-                chunk
-                    .get_tile([
-                        pos[0].rem_euclid(CHUNK_SIZE as i32) as u32,
-                        pos[1].rem_euclid(CHUNK_SIZE as i32) as u32,
-                    ])
-                    .try_into()
-                    .ok()
-            })
-            .unwrap_or(Tile::Empty)
+    fn set_label(&mut self, pos: [i32; 2], text: &str) {
+        self.history
+            .record_label(pos, self.labels.get(&pos).cloned());
+        self.record_macro_op(pos, MacroOp::SetLabel(text.to_owned()));
+        if text.is_empty() {
+            self.labels.remove(&pos);
+        } else {
+            self.labels.insert(pos, text.to_owned());
+        }
+        if let Some(changed) = self.changed() {
+            changed.labels = true;
+        }
     }
 
-    fn set_ball(&mut self, pos: [i32; 2], on: (bool, Direction)) {
-        self.balls.insert(BallPosition { position: pos }, on);
+    pub fn tile_data(&self, pos: [i32; 2]) -> Option<&TileData> {
+        self.world.tile_data(pos)
+    }
+
+    //for state behaviors keep while running, edits go through set_tile_data so they can be undone
+    pub fn tile_data_mut(&mut self, pos: [i32; 2]) -> &mut TileData {
+        self.world.tile_data_mut(pos)
+    }
+
+    fn set_tile_data(&mut self, pos: [i32; 2], data: TileData) {
+        self.history
+            .record_tile_data(pos, self.world.tile_data(pos).cloned());
+        self.world.set_tile_data(pos, data);
+    }
+
+    fn layer_chunks(&self, layer: Layer) -> &HashMap<ChunkPosition, Chunk> {
+        match layer {
+            Layer::Ground => &self.world.chunks,
+            Layer::Overlay => &self.overlay,
+        }
+    }
+
+    pub fn set_tile(&mut self, pos: [i32; 2], tile: Tile) {
+        self.set_layer_tile(Layer::Ground, pos, tile);
+    }
+
+    //the world only steps the ground, the overlay is kept here
+    fn set_layer_tile(&mut self, layer: Layer, pos: [i32; 2], tile: Tile) {
+        self.record_macro_op(pos, MacroOp::SetTile(layer, tile));
+        let old = match layer {
+            Layer::Ground => self.world.set_tile(pos, tile),
+            Layer::Overlay => {
+                if let Some(changed) = self.changed() {
+                    changed.chunks.insert((layer, chunk_of(pos)));
+                }
+                let old = self.overlay.get_tile(pos);
+                write_tile(&mut self.overlay, pos, tile);
+                old
+            }
+        };
+        self.history.record_tile(layer, pos, old);
     }
 
-    fn get_ball(&self, pos: [i32; 2]) -> Option<(bool, Direction)> {
-        self.balls.get(&BallPosition { position: pos }).copied()
+    pub fn get_tile(&self, pos: [i32; 2]) -> Tile {
+        self.world.get_tile(pos)
+    }
+
+    fn get_layer_tile(&self, layer: Layer, pos: [i32; 2]) -> Tile {
+        self.layer_chunks(layer).get_tile(pos)
+    }
+
+    pub fn set_ball(&mut self, pos: [i32; 2], on: (bool, Direction)) {
+        self.history.record_ball(pos, self.get_ball(pos));
+        self.record_macro_op(pos, MacroOp::SetBall(on));
+        self.world.set_ball(pos, on);
+    }
+
+    pub fn remove_ball(&mut self, pos: [i32; 2]) {
+        self.history.record_ball(pos, self.get_ball(pos));
+        self.record_macro_op(pos, MacroOp::RemoveBall);
+        self.world.remove_ball(pos);
+    }
+
+    fn record_macro_op(&mut self, pos: [i32; 2], op: MacroOp) {
+        if let Some(recording) = &mut self.macro_recording {
+            recording.push((pos, op));
+        }
+    }
+
+    fn play_macro(&mut self, anchor: [i32; 2]) {
+        let Some(edit_macro) = self.current_macro.and_then(|i| self.macros.get(i)).cloned() else {
+            return;
+        };
+        edit_macro.ops.into_iter().for_each(|(pos, op)| {
+            let pos = [pos[0] + anchor[0], pos[1] + anchor[1]];
+            match op {
+                MacroOp::SetTile(layer, tile) => self.set_layer_tile(layer, pos, tile),
+                MacroOp::SetBall(ball) => self.set_ball(pos, ball),
+                MacroOp::RemoveBall => self.remove_ball(pos),
+                MacroOp::SetLabel(text) => self.set_label(pos, &text),
+            }
+        });
+    }
+
+    fn erase(&mut self, pos: [i32; 2]) {
+        if self.get_ball(pos).is_some() {
+            self.remove_ball(pos);
+        }
+        if self.labels.contains_key(&pos) {
+            self.set_label(pos, "");
+        }
+        if self.layer == Layer::Ground && self.world.tile_data.contains_key(&pos) {
+            self.set_tile_data(pos, TileData::default());
+        }
+        //only touching chunks that exist so erasing empty space doesn't allocate them
+        if self.get_layer_tile(self.layer, pos) != Tile::Empty {
+            self.set_layer_tile(self.layer, pos, Tile::Empty);
+        }
+    }
+
+    pub fn get_ball(&self, pos: [i32; 2]) -> Option<(bool, Direction)> {
+        self.world
+            .balls
+            .get(&BallPosition { position: pos })
+            .copied()
     }
 
     fn handle_mouse(&mut self, app: &mut App) {
-        if app.mouse_buttons().0 {
-            if app.is_key_pressed(KeyCode::ShiftLeft) {
+        let (left, right, middle) = app.mouse_buttons();
+        if middle {
+            self.drag_camera(app);
+        } else if left && app.is_action_active(Action::PanModifier) {
+            if !app.in_ui() {
                 self.drag_camera(app);
-            } else {
-                let pos = app.get_mouse_position_world();
-                let w_pos = [pos[0].floor() as i32, pos[1].floor() as i32];
-                match self.current_tool {
-                    Tool::BallTool(on) => self.set_ball(w_pos, (on, Direction::Right)),
-                    Tool::TileTool(tile) => self.set_tile(w_pos, tile),
-                }
-            }
-        }
-    }
-
-    fn sim_step(
-        &mut self,
-        dir: Direction,
-        dont_move: &mut HashSet<[i32; 2]>,
-        duplicated: &mut HashSet<[i32; 2]>,
-    ) {
-        let mut balls_to_update = vec![];
-        let mut balls_to_remove = vec![];
-        let mut balls_to_duplicate = HashSet::new();
-        self.balls.iter_mut().for_each(|(pos, on)| {
-            if !dont_move.contains(&pos.position) {
-                let tile = self.chunks.get_tile(pos.position);
-                on.1 = match tile {
-                    Tile::Up => Direction::Up,
-                    Tile::Down => Direction::Down,
-                    Tile::Left => Direction::Left,
-                    Tile::Right => Direction::Right,
-                    Tile::Destroy => {
-                        balls_to_remove.push(*pos);
-                        return;
-                    }
-                    Tile::Hold => {
-                        return;
-                    }
-                    Tile::FilterR => {
-                        if on.0 {
-                            Direction::Left
-                        } else {
-                            Direction::Right
-                        }
-                    }
-                    Tile::FilterL => {
-                        if !on.0 {
-                            Direction::Left
-                        } else {
-                            Direction::Right
-                        }
+            }
+        } else if (left || right) && !app.in_ui() {
+            let pos = app.get_mouse_position_world();
+            let cell = [pos[0].floor() as i32, pos[1].floor() as i32];
+            //a right click is a menu until the pointer leaves the cell, then it erases as usual
+            let mut dragged_from = None;
+            if right && !left && !self.painting {
+                let (start, start_pos) = *self.right_press.get_or_insert((cell, pos));
+                if start == cell {
+                    return;
+                }
+                self.right_press = None;
+                dragged_from = Some((start, start_pos));
+            }
+            if !self.painting {
+                let verb = if left { "" } else { "erase with " };
+                self.history.begin(format!("{verb}{:?}", self.current_tool));
+            }
+            let recorded = self.history.pending();
+            if let Some((start, start_pos)) = dragged_from {
+                self.use_tool(start_pos, start, false);
+                self.painting = true;
+                self.last_mouse_pos = start_pos;
+            }
+            self.use_tool(pos, cell, left);
+            if left && self.history.pending() > recorded {
+                self.effects.push(Effect::Place);
+            }
+            self.painting = true;
+            app.request_redraw();
+            return;
+        }
+        if !right {
+            if let Some((cell, _)) = self.right_press.take() {
+                self.cell_menu = Some((cell, None));
+                app.request_redraw();
+            }
+        }
+        if self.held_ball.is_some() {
+            self.drop_ball();
+            app.request_redraw();
+        }
+        if self.painting {
+            match self.current_tool {
+                Tool::Scatter => self.scatter_balls(),
+                Tool::Fill(on) => self.fill_balls(on),
+                _ => {}
+            }
+            self.history.commit();
+            app.request_redraw();
+        }
+        self.painting = false;
+    }
+
+    //what a press of the left or right button does at a cell with the current tool
+    fn use_tool(&mut self, pos: [f32; 2], cell: [i32; 2], left: bool) {
+        let right = !left;
+        match self.current_tool {
+            Tool::Select | Tool::Scatter | Tool::Fill(_) => self.drag_selection(cell, right),
+            //one copy per click rather than a stream of them while dragging
+            Tool::Stamp if left && !self.painting => self.place_stamp(cell),
+            Tool::Stamp => {}
+            Tool::MoveBall if left && !self.painting => self.pick_up_ball(cell),
+            Tool::MoveBall => {}
+            Tool::PlayMacro if left && !self.painting => self.play_macro(cell),
+            Tool::PlayMacro => {}
+            Tool::Inspect if left && !self.painting => self.inspect(cell),
+            Tool::Inspect => {}
+            Tool::Measure if right => self.measurement = None,
+            Tool::Measure => {
+                let start = self
+                    .measurement
+                    .filter(|_| self.painting)
+                    .map_or(cell, |(start, _)| start);
+                self.measurement = Some((start, cell));
+            }
+            //left places the current tool, right erases what that tool places
+            _ => self.paint(pos, !left),
+        }
+    }
+
+    fn cell_menu_ui(&mut self, ctx: &egui::Context) {
+        let Some((cell, at)) = self.cell_menu else {
+            return;
+        };
+        let opened = at.is_none();
+        let at = at
+            .or_else(|| ctx.input(|input| input.pointer.latest_pos()))
+            .unwrap_or_default();
+        self.cell_menu = Some((cell, Some(at)));
+        let mut close = false;
+        let response = egui::Area::new(egui::Id::new("cell menu"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(at)
+            .show(ctx, |ui| {
+                egui::Frame::menu(ui.style()).show(ui, |ui| {
+                    ui.label(format!("{cell:?}"));
+                    ui.separator();
+                    if ui.button("inspect").clicked() {
+                        self.inspect(cell);
+                        close = true;
                     }
-                    Tile::FilterU => {
-                        if on.0 {
-                            Direction::Down
-                        } else {
-                            Direction::Up
-                        }
+                    //an empty cell with a ball on it gives the ball tool instead
+                    let tool = match (self.get_layer_tile(self.layer, cell), self.get_ball(cell)) {
+                        (Tile::Empty, Some((on, _))) => Tool::BallTool(on),
+                        (tile, _) => Tool::TileTool(tile),
+                    };
+                    if ui.button(format!("use {tool:?} as the tool")).clicked() {
+                        self.current_tool = tool;
+                        close = true;
                     }
-                    Tile::FilterD => {
-                        if !on.0 {
-                            Direction::Down
-                        } else {
-                            Direction::Up
-                        }
+                    if ui.button("clear").clicked() {
+                        self.history.begin("clear cell");
+                        self.erase(cell);
+                        self.history.commit();
+                        close = true;
                     }
-                    Tile::DuplicateH => {
-                        if matches!(dir, Direction::Right | Direction::Left) {
-                            if !duplicated.contains(&pos.position) {
-                                balls_to_duplicate.insert(*pos);
-                            }
-                            dir
+                    let bookmarked = self.bookmarks.contains(&cell);
+                    let text = if bookmarked {
+                        "remove the camera bookmark"
+                    } else {
+                        "set as a camera bookmark"
+                    };
+                    if ui.button(text).clicked() {
+                        if bookmarked {
+                            self.bookmarks.retain(|pos| *pos != cell);
                         } else {
-                            return;
+                            self.bookmarks.push(cell);
                         }
+                        close = true;
                     }
-                    Tile::DuplicateV => {
-                        if matches!(dir, Direction::Up | Direction::Down) {
-                            if !duplicated.contains(&pos.position) {
-                                balls_to_duplicate.insert(*pos);
-                            }
-                            dir
-                        } else {
-                            return;
+                    let text = if self.breakpoints.contains(&cell) {
+                        "remove the breakpoint"
+                    } else {
+                        "add a breakpoint"
+                    };
+                    if ui.button(text).clicked() {
+                        if !self.breakpoints.remove(&cell) {
+                            self.breakpoints.insert(cell);
                         }
+                        close = true;
                     }
-                    _ => on.1,
-                };
-                if on.1 == dir {
-                    balls_to_update.push(pos.position);
+                });
+            })
+            .response;
+        //the click that opened it doesn't count as clicking away
+        if close || (!opened && response.clicked_elsewhere()) {
+            self.cell_menu = None;
+        }
+    }
+
+    fn inspect(&mut self, cell: [i32; 2]) {
+        self.tile_inspector = Some(cell);
+        if let Some(ball) = self.get_ball(cell) {
+            self.ball_inspector = Some(BallInspector::new(cell, ball));
+            self.world.follow(Some(cell));
+        }
+    }
+
+    fn pick_up_ball(&mut self, cell: [i32; 2]) {
+        if let Some(ball) = self.get_ball(cell) {
+            self.remove_ball(cell);
+            self.held_ball = Some((ball, cell));
+        }
+    }
+
+    //occupied cells send the ball back where it came from
+    fn drop_ball(&mut self) {
+        let Some((ball, origin)) = self.held_ball.take() else {
+            return;
+        };
+        let pos = self.last_mouse_pos;
+        let cell = [pos[0].floor() as i32, pos[1].floor() as i32];
+        if self.get_ball(cell).is_none() {
+            self.set_ball(cell, ball);
+        } else {
+            self.set_ball(origin, ball);
+        }
+    }
+
+    fn paint(&mut self, pos: [f32; 2], erase: bool) {
+        //fast drags skip cells between events so the whole stroke gets painted
+        let from = if self.painting {
+            self.last_mouse_pos
+        } else {
+            pos
+        };
+        //labels are always placed one cell at a time
+        let brush_size = match self.current_tool {
+            Tool::LabelTool => 1,
+            _ => self.brush_size as i32,
+        };
+        let (symmetry, axis) = (self.symmetry, self.symmetry_axis);
+        cells_on_line(from, pos)
+            .into_iter()
+            .flat_map(|center| brush_cells(center, brush_size))
+            .flat_map(|w_pos| symmetry.mirror(w_pos, axis))
+            .for_each(|(w_pos, x, y)| match (self.current_tool.clone(), erase) {
+                (Tool::BallTool(on), false) => {
+                    let dir = if x { Direction::Left } else { Direction::Right };
+                    self.set_ball(w_pos, (on, dir))
+                }
+                (Tool::BallTool(_), true) => self.remove_ball(w_pos),
+                (Tool::TileTool(tile), false) => {
+                    self.set_layer_tile(self.layer, w_pos, tile.mirrored(x, y))
                 }
+                (Tool::TileTool(_), true) => self.set_layer_tile(self.layer, w_pos, Tile::Empty),
+                (Tool::LabelTool, false) => self.set_label(w_pos, &self.label_text.clone()),
+                (Tool::LabelTool, true) => self.set_label(w_pos, ""),
+                (Tool::Eraser, _) => self.erase(w_pos),
+                (
+                    Tool::Select
+                    | Tool::Stamp
+                    | Tool::MoveBall
+                    | Tool::Scatter
+                    | Tool::Fill(_)
+                    | Tool::Measure
+                    | Tool::Inspect
+                    | Tool::PlayMacro,
+                    _,
+                ) => {}
+            });
+    }
+
+    //left drags out a rectangle from where the press started, right clears it
+    fn drag_selection(&mut self, cell: [i32; 2], clear: bool) {
+        if clear {
+            self.selection = None;
+            return;
+        }
+        if !self.painting {
+            self.selection_anchor = cell;
+        }
+        let anchor = self.selection_anchor;
+        self.selection = Some((
+            [anchor[0].min(cell[0]), anchor[1].min(cell[1])],
+            [anchor[0].max(cell[0]), anchor[1].max(cell[1])],
+        ));
+    }
+
+    //random cells of the selection get random balls, blocks are skipped
+    fn scatter_balls(&mut self) {
+        let Some((min, max)) = self.selection else {
+            return;
+        };
+        (0..self.scatter_count).for_each(|_| {
+            let pos = [
+                fastrand::i32(min[0]..=max[0]),
+                fastrand::i32(min[1]..=max[1]),
+            ];
+            if !self.get_tile(pos).is_solid() {
+                self.set_ball(pos, (fastrand::bool(), Direction::Right));
+            }
+        });
+    }
+
+    fn fill_balls(&mut self, on: bool) {
+        let Some((min, max)) = self.selection else {
+            return;
+        };
+        (min[0]..=max[0])
+            .flat_map(|x| (min[1]..=max[1]).map(move |y| [x, y]))
+            .filter(|pos| !self.get_tile(*pos).is_solid())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .for_each(|pos| self.set_ball(pos, (on, Direction::Right)));
+    }
+
+    //returns how many tiles of the current layer changed
+    fn replace_tiles(&mut self, from: Tile, to: Tile, in_selection: bool) -> usize {
+        let layer = self.layer;
+        let positions: Vec<[i32; 2]> = match self.selection.filter(|_| in_selection) {
+            Some((min, max)) => (min[0]..=max[0])
+                .flat_map(|x| (min[1]..=max[1]).map(move |y| [x, y]))
+                .filter(|pos| self.get_layer_tile(layer, *pos) == from)
+                .collect(),
+            None => self
+                .layer_chunks(layer)
+                .iter()
+                .flat_map(|(chunk_pos, chunk)| {
+                    (0..CHUNK_SIZE as u32)
+                        .flat_map(|x| (0..CHUNK_SIZE as u32).map(move |y| [x, y]))
+                        .filter(|pos| chunk.get_tile(*pos) == u8::from(from))
+                        .map(|pos| {
+                            [
+                                chunk_pos.position[0] * CHUNK_SIZE as i32 + pos[0] as i32,
+                                chunk_pos.position[1] * CHUNK_SIZE as i32 + pos[1] as i32,
+                            ]
+                        })
+                })
+                .collect(),
+        };
+        self.history
+            .begin(format!("replace {} {from:?} with {to:?}", positions.len()));
+        positions
+            .iter()
+            .for_each(|pos| self.set_layer_tile(layer, *pos, to));
+        self.history.commit();
+        positions.len()
+    }
+
+    //the bottom left corner goes at the selection if there is one, otherwise the middle of the screen
+    fn generate(&mut self, app: &App) {
+        let (tiles, balls) = self.generator.generate(&mut fastrand::Rng::new());
+        let origin = match self.selection {
+            Some((min, _)) => min,
+            None => {
+                let center = app.camera().pos;
+                [center[0].floor() as i32, center[1].floor() as i32]
             }
+        };
+        let offset = |pos: [i32; 2]| [pos[0] + origin[0], pos[1] + origin[1]];
+        self.history.begin(format!("generate {:?}", self.generator));
+        tiles
+            .into_iter()
+            .for_each(|(pos, tile)| self.set_tile(offset(pos), tile));
+        balls
+            .into_iter()
+            .for_each(|(pos, ball)| self.set_ball(offset(pos), ball));
+        self.history.commit();
+    }
+
+    //puts back what the edit recorded, returning what was there so it can be reapplied
+    fn apply_edit(&mut self, edit: Edit) -> Edit {
+        let mut inverse = Edit {
+            description: edit.description,
+            ..Default::default()
+        };
+        edit.tiles.into_iter().for_each(|((layer, pos), tile)| {
+            inverse
+                .tiles
+                .insert((layer, pos), self.get_layer_tile(layer, pos));
+            self.set_layer_tile(layer, pos, tile);
         });
-        balls_to_remove.into_iter().for_each(|pos| {
-            self.balls.remove(&pos);
+        edit.balls.into_iter().for_each(|(pos, ball)| {
+            inverse.balls.insert(pos, self.get_ball(pos));
+            match ball {
+                Some(ball) => self.set_ball(pos, ball),
+                None => self.remove_ball(pos),
+            }
         });
-        balls_to_update.sort_by(|a, b| match dir {
-            Direction::Up => a[1].cmp(&b[1]),
-            Direction::Down => b[1].cmp(&a[1]),
-            Direction::Left => b[0].cmp(&a[0]),
-            Direction::Right => a[0].cmp(&b[0]),
+        edit.labels.into_iter().for_each(|(pos, text)| {
+            inverse.labels.insert(pos, self.labels.get(&pos).cloned());
+            self.set_label(pos, &text.unwrap_or_default());
         });
-        let mut failed_holds = HashSet::new();
-        while let Some(pos) = balls_to_update.pop() {
-            let next_pos = BallPosition {
-                position: match dir {
-                    Direction::Up => [pos[0], pos[1] + 1],
-                    Direction::Down => [pos[0], pos[1] - 1],
-                    Direction::Left => [pos[0] - 1, pos[1]],
-                    Direction::Right => [pos[0] + 1, pos[1]],
-                },
-            };
-            if !self.balls.contains_key(&next_pos) {
-                if self.get_tile(next_pos.position) != Tile::Block {
-                    let ball = self
-                        .balls
-                        .remove(&BallPosition { position: pos })
-                        .expect("we are trying to move a ball that doesn't exist");
-                    self.balls.insert(next_pos, ball);
-                    dont_move.insert(next_pos.position);
-                    if matches!(self.get_tile(pos), Tile::DuplicateH | Tile::DuplicateV) {
-                        duplicated.insert(pos);
-                        if balls_to_duplicate.contains(&BallPosition { position: pos }) {
-                            self.balls.insert(BallPosition { position: pos }, ball);
-                        }
-                    }
+        edit.tile_data.into_iter().for_each(|(pos, data)| {
+            inverse
+                .tile_data
+                .insert(pos, self.world.tile_data.get(&pos).cloned());
+            self.set_tile_data(pos, data.unwrap_or_default());
+        });
+        inverse
+    }
+
+    fn undo(&mut self) {
+        if let Some(edit) = self.history.pop_undo() {
+            let inverse = self.apply_edit(edit);
+            self.history.push_redo(inverse);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(edit) = self.history.pop_redo() {
+            let inverse = self.apply_edit(edit);
+            self.history.push_undo(inverse);
+        }
+    }
+
+    //undoes or redoes until `applied` edits are left to undo
+    fn jump_in_history(&mut self, applied: usize) {
+        self.history.commit();
+        while self.history.undo_list().len() > applied && self.history.can_undo() {
+            self.undo();
+        }
+        while self.history.undo_list().len() < applied && self.history.can_redo() {
+            self.redo();
+        }
+    }
+
+    fn history_ui(&mut self, ui: &mut egui::Ui) {
+        let undo: Vec<(String, usize)> = self
+            .history
+            .undo_list()
+            .iter()
+            .map(|edit| (edit.description.clone(), edit.cells()))
+            .collect();
+        let redo: Vec<(String, usize)> = self
+            .history
+            .redo_list()
+            .iter()
+            .rev()
+            .map(|edit| (edit.description.clone(), edit.cells()))
+            .collect();
+        ui.label("click an entry to go back or forward to it, editing after going back drops the entries below");
+        let mut jump = None;
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if ui.selectable_label(undo.is_empty(), "start").clicked() {
+                    jump = Some(0);
                 }
-            } else if self.get_tile(next_pos.position) == Tile::Hold
-                && !failed_holds.contains(&next_pos.position)
+                undo.iter()
+                    .enumerate()
+                    .for_each(|(i, (description, cells))| {
+                        let current = i + 1 == undo.len();
+                        if ui
+                            .selectable_label(current, format!("{description} ({cells})"))
+                            .clicked()
+                        {
+                            jump = Some(i + 1);
+                        }
+                    });
+                //undone edits are greyed out until they are redone
+                redo.iter()
+                    .enumerate()
+                    .for_each(|(i, (description, cells))| {
+                        let text = egui::RichText::new(format!("{description} ({cells})")).weak();
+                        if ui.selectable_label(false, text).clicked() {
+                            jump = Some(undo.len() + i + 1);
+                        }
+                    });
+            });
+        if let Some(applied) = jump {
+            self.jump_in_history(applied);
+        }
+    }
+
+    fn capture_selection(&self, name: &str) -> Option<Blueprint> {
+        let (min, max) = self.selection?;
+        let relative = |pos: [i32; 2]| [pos[0] - min[0], pos[1] - min[1]];
+        let inside = |pos: &[i32; 2]| (0..2).all(|i| (min[i]..=max[i]).contains(&pos[i]));
+        let tiles = (min[0]..=max[0])
+            .flat_map(|x| (min[1]..=max[1]).map(move |y| [x, y]))
+            .map(|pos| (pos, self.get_tile(pos)))
+            .filter(|(_, tile)| *tile != Tile::Empty)
+            .map(|(pos, tile)| (relative(pos), tile))
+            .collect();
+        let balls = self
+            .world
+            .balls
+            .iter()
+            .filter(|(pos, _)| inside(&pos.position))
+            .map(|(pos, ball)| (relative(pos.position), *ball))
+            .collect();
+        Some(Blueprint {
+            name: name.to_owned(),
+            tiles,
+            balls,
+            tags: Vec::new(),
+        })
+    }
+
+    fn export_image(&mut self) {
+        let (Some((min, max)), Some(blueprint)) = (self.selection, self.capture_selection(""))
+        else {
+            return;
+        };
+        let size = [max[0] - min[0] + 1, max[1] - min[1] + 1];
+        let path = Path::new(&self.image_path);
+        self.image_summary = match tile_image::export(&blueprint, size, self.image_mode, path) {
+            Ok(()) => format!("wrote {}x{} cells to {}", size[0], size[1], path.display()),
+            Err(e) => format!("couldn't export {}: {e}", path.display()),
+        };
+    }
+
+    fn place_stamp(&mut self, cell: [i32; 2]) {
+        let Some(stamp) = self.stamp.take() else {
+            return;
+        };
+        let offset = |pos: [i32; 2]| [pos[0] + cell[0], pos[1] + cell[1]];
+        stamp
+            .tiles
+            .iter()
+            .for_each(|(pos, tile)| self.set_tile(offset(*pos), *tile));
+        stamp
+            .balls
+            .iter()
+            .for_each(|(pos, ball)| self.set_ball(offset(*pos), *ball));
+        self.stamp = Some(stamp);
+    }
+
+    fn handle_tool_hotkeys(&mut self, app: &App) {
+        match self.current_tool {
+            Tool::BallTool(on) => self.last_ball_tool = on,
+            Tool::TileTool(tile) => self.last_tile_tool = tile,
+            Tool::LabelTool
+            | Tool::Eraser
+            | Tool::Select
+            | Tool::Stamp
+            | Tool::MoveBall
+            | Tool::Scatter
+            | Tool::Fill(_)
+            | Tool::Measure
+            | Tool::Inspect
+            | Tool::PlayMacro => {}
+        }
+        if app.was_action_pressed(Action::ToggleToolKind) {
+            self.current_tool = match self.current_tool {
+                Tool::BallTool(_) => Tool::TileTool(self.last_tile_tool),
+                _ => Tool::BallTool(self.last_ball_tool),
+            };
+        }
+        Action::TILE_SLOTS
+            .iter()
+            .zip(0_u8..)
+            .filter(|(action, _)| app.was_action_pressed(**action))
+            .filter_map(|(_, tile)| Tile::try_from(tile).ok())
+            .for_each(|tile| self.current_tool = Tool::TileTool(tile));
+    }
+
+    //full updates from the toolbar, the first one since editing remembers the world to reset to
+    fn step(&mut self, app: &mut App, count: u32) {
+        if self.edit_state.is_none() {
+            self.edit_state = Some((self.world_save(app), self.world.stats.tick));
+        }
+        for _ in 0..count {
+            let waiting: Vec<[i32; 2]> = self
+                .breakpoints
+                .iter()
+                .filter(|pos| self.get_ball(**pos).is_some())
+                .copied()
+                .collect();
+            self.full_step();
+            let hit = self
+                .breakpoints
+                .iter()
+                .find(|pos| self.get_ball(**pos).is_some() && !waiting.contains(pos));
+            if let Some(pos) = hit {
+                self.breakpoint_message =
+                    format!("stopped at {pos:?} on tick {}", self.world.stats.tick);
+                self.playing = false;
+                self.turbo_left = 0;
+                break;
+            }
+        }
+        app.request_redraw();
+    }
+
+    fn reset_to_edit_state(&mut self, app: &mut App) {
+        self.playing = false;
+        let Some((save, tick)) = self.edit_state.take() else {
+            return;
+        };
+        //anything edited while it ran stays undoable
+        let history = std::mem::take(&mut self.history);
+        let dirty = self.dirty;
+        if let Err(e) = self.replace_world(save) {
+            log::error!("couldn't reset the world: {e}");
+        }
+        self.world.stats.tick = tick;
+        self.history = history;
+        self.dirty = dirty;
+        app.request_redraw();
+    }
+
+    fn play(&mut self, app: &mut App, delta_time: f32) {
+        //a slow update doesn't get to fall further and further behind
+        const MAX_UPDATES_PER_FRAME: u32 = 8;
+        if !self.playing {
+            self.play_time = 0.0;
+            return;
+        }
+        let interval = 1000.0 / self.speed;
+        self.play_time += delta_time;
+        let updates = ((self.play_time / interval) as u32).min(MAX_UPDATES_PER_FRAME);
+        self.play_time = (self.play_time - updates as f32 * interval).min(interval);
+        self.step(app, updates);
+    }
+
+    //a frame is only drawn once every `turbo_frame_ticks` updates
+    fn turbo(&mut self, app: &mut App, delta_time: f32) {
+        let ticks = self.turbo_left.min(self.turbo_frame_ticks.max(1));
+        self.step(app, ticks as u32);
+        //a breakpoint may have ended it already
+        self.turbo_left = self.turbo_left.saturating_sub(ticks);
+        self.look_around(app, delta_time);
+        self.draw(app);
+    }
+
+    fn turbo_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("turbo").show(ctx, |ui| {
+            let done = self.turbo_ticks.saturating_sub(self.turbo_left);
+            ui.add(
+                egui::ProgressBar::new(done as f32 / self.turbo_ticks.max(1) as f32)
+                    .text(format!("{done}/{} updates", self.turbo_ticks)),
+            );
+            ui.label(format!(
+                "tick {}, {} balls, {:.0} ticks/s",
+                self.world.stats.tick,
+                self.world.stats.balls,
+                self.world.stats.ticks_per_second()
+            ));
+            if ui.button("stop").clicked() {
+                self.turbo_left = 0;
+            }
+        });
+    }
+
+    fn change_speed(&mut self, faster: bool) {
+        let index = SPEEDS
+            .iter()
+            .position(|speed| *speed >= self.speed)
+            .unwrap_or(SPEEDS.len() - 1);
+        self.speed = match faster {
+            true => SPEEDS[(index + 1).min(SPEEDS.len() - 1)],
+            false => SPEEDS[index.saturating_sub(1)],
+        };
+    }
+
+    fn toolbar(&mut self, app: &mut App, ui: &mut egui::Ui) {
+        let keybinds = &app.settings().keybinds;
+        let keys = [
+            Action::PlaySim,
+            Action::StepSim,
+            Action::StepSimTen,
+            Action::ResetSim,
+            Action::SlowerSim,
+            Action::FasterSim,
+        ]
+        .map(|action| format!("{:?}", keybinds.key(action)));
+        ui.horizontal(|ui| {
+            let play_text = if self.playing { "pause" } else { "play" };
+            if ui.button(play_text).on_hover_text(&keys[0]).clicked() {
+                self.playing = !self.playing;
+            }
+            if ui.button("step").on_hover_text(&keys[1]).clicked() {
+                self.step(app, 1);
+            }
+            if ui.button("step 10").on_hover_text(&keys[2]).clicked() {
+                self.step(app, 10);
+            }
+            if ui
+                .add_enabled(self.edit_state.is_some(), egui::Button::new("reset"))
+                .on_hover_text(&keys[3])
+                .clicked()
             {
-                balls_to_update.push(pos);
-                balls_to_update.push(next_pos.position);
-            } else if self.get_tile(pos) == Tile::Hold {
-                failed_holds.insert(pos);
+                self.reset_to_edit_state(app);
+            }
+            egui::ComboBox::from_id_salt("speed")
+                .selected_text(format!("{}/s", self.speed))
+                .show_ui(ui, |ui| {
+                    SPEEDS.into_iter().for_each(|speed| {
+                        ui.selectable_value(&mut self.speed, speed, format!("{speed}/s"));
+                    });
+                })
+                .response
+                .on_hover_text(format!("{} slower, {} faster", keys[4], keys[5]));
+        });
+        //for long runs on slow machines, editing and most of the ui are left out until it's done
+        ui.horizontal(|ui| {
+            if ui.button("turbo").clicked() {
+                self.playing = false;
+                self.turbo_left = self.turbo_ticks;
+            }
+            ui.add(egui::DragValue::new(&mut self.turbo_ticks).suffix(" updates"));
+            ui.add(
+                egui::DragValue::new(&mut self.turbo_frame_ticks)
+                    .range(1..=u32::MAX)
+                    .prefix("drawing every "),
+            );
+        });
+    }
+
+    //one update of the world, then everything that shows it catches up
+    pub fn full_step(&mut self) {
+        if !self.world.full_step() {
+            self.cap_warning = true;
+            return;
+        }
+        let followed = self.world.followed();
+        if let Some(inspector) = &mut self.ball_inspector {
+            followed.into_iter().for_each(|followed| match followed {
+                Followed::Moved(tick, to) => inspector.moved(tick, to),
+                Followed::Destroyed(tick) => inspector.destroyed(tick),
+            });
+        }
+        self.stats_history.push(&self.world.stats);
+        self.broadcast_changes();
+    }
+
+    //in no particular order
+    pub fn balls(&self) -> impl Iterator<Item = ([i32; 2], (bool, Direction))> + '_ {
+        self.world.balls()
+    }
+
+    pub fn ball_count(&self) -> usize {
+        self.world.ball_count()
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) -> anyhow::Result<()> {
+        self.world.set_rules(rules)
+    }
+
+    pub fn stats(&self) -> SimStats {
+        self.world.stats()
+    }
+
+    //the subscriber is called with everything that happened once each update is done
+    pub fn subscribe(&mut self, subscriber: impl FnMut(SimEvent) + 'static) -> SubscriptionId {
+        self.world.subscribe(subscriber)
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.world.unsubscribe(id);
+    }
+
+    //only subscribed while something can be heard, so silent runs don't collect events
+    fn play_sounds(&mut self, app: &App) {
+        //most different pitches played per frame
+        const MAX_CHORD: usize = 8;
+        let settings = app.settings();
+        self.midi.connect(settings.midi_port.as_deref());
+        self.midi.release(false);
+        let audible = !settings.muted
+            && settings.volume > 0.0
+            && (self.audio.available() || self.midi.connected());
+        match (audible, self.sound_subscription) {
+            (true, None) => {
+                let sounds = self.sounds.clone();
+                self.sound_subscription = Some(self.world.subscribe(move |event| {
+                    if matches!(
+                        event,
+                        SimEvent::BallEntered(Tile::Note, _)
+                            | SimEvent::BallDestroyed(_)
+                            | SimEvent::BallDuplicated(_)
+                    ) {
+                        sounds.borrow_mut().push(event);
+                    }
+                }));
             }
+            (false, Some(id)) => {
+                self.world.unsubscribe(id);
+                self.sound_subscription = None;
+            }
+            _ => {}
         }
+        let default = TileData::default();
+        let mut effects = std::mem::take(&mut self.effects);
+        let mut notes: Vec<[u8; 3]> = vec![];
+        self.sounds
+            .borrow_mut()
+            .drain(..)
+            .for_each(|event| match event {
+                SimEvent::BallEntered(_, pos) => {
+                    let data = self.world.tile_data.get(&pos).unwrap_or(&default);
+                    notes.push([data.midi_channel(), data.pitch(), data.midi_program()]);
+                }
+                SimEvent::BallDestroyed(_) => effects.push(Effect::Destroy),
+                SimEvent::BallDuplicated(_) => effects.push(Effect::Duplicate),
+            });
+        if !audible {
+            return;
+        }
+        effects.sort();
+        effects.dedup();
+        effects.into_iter().for_each(|effect| {
+            self.audio.play_effect(
+                effect,
+                settings.volume * settings.effect_volumes.get(effect),
+            );
+        });
+        //each note once, a full machine playing every note at once is just noise
+        notes.sort();
+        notes.dedup();
+        let velocity = (settings.volume * 127.0).round() as u8;
+        notes
+            .into_iter()
+            .take(MAX_CHORD)
+            .for_each(|[channel, pitch, program]| {
+                self.audio.play(pitch, settings.volume);
+                self.midi.play(channel, program, pitch, velocity);
+            });
     }
 }
 
+//balls move one cell per full update and only along axes, so the tick count is the manhattan distance
+fn measure_text(start: [i32; 2], end: [i32; 2]) -> String {
+    let (dx, dy) = ((end[0] - start[0]).abs(), (end[1] - start[1]).abs());
+    format!("dx {dx} dy {dy} | {} ticks", dx + dy)
+}
+
+pub fn tile_combo_box(ui: &mut egui::Ui, id: &str, tile: &mut Tile) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(format!("{tile:?}"))
+        .show_ui(ui, |ui| {
+            (0..Tile::COUNT)
+                .filter_map(|val| Tile::try_from(val).ok())
+                .for_each(|option| {
+                    ui.selectable_value(tile, option, format!("{option:?}"));
+                });
+        });
+}
+
+//a size by size square around center, biased down and left for even sizes
+fn brush_cells(center: [i32; 2], size: i32) -> impl Iterator<Item = [i32; 2]> {
+    let start = [center[0] - (size - 1) / 2, center[1] - (size - 1) / 2];
+    (0..size).flat_map(move |x| (0..size).map(move |y| [start[0] + x, start[1] + y]))
+}
+
+//every cell the segment from a to b passes through, in order
+fn cells_on_line(a: [f32; 2], b: [f32; 2]) -> Vec<[i32; 2]> {
+    let mut cell = [a[0].floor() as i32, a[1].floor() as i32];
+    let end = [b[0].floor() as i32, b[1].floor() as i32];
+    let delta = [b[0] - a[0], b[1] - a[1]];
+    let step = delta.map(|d| if d > 0.0 { 1 } else { -1 });
+    //distance along the segment, from 0 to 1, of the next cell border on each axis
+    let mut next_border = [0, 1].map(|i| {
+        let border = cell[i] as f32 + if delta[i] > 0.0 { 1.0 } else { 0.0 };
+        if delta[i] == 0.0 {
+            f32::INFINITY
+        } else {
+            (border - a[i]) / delta[i]
+        }
+    });
+    let border_step = delta.map(|d| 1.0 / d.abs());
+
+    let steps = (end[0] - cell[0]).abs() + (end[1] - cell[1]).abs();
+    let mut out = vec![cell];
+    (0..steps).for_each(|_| {
+        let axis = if next_border[0] < next_border[1] {
+            0
+        } else {
+            1
+        };
+        cell[axis] += step[axis];
+        next_border[axis] += border_step[axis];
+        out.push(cell);
+    });
+    out
+}
+
 impl State for Simulation {
     fn update(&mut self, app: &mut crate::app::App, delta_time: f32) {
-        Simulation::update_zoom(app);
+        self.world.debug_log.enabled = app.settings().sim_debug_log;
+        let changed = self.history.take_changed() && !self.practice;
+        self.dirty |= changed;
+        self.autosave_pending |= changed;
+        if std::mem::take(&mut self.start_tutorial) {
+            let world = std::mem::replace(self, Simulation::headless());
+            let tutorial = Tutorial::new(app, world);
+            app.set_update_loop(Box::new(tutorial));
+            return;
+        }
+        if app.take_close_request() {
+            if self.dirty {
+                self.pending = Some(Pending::Exit);
+            } else {
+                app.exit();
+            }
+        }
+        if self.spectator.is_some() {
+            self.watch(app, delta_time);
+            return;
+        }
+        if self.turbo_left > 0 {
+            self.turbo(app, delta_time);
+            return;
+        }
+        self.update_remote(app);
+        self.autosave(app);
+        app.take_dropped_files()
+            .into_iter()
+            .for_each(|path| self.open_dropped(app, path));
+        if app.was_action_pressed(Action::FrameAll) {
+            self.frame_all(app);
+        }
+        if app.was_action_pressed(Action::QuickSave) {
+            self.quicksave(app);
+        }
+        if app.was_action_pressed(Action::QuickLoad) {
+            if let Some((_, path, _)) = world::quicksaves().first() {
+                self.quickload(app, path);
+            }
+        }
+        if app.was_action_pressed(Action::PlaySim) {
+            self.playing = !self.playing;
+        }
+        if app.was_action_pressed(Action::StepSim) {
+            self.step(app, 1);
+        }
+        if app.was_action_pressed(Action::StepSimTen) {
+            self.step(app, 10);
+        }
+        if app.was_action_pressed(Action::ResetSim) {
+            self.reset_to_edit_state(app);
+        }
+        if app.was_action_pressed(Action::SlowerSim) {
+            self.change_speed(false);
+        }
+        if app.was_action_pressed(Action::FasterSim) {
+            self.change_speed(true);
+        }
+        self.play(app, delta_time);
+        self.handle_tool_hotkeys(app);
+        if app.is_action_active(Action::CommandModifier) {
+            if app.was_action_pressed(Action::CommandPalette) {
+                self.console.open_palette();
+            }
+            if app.was_action_pressed(Action::Undo) {
+                self.undo();
+                app.request_redraw();
+            }
+            if app.was_action_pressed(Action::Redo) {
+                self.redo();
+                app.request_redraw();
+            }
+        }
+        if app.was_action_pressed(Action::Rotate) {
+            match self.current_tool {
+                Tool::Stamp => self.stamp = self.stamp.as_ref().map(Blueprint::rotated),
+                Tool::TileTool(tile) => self.current_tool = Tool::TileTool(tile.rotated()),
+                _ => {}
+            }
+        }
+        if app.was_action_pressed(Action::PlaceSymmetryAxis) && !app.in_ui() {
+            let pos = app.get_mouse_position_world();
+            self.symmetry_axis = [pos[0].floor() as i32, pos[1].floor() as i32];
+        }
+        [
+            (Action::BallOnTool, Tool::BallTool(true)),
+            (Action::BallOffTool, Tool::BallTool(false)),
+            (Action::LabelTool, Tool::LabelTool),
+        ]
+        .into_iter()
+        .filter(|(action, _)| app.was_action_pressed(*action))
+        .for_each(|(_, tool)| self.current_tool = tool);
+        Simulation::update_zoom(app, delta_time);
+        if let Some(inspector) = self.ball_inspector.as_ref().filter(|i| i.follow) {
+            let target = [inspector.pos[0] as f32 + 0.5, inspector.pos[1] as f32 + 0.5];
+            if app.camera().pos != target {
+                self.camera_target = Some(target);
+            }
+        }
+        self.update_pan(app, delta_time);
         self.handle_mouse(app);
+        self.play_sounds(app);
 
-        //ending stuff
-        app.set_chunk_to_draw(self.get_visible_chunks(app));
-        app.set_balls_to_draw(self.get_visible_balls(app));
-        self.last_mouse_pos = app.get_mouse_position_world();
+        self.broadcast();
+        self.draw(app);
     }
 
     fn ui(&mut self, app: &mut crate::app::App, ctx: &shared::egui::Context) {
+        if self.spectator.is_some() {
+            self.spectator_ui(ctx);
+            return;
+        }
+        if self.turbo_left > 0 {
+            self.turbo_ui(ctx);
+            self.ask_to_save(app, ctx);
+            return;
+        }
+        if ctx.is_pointer_over_area() && ctx.input(|input| input.pointer.any_click()) {
+            self.effects.push(Effect::Click);
+        }
+        self.cell_menu_ui(ctx);
+        if let Some(line) = self.console.palette_ui(ctx) {
+            let result = self.run_console_line(app, &line);
+            self.console.palette_result(result);
+        }
+        let entered = egui::Window::new("console")
+            .default_open(false)
+            .show(ctx, |ui| self.console.console_ui(ui))
+            .and_then(|response| response.inner.flatten());
+        if let Some(line) = entered {
+            match self.run_console_line(app, &line) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => self.console.print(output),
+                Err(e) => self.console.print(format!("error: {e}")),
+            }
+        }
         egui::Window::new("tile select").show(ctx, |ui| {
             [true, false].iter().for_each(|on| {
                 ui.selectable_value(
@@ -326,54 +2573,592 @@ impl State for Simulation {
                     format!("{on:?}"),
                 );
             });
-            (0_u8..14_u8)
-                .filter_map(|val| Some(Tool::TileTool(val.try_into().ok()?)))
-                .for_each(|tile| {
-                    ui.selectable_value(&mut self.current_tool, tile.clone(), format!("{tile:?}"));
+            (0..Tile::COUNT)
+                .filter_map(|val| Some((val, Tile::try_from(val).ok()?)))
+                .for_each(|(val, tile)| {
+                    let key = Action::TILE_SLOTS
+                        .get(val as usize)
+                        .map(|action| app.settings().keybinds.key(*action));
+                    ui.selectable_value(
+                        &mut self.current_tool,
+                        Tool::TileTool(tile),
+                        format!("{tile:?}"),
+                    )
+                    .on_hover_ui(|ui| {
+                        TileInfo::of(tile).ui(ui, tile);
+                        if let Some(key) = key {
+                            ui.label(format!("key: {key:?}"));
+                        }
+                    });
+                });
+            if let Tool::TileTool(tile) = self.current_tool {
+                if tile.rotated() != tile {
+                    let rotate_key = app.settings().keybinds.key(Action::Rotate);
+                    ui.label(format!("press {rotate_key:?} to rotate"));
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.current_tool, Tool::LabelTool, "Label");
+                ui.text_edit_singleline(&mut self.label_text);
+            });
+            ui.selectable_value(&mut self.current_tool, Tool::Eraser, "Eraser");
+            ui.selectable_value(&mut self.current_tool, Tool::Select, "Select");
+            ui.selectable_value(&mut self.current_tool, Tool::MoveBall, "Move ball");
+            ui.selectable_value(&mut self.current_tool, Tool::Measure, "Measure");
+            ui.selectable_value(&mut self.current_tool, Tool::Inspect, "Inspect");
+            if let Some(text) = self
+                .measurement
+                .map(|(start, end)| measure_text(start, end))
+            {
+                ui.label(text);
+            }
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.current_tool, Tool::Scatter, "Scatter");
+                ui.add(egui::Slider::new(&mut self.scatter_count, 1..=10000).logarithmic(true));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Fill");
+                [true, false].iter().for_each(|on| {
+                    ui.selectable_value(&mut self.current_tool, Tool::Fill(*on), format!("{on:?}"));
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label("layer");
+                Layer::ALL.iter().for_each(|layer| {
+                    ui.selectable_value(&mut self.layer, *layer, format!("{layer:?}"));
+                });
+            });
+            ui.add(egui::Slider::new(&mut self.brush_size, 1..=16).text("brush size"));
+            ui.horizontal(|ui| {
+                ui.label("symmetry");
+                Symmetry::ALL.iter().for_each(|symmetry| {
+                    ui.selectable_value(&mut self.symmetry, *symmetry, format!("{symmetry:?}"));
                 });
+            });
+            if self.symmetry != Symmetry::Off {
+                let axis_key = app.settings().keybinds.key(Action::PlaceSymmetryAxis);
+                ui.label(format!(
+                    "axis at {:?}, press {axis_key:?} to move it to the cursor",
+                    self.symmetry_axis
+                ));
+            }
+        });
+        egui::Window::new("edit").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.history.can_undo(), egui::Button::new("undo"))
+                    .clicked()
+                {
+                    self.undo();
+                    app.request_redraw();
+                }
+                if ui
+                    .add_enabled(self.history.can_redo(), egui::Button::new("redo"))
+                    .clicked()
+                {
+                    self.redo();
+                    app.request_redraw();
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("replace");
+                tile_combo_box(ui, "replace_from", &mut self.replace_from);
+                ui.label("with");
+                tile_combo_box(ui, "replace_to", &mut self.replace_to);
+            });
+            ui.add_enabled(
+                self.selection.is_some(),
+                egui::Checkbox::new(&mut self.replace_in_selection, "only in selection"),
+            );
+            if ui.button("replace all").clicked() {
+                let (from, to) = (self.replace_from, self.replace_to);
+                let count = self.replace_tiles(from, to, self.replace_in_selection);
+                self.replace_summary = format!("replaced {count} {from:?} with {to:?}");
+                app.request_redraw();
+            }
+            if !self.replace_summary.is_empty() {
+                ui.label(&self.replace_summary);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("export selection to");
+                ui.text_edit_singleline(&mut self.image_path);
+                if ui.button("browse...").clicked() {
+                    if let Some(path) = dialogs::save_image(&self.image_path) {
+                        self.image_path = path.to_string_lossy().into_owned();
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.image_mode, ImageMode::Pixels, "pixel per tile")
+                    .on_hover_text("can be imported back exactly");
+                ui.selectable_value(&mut self.image_mode, ImageMode::Sprites, "sprites");
+                if ui
+                    .add_enabled(self.selection.is_some(), egui::Button::new("export png"))
+                    .clicked()
+                {
+                    self.export_image();
+                }
+            });
+            if !self.image_summary.is_empty() {
+                ui.label(&self.image_summary);
+            }
         });
+        egui::Window::new("new world from template")
+            .default_open(false)
+            .show(ctx, |ui| {
+                let mut chosen = None;
+                self.templates.iter().enumerate().for_each(|(i, template)| {
+                    ui.horizontal(|ui| {
+                        if ui.button(&template.name).clicked() {
+                            chosen = Some(i);
+                        }
+                        ui.label(&template.description);
+                    });
+                });
+                if let Some(template) = chosen.map(|i| self.templates[i].clone()) {
+                    if let Err(e) = self.new_world(app, &template) {
+                        log::error!("couldn't create world from {}: {e}", template.name);
+                    }
+                }
+            });
+        if self.cap_warning {
+            let cap = self.world.rules().population_cap.unwrap_or_default();
+            egui::Window::new("population cap reached")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} balls is over the cap of {cap}, updates are paused until balls are removed or the cap is raised",
+                        self.world.balls.len()
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button("double the cap").clicked() {
+                            let rules = Rules {
+                                population_cap: Some(cap.saturating_mul(2)),
+                                ..self.world.rules().clone()
+                            };
+                            if let Err(e) = self.world.set_rules(rules) {
+                                log::error!("couldn't raise the cap: {e}");
+                            }
+                            self.cap_warning = false;
+                        }
+                        if ui.button("close").clicked() {
+                            self.cap_warning = false;
+                        }
+                    });
+                });
+        }
+        if let Some(inspector) = &mut self.ball_inspector {
+            let pos = BallPosition {
+                position: inspector.pos,
+            };
+            let (ball, age) = (
+                self.world.balls.get(&pos).copied(),
+                self.world.ages.get(&pos).copied(),
+            );
+            let mut open = true;
+            egui::Window::new("ball inspector").show(ctx, |ui| open = inspector.ui(ui, ball, age));
+            if !open {
+                self.ball_inspector = None;
+                self.world.follow(None);
+            }
+        }
+        if let Some(pos) = self.tile_inspector {
+            let mut open = true;
+            egui::Window::new("tile inspector").show(ctx, |ui| {
+                ui.label(format!("position {pos:?}"));
+                Layer::ALL.into_iter().for_each(|layer| {
+                    let old = self.get_layer_tile(layer, pos);
+                    let mut tile = old;
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{layer:?}"));
+                        tile_combo_box(ui, &format!("inspected {layer:?}"), &mut tile);
+                    });
+                    if tile != old {
+                        self.history.begin(format!("set inspected {layer:?} tile"));
+                        self.set_layer_tile(layer, pos, tile);
+                        self.history.commit();
+                    }
+                });
+                let mut data = self.world.tile_data.get(&pos).cloned().unwrap_or_default();
+                let old = data.clone();
+                tile_data::ui(&mut data, ui, self.get_tile(pos));
+                if data != old {
+                    self.history.begin("edit tile data");
+                    self.set_tile_data(pos, data);
+                    self.history.commit();
+                }
+                if ui.button("close").clicked() {
+                    open = false;
+                }
+            });
+            if !open {
+                self.tile_inspector = None;
+            }
+        }
+        let mut edited = self.world.rules().clone();
+        egui::Window::new("world rules")
+            .default_open(false)
+            .show(ctx, |ui| rules::ui(&mut edited, ui));
+        if edited != *self.world.rules() {
+            if let Err(e) = self.world.set_rules(edited) {
+                log::error!("couldn't change the rules: {e}");
+            }
+        }
+        egui::Window::new("help")
+            .default_open(false)
+            .show(ctx, |ui| self.help.ui(ui, self.world.rules()));
+        egui::Window::new("undo history")
+            .default_open(false)
+            .show(ctx, |ui| self.history_ui(ui));
+        egui::Window::new("truth table")
+            .default_open(false)
+            .show(ctx, |ui| {
+                ui.label("mark input and output cells in the tile inspector");
+                ui.horizontal(|ui| {
+                    ui.label("run each combination for");
+                    ui.add(egui::DragValue::new(&mut self.analysis_updates).range(1..=10000));
+                    ui.label("updates");
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("export csv").clicked() {
+                        self.export_truth_table(false);
+                    }
+                    if ui.button("export netlist").clicked() {
+                        self.export_truth_table(true);
+                    }
+                });
+                if !self.analysis_summary.is_empty() {
+                    ui.label(&self.analysis_summary);
+                }
+            });
+        egui::Window::new("generate")
+            .default_open(false)
+            .show(ctx, |ui| {
+                self.generator.ui(ui);
+                if ui.button("generate").clicked() {
+                    self.generate(app);
+                    app.request_redraw();
+                }
+            });
+        egui::Window::new("macros").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.macro_name);
+                if self.macro_recording.is_none() {
+                    if ui.button("record").clicked() {
+                        self.macro_recording = Some(vec![]);
+                    }
+                } else if ui.button("stop").clicked() {
+                    let recording = self.macro_recording.take().unwrap_or_default();
+                    let name = if self.macro_name.is_empty() {
+                        format!("macro {}", self.macros.len() + 1)
+                    } else {
+                        self.macro_name.clone()
+                    };
+                    if let Some(edit_macro) = EditMacro::from_recording(name, recording) {
+                        self.macros.push(edit_macro);
+                        self.current_macro = Some(self.macros.len() - 1);
+                        self.current_tool = Tool::PlayMacro;
+                    }
+                }
+            });
+            if let Some(recording) = &self.macro_recording {
+                ui.label(format!("recording, {} edits so far", recording.len()));
+            }
+            (0..self.macros.len()).for_each(|i| {
+                let selected =
+                    self.current_tool == Tool::PlayMacro && self.current_macro == Some(i);
+                let label = format!(
+                    "{} ({} edits)",
+                    self.macros[i].name,
+                    self.macros[i].ops.len()
+                );
+                if ui.selectable_label(selected, label).clicked() {
+                    self.current_macro = Some(i);
+                    self.current_tool = Tool::PlayMacro;
+                }
+            });
+        });
+        egui::Window::new("blueprints").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.blueprint_name);
+                let can_save = self.selection.is_some() && !self.blueprint_name.is_empty();
+                if ui
+                    .add_enabled(can_save, egui::Button::new("save selection"))
+                    .clicked()
+                {
+                    if let Some(mut blueprint) = self.capture_selection(&self.blueprint_name) {
+                        //saving over a blueprint keeps its tags
+                        if let Some(old) = self
+                            .blueprints
+                            .iter()
+                            .find(|old| old.name == blueprint.name)
+                        {
+                            blueprint.tags = old.tags.clone();
+                        }
+                        match blueprint.save(Path::new(BLUEPRINT_DIR)) {
+                            Ok(()) => {
+                                self.blueprints.retain(|other| other.name != blueprint.name);
+                                self.blueprints.push(blueprint);
+                                self.blueprints.sort_by(|a, b| a.name.cmp(&b.name));
+                            }
+                            Err(e) => log::error!("couldn't save blueprint: {e}"),
+                        }
+                    }
+                }
+            });
+            let selected = self
+                .stamp
+                .as_ref()
+                .filter(|_| self.current_tool == Tool::Stamp)
+                .map(|stamp| stamp.name.as_str());
+            match self
+                .blueprint_browser
+                .ui(ui, &mut self.blueprints, selected)
+            {
+                Some(BlueprintAction::Pick(i)) => {
+                    self.stamp = Some(self.blueprints[i].clone());
+                    self.current_tool = Tool::Stamp;
+                }
+                Some(BlueprintAction::Drop(i)) => {
+                    let pos = app.get_mouse_position_world();
+                    let cell = [pos[0].floor() as i32, pos[1].floor() as i32];
+                    self.stamp = Some(self.blueprints[i].clone());
+                    self.current_tool = Tool::Stamp;
+                    self.history
+                        .begin(format!("stamp {}", self.blueprints[i].name));
+                    self.place_stamp(cell);
+                    self.history.commit();
+                    self.effects.push(Effect::Place);
+                }
+                None => {}
+            }
+            if self.current_tool == Tool::Stamp {
+                let rotate_key = app.settings().keybinds.key(Action::Rotate);
+                ui.label(format!("press {rotate_key:?} to rotate"));
+            }
+            ui.collapsing("import pattern (.rle, .csv, .png)", |ui| {
+                if let Some(blueprint) = self.importer.ui(ui) {
+                    self.stamp = Some(blueprint);
+                    self.current_tool = Tool::Stamp;
+                }
+            });
+        });
+        egui::Window::new("statistics")
+            .default_open(false)
+            .show(ctx, |ui| self.stats_history.ui(ui));
+        let action = egui::Window::new("worlds")
+            .default_open(false)
+            .show(ctx, |ui| self.world_browser.ui(ui))
+            .and_then(|response| response.inner)
+            .flatten();
+        match action {
+            Some(BrowserAction::Open(path)) => self.open_world(app, &path),
+            Some(BrowserAction::Save(path)) => {
+                self.save_as(app, &path);
+                self.world_browser.refresh();
+            }
+            None => {}
+        }
+        if cfg!(feature = "online") {
+            let server = app.settings().level_server.clone();
+            let action = egui::Window::new("community levels")
+                .default_open(false)
+                .show(ctx, |ui| self.level_client.ui(ui, &server))
+                .and_then(|response| response.inner)
+                .flatten();
+            match action {
+                Some(OnlineAction::Open(path)) => {
+                    self.world_browser.refresh();
+                    self.request_open(app, path);
+                }
+                Some(OnlineAction::Upload(name)) => self.upload_world(app, &name),
+                None => {}
+            }
+        }
+        self.ask_to_save(app, ctx);
         egui::Window::new("simulate").show(ctx, |ui| {
-            if ui.button("full update").clicked() {
-                [
-                    Direction::Up,
-                    Direction::Right,
-                    Direction::Left,
-                    Direction::Down,
-                ]
-                .into_iter()
-                .fold(
-                    (HashSet::new(), HashSet::new()),
-                    |(mut moved, mut dup), dir| {
-                        self.sim_step(dir, &mut moved, &mut dup);
-                        (moved, dup)
-                    },
+            let frame_key = app.settings().keybinds.key(Action::FrameAll);
+            if ui
+                .button("frame all")
+                .on_hover_text(format!("{frame_key:?}"))
+                .clicked()
+            {
+                self.frame_all(app);
+            }
+            if !self.practice && ui.button("tutorial").clicked() {
+                self.start_tutorial = true;
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.world_path);
+                if ui.button("save").clicked() {
+                    let path = self.world_path.clone();
+                    self.save_as(app, Path::new(&path));
+                }
+                if ui.button("load").clicked() {
+                    let path = self.world_path.clone();
+                    if let Err(e) = self.load_world(app, Path::new(&path)) {
+                        log::error!("couldn't load {path}: {e}");
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("save as...").clicked() {
+                    if let Some(path) = dialogs::save_world(&self.world_path) {
+                        self.save_as(app, &path);
+                        self.world_browser.refresh();
+                    }
+                }
+                if ui.button("open...").clicked() {
+                    if let Some(path) = dialogs::open_world(&self.world_path) {
+                        self.open_world(app, &path);
+                    }
+                }
+            });
+            ui.collapsing("level code", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("copy level code").clicked() {
+                        self.copy_level_code(app, ui.ctx());
+                    }
+                    if ui.button("paste level code").clicked() {
+                        let code = self.level_code.clone();
+                        if self.dirty {
+                            self.pending = Some(Pending::Paste(code));
+                        } else {
+                            self.paste_level_code(app, &code);
+                        }
+                    }
+                });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.level_code)
+                        .hint_text("paste a level code here"),
+                );
+                if !self.level_code_summary.is_empty() {
+                    ui.label(&self.level_code_summary);
+                }
+            });
+            ui.collapsing("bookmarks and breakpoints", |ui| {
+                if self.bookmarks.is_empty() && self.breakpoints.is_empty() {
+                    ui.label("right click a cell to add some");
+                }
+                let mut removed = None;
+                self.bookmarks.iter().enumerate().for_each(|(i, pos)| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("bookmark {pos:?}"));
+                        if ui.button("go").clicked() {
+                            self.camera_target = Some([pos[0] as f32 + 0.5, pos[1] as f32 + 0.5]);
+                        }
+                        if ui.button("remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                });
+                if let Some(i) = removed {
+                    self.bookmarks.remove(i);
+                }
+                let mut breakpoints: Vec<[i32; 2]> = self.breakpoints.iter().copied().collect();
+                breakpoints.sort();
+                breakpoints.into_iter().for_each(|pos| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("breakpoint {pos:?}"));
+                        if ui.button("remove").clicked() {
+                            self.breakpoints.remove(&pos);
+                        }
+                    });
+                });
+                if !self.breakpoint_message.is_empty() {
+                    ui.label(&self.breakpoint_message);
+                }
+            });
+            ui.collapsing("spectators", |ui| {
+                match &self.broadcaster {
+                    Some(broadcaster) => {
+                        ui.label(format!(
+                            "broadcasting on port {} to {} spectators",
+                            broadcaster.port(),
+                            broadcaster.spectators()
+                        ));
+                        if broadcaster.public() {
+                            ui.label("other machines can watch too");
+                        }
+                        if ui.button("stop broadcasting").clicked() {
+                            self.broadcaster = None;
+                            self.world.track_changes(false);
+                        }
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut self.broadcast_port));
+                            if ui.button("broadcast").clicked() {
+                                match Broadcaster::start(self.broadcast_port, self.broadcast_public)
+                                {
+                                    Ok(broadcaster) => {
+                                        self.broadcaster = Some(broadcaster);
+                                        self.world.track_changes(true);
+                                    }
+                                    Err(e) => {
+                                        self.spectate_message = format!(
+                                            "couldn't broadcast on port {}: {e}",
+                                            self.broadcast_port
+                                        )
+                                    }
+                                }
+                            }
+                        });
+                        ui.checkbox(&mut self.broadcast_public, "let other machines watch")
+                            .on_hover_text(
+                                "listens on every network interface, anyone who can reach the \
+                                 port can watch without a password",
+                            );
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.spectate_address);
+                    if ui.button("watch").clicked() {
+                        let address = self.spectate_address.clone();
+                        if self.dirty {
+                            self.pending = Some(Pending::Watch(address));
+                        } else {
+                            self.start_watching(&address);
+                        }
+                    }
+                });
+                if !self.spectate_message.is_empty() {
+                    ui.label(&self.spectate_message);
+                }
+            });
+            ui.collapsing("quicksaves", |ui| {
+                let (save_key, load_key) = (
+                    app.settings().keybinds.key(Action::QuickSave),
+                    app.settings().keybinds.key(Action::QuickLoad),
                 );
+                ui.label(format!("{save_key:?} saves, {load_key:?} loads the newest"));
+                if ui.button("quicksave").clicked() {
+                    self.quicksave(app);
+                }
+                world::quicksaves()
+                    .into_iter()
+                    .for_each(|(slot, path, modified)| {
+                        let text = format!("load slot {} ({})", slot + 1, age_text(Some(modified)));
+                        if ui.button(text).clicked() {
+                            self.quickload(app, &path);
+                        }
+                    });
+                let autosave = world::autosave_path();
+                if let Ok(modified) = fs::metadata(&autosave).and_then(|data| data.modified()) {
+                    let text = format!("load the autosave ({})", age_text(Some(modified)));
+                    if ui.button(text).clicked() {
+                        self.quickload(app, &autosave);
+                    }
+                }
+            });
+            self.toolbar(app, ui);
+            stats::summary(&self.world.stats, ui);
+            let asleep = self.world.asleep_chunks();
+            if asleep > 0 {
+                ui.label(format!("{asleep} chunks asleep"));
             }
         });
     }
 }
-
-trait GetTile {
-    fn get_tile(&self, pos: [i32; 2]) -> Tile;
-}
-
-impl GetTile for HashMap<ChunkPosition, Chunk> {
-    fn get_tile(&self, pos: [i32; 2]) -> Tile {
-        self.get(&ChunkPosition {
-            position: [
-                pos[0].div_euclid(CHUNK_SIZE as i32),
-                pos[1].div_euclid(CHUNK_SIZE as i32),
-            ],
-        })
-        .and_then(|chunk| {
-            chunk
-                .get_tile([
-                    pos[0].rem_euclid(CHUNK_SIZE as i32) as u32,
-                    pos[1].rem_euclid(CHUNK_SIZE as i32) as u32,
-                ])
-                .try_into()
-                .ok()
-        })
-        .unwrap_or(Tile::Empty)
-    }
-}
@@ -0,0 +1,306 @@
+//one instance broadcasts the cells that changed in every update and others watch it with their
+//own cameras, frames are bincode prefixed with their length as a little endian u32
+use std::{
+    io::{Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use engine::{ball::Direction, chunk::ChunkPosition, hash::HashSet, tiles::Layer};
+use serde::{Deserialize, Serialize};
+use shared::{anyhow, log};
+
+pub const BROADCAST_PORT: u16 = 7879;
+//how often the listener checks whether it was stopped
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+//a frame bigger than this is more likely garbage than a world
+const MAX_FRAME_BYTES: u32 = 256 << 20;
+//frames waiting to be written to one spectator, past this it has fallen behind
+const SPECTATOR_QUEUE: usize = 64;
+//a spectator that takes longer than this to read a frame is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+//for each address the host name resolves to
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+//what changed since the last frame went out, only kept track of while broadcasting
+#[derive(Default)]
+pub struct ChangedCells {
+    //the world was swapped for another, spectators are sent all of it again
+    pub everything: bool,
+    pub chunks: HashSet<(Layer, ChunkPosition)>,
+    pub balls: HashSet<[i32; 2]>,
+    pub labels: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Frame {
+    //everything the spectator had is dropped first, set on the first frame
+    pub reset: bool,
+    //None for chunks that are gone
+    pub chunks: Vec<(Layer, ChunkPosition, Option<Vec<u8>>)>,
+    pub removed_balls: Vec<[i32; 2]>,
+    pub balls: Vec<([i32; 2], (bool, Direction))>,
+    //all of them whenever any changed, they rarely do
+    pub labels: Option<Vec<([i32; 2], String)>>,
+    pub tick: u64,
+}
+
+impl Frame {
+    fn is_empty(&self) -> bool {
+        !self.reset
+            && self.chunks.is_empty()
+            && self.removed_balls.is_empty()
+            && self.balls.is_empty()
+            && self.labels.is_none()
+    }
+
+    fn encode(&self) -> anyhow::Result<Arc<Vec<u8>>> {
+        let body = bincode::serialize(self)?;
+        let mut bytes = (body.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(body);
+        Ok(Arc::new(bytes))
+    }
+}
+
+//anyone who can reach the port can watch, but nothing they send is ever read. only this
+//machine can unless the broadcast is made public
+pub struct Broadcaster {
+    port: u16,
+    public: bool,
+    arrivals: Receiver<TcpStream>,
+    running: Arc<AtomicBool>,
+    //one writer thread per spectator so a slow one doesn't hold up the updates
+    spectators: Vec<Watcher>,
+    //connected but not sent the world yet
+    joining: Vec<TcpStream>,
+    pub changed: ChangedCells,
+}
+
+struct Watcher {
+    frames: SyncSender<Arc<Vec<u8>>>,
+    //its queue filled up so frames were skipped, it gets everything again once there is room
+    behind: bool,
+}
+
+impl Watcher {
+    //false once its thread ended, the spectator has left
+    fn offer(&mut self, bytes: &Arc<Vec<u8>>, starts_over: bool) -> bool {
+        match self.frames.try_send(bytes.clone()) {
+            Ok(()) => {
+                self.behind &= !starts_over;
+                true
+            }
+            Err(TrySendError::Full(_)) => {
+                if !self.behind {
+                    log::info!("a spectator fell behind, it will be sent the world again");
+                }
+                self.behind = true;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+impl Broadcaster {
+    pub fn start(port: u16, public: bool) -> anyhow::Result<Self> {
+        let address = if public {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        let listener = TcpListener::bind((address, port))?;
+        listener.set_nonblocking(true)?;
+        let (sender, arrivals) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let still_running = running.clone();
+        thread::spawn(move || {
+            while still_running.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, address)) => {
+                        log::info!("spectator joined from {address}");
+                        if sender.send(stream).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL);
+                    }
+                    Err(e) => log::warn!("couldn't accept a spectator: {e}"),
+                }
+            }
+        });
+        log::info!("broadcasting on {address}:{port}");
+        Ok(Self {
+            port,
+            public,
+            arrivals,
+            running,
+            spectators: vec![],
+            joining: vec![],
+            changed: ChangedCells::default(),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn public(&self) -> bool {
+        self.public
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    fn join(&mut self, mut stream: TcpStream, first: Arc<Vec<u8>>) {
+        let (sender, frames) = mpsc::sync_channel::<Arc<Vec<u8>>>(SPECTATOR_QUEUE);
+        thread::spawn(move || {
+            let write = |stream: &mut TcpStream, bytes: &[u8]| stream.write_all(bytes);
+            if let Err(e) = stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+                .and_then(|_| write(&mut stream, &first))
+            {
+                log::info!("spectator left: {e}");
+                return;
+            }
+            for frame in frames {
+                if let Err(e) = write(&mut stream, &frame) {
+                    log::info!("spectator left: {e}");
+                    return;
+                }
+            }
+        });
+        self.spectators.push(Watcher {
+            frames: sender,
+            behind: false,
+        });
+    }
+
+    //those who fell behind skip it unless it starts over
+    pub fn send(&mut self, frame: &Frame) -> anyhow::Result<()> {
+        if frame.is_empty() {
+            return Ok(());
+        }
+        let bytes = frame.encode()?;
+        self.spectators.retain_mut(|spectator| {
+            (spectator.behind && !frame.reset) || spectator.offer(&bytes, frame.reset)
+        });
+        Ok(())
+    }
+
+    //someone joined or fell behind and needs the whole world
+    pub fn wants_everything(&mut self) -> bool {
+        self.joining.extend(self.arrivals.try_iter());
+        !self.joining.is_empty() || self.spectators.iter().any(|spectator| spectator.behind)
+    }
+
+    //`everything` starts over with the whole world, it only goes to those who need it
+    pub fn resync(&mut self, everything: &Frame) -> anyhow::Result<()> {
+        let bytes = everything.encode()?;
+        self.spectators
+            .retain_mut(|spectator| !spectator.behind || spectator.offer(&bytes, true));
+        std::mem::take(&mut self.joining)
+            .into_iter()
+            .for_each(|stream| self.join(stream, bytes.clone()));
+        Ok(())
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+fn read_frame(stream: &mut TcpStream) -> anyhow::Result<Frame> {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length);
+    if length > MAX_FRAME_BYTES {
+        anyhow::bail!("the broadcaster sent a {length} byte frame");
+    }
+    let mut body = vec![0; length as usize];
+    stream.read_exact(&mut body)?;
+    Ok(bincode::deserialize(&body)?)
+}
+
+//the first address the name resolves to that answers in time
+fn open(address: &str) -> anyhow::Result<TcpStream> {
+    let mut last_error = anyhow::anyhow!("{address} doesn't resolve to any address");
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e.into(),
+        }
+    }
+    Err(last_error)
+}
+
+//watching another instance, frames are read on a thread and applied by the simulation
+pub struct Spectator {
+    address: String,
+    frames: Receiver<anyhow::Result<Frame>>,
+    //a frame arrived, until then the thread may still be connecting
+    connected: bool,
+}
+
+impl Spectator {
+    //connecting happens on the thread too, a host that doesn't answer shows up as an error frame
+    pub fn connect(address: &str) -> Self {
+        let (sender, frames) = mpsc::channel();
+        let target = address.to_owned();
+        thread::spawn(move || {
+            let mut stream = match open(&target) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = sender.send(Err(e.context(format!("couldn't connect to {target}"))));
+                    return;
+                }
+            };
+            loop {
+                let frame = read_frame(&mut stream);
+                let failed = frame.is_err();
+                if sender.send(frame).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Self {
+            address: address.to_owned(),
+            frames,
+            connected: false,
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    //the frames that arrived since the last call, an error means the broadcast is over
+    pub fn frames(&mut self) -> anyhow::Result<Vec<Frame>> {
+        let mut frames = vec![];
+        loop {
+            match self.frames.try_recv() {
+                Ok(frame) => {
+                    frames.push(frame?);
+                    self.connected = true;
+                }
+                Err(TryRecvError::Empty) => return Ok(frames),
+                Err(TryRecvError::Disconnected) => anyhow::bail!("the broadcast ended"),
+            }
+        }
+    }
+}
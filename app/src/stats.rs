@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use engine::stats::SimStats;
+use shared::egui::Ui;
+
+//ticks the statistics window keeps
+const HISTORY_LENGTH: usize = 4096;
+
+//the numbers under the toolbar
+pub fn summary(stats: &SimStats, ui: &mut Ui) {
+    ui.label(format!("tick {}, {} balls", stats.tick, stats.balls));
+    ui.label(format!(
+        "{} ticks since reset, {:.1} ticks/s",
+        stats.run_ticks,
+        stats.ticks_per_second()
+    ));
+    ui.label(format!(
+        "last update: {} moved, {} destroyed, {} duplicated in {:.2?}",
+        stats.moved, stats.destroyed, stats.duplicated, stats.step_time
+    ));
+}
+
+//a line's name and what it shows of each sample
+type Series = (&'static str, fn(&SimStats) -> f64);
+
+//recent stats for the statistics window, one sample per update
+#[derive(Default)]
+pub struct StatsHistory {
+    samples: VecDeque<SimStats>,
+}
+
+impl StatsHistory {
+    pub fn push(&mut self, stats: &SimStats) {
+        //going back in time means a reset or a load, the old samples don't connect to the new ones
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| last.tick >= stats.tick)
+        {
+            self.samples.clear();
+        }
+        if self.samples.len() == HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(*stats);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn plot(&self, ui: &mut Ui, id: &str, series: &[Series]) {
+        Plot::new(id)
+            .height(120.0)
+            .legend(Legend::default())
+            .allow_scroll(false)
+            .show(ui, |plot| {
+                series.iter().for_each(|(name, value)| {
+                    let points: PlotPoints = self
+                        .samples
+                        .iter()
+                        .map(|stats| [stats.tick as f64, value(stats)])
+                        .collect();
+                    plot.line(Line::new(points).name(*name));
+                });
+            });
+    }
+
+    pub fn ui(&self, ui: &mut Ui) {
+        if self.samples.is_empty() {
+            ui.label("nothing has run yet");
+            return;
+        }
+        ui.label(format!("the last {} ticks", self.samples.len()));
+        self.plot(ui, "ball counts", &[("balls", |stats| stats.balls as f64)]);
+        self.plot(
+            ui,
+            "ball changes",
+            &[
+                ("moved", |stats| stats.moved as f64),
+                ("destroyed", |stats| stats.destroyed as f64),
+                ("duplicated", |stats| stats.duplicated as f64),
+            ],
+        );
+        self.plot(
+            ui,
+            "step times",
+            &[("step time (ms)", |stats| {
+                stats.step_time.as_secs_f64() * 1000.0
+            })],
+        );
+    }
+}
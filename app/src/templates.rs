@@ -0,0 +1,67 @@
+use engine::{ball::Direction, tiles::Tile};
+use serde::Deserialize;
+use shared::anyhow::{self, anyhow};
+
+//starter worlds shipped inside the binary
+const TEMPLATE_SOURCES: [&str; 4] = [
+    include_str!("../templates/sandbox.toml"),
+    include_str!("../templates/tutorial.toml"),
+    include_str!("../templates/sorter.toml"),
+    include_str!("../templates/duplicator.toml"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateLabel {
+    pub pos: [i32; 2],
+    pub text: String,
+}
+
+//tiles and balls are drawn as text with the top line being the highest row
+#[derive(Debug, Clone, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    tiles: String,
+    //1 for an on ball, 0 for an off one, all start moving right
+    balls: String,
+    #[serde(default)]
+    pub labels: Vec<TemplateLabel>,
+}
+
+//cells of a text grid with y flipped so the bottom line is row 0
+fn grid_cells(text: &str) -> impl Iterator<Item = ([i32; 2], char)> + '_ {
+    let lines: Vec<&str> = text.lines().collect();
+    let height = lines.len() as i32;
+    lines.into_iter().enumerate().flat_map(move |(y, line)| {
+        line.chars()
+            .enumerate()
+            .map(move |(x, c)| ([x as i32, height - 1 - y as i32], c))
+    })
+}
+
+impl Template {
+    pub fn all() -> Vec<Template> {
+        TEMPLATE_SOURCES
+            .iter()
+            .map(|source| toml::from_str(source).expect("built in templates are valid"))
+            .collect()
+    }
+
+    pub fn tiles(&self) -> anyhow::Result<Vec<([i32; 2], Tile)>> {
+        grid_cells(&self.tiles)
+            .map(|(pos, c)| {
+                Tile::try_from(c)
+                    .map(|tile| (pos, tile))
+                    .map_err(|_| anyhow!("unknown tile {c:?} at {pos:?} in {}", self.name))
+            })
+            .filter(|tile| !matches!(tile, Ok((_, Tile::Empty))))
+            .collect()
+    }
+
+    pub fn balls(&self) -> Vec<([i32; 2], (bool, Direction))> {
+        grid_cells(&self.balls)
+            .filter(|(_, c)| matches!(c, '0' | '1'))
+            .map(|(pos, c)| (pos, (c == '1', Direction::Right)))
+            .collect()
+    }
+}
@@ -0,0 +1,81 @@
+use engine::{
+    tile_data::{Probe, TileData},
+    tiles::Tile,
+};
+use shared::egui::{DragValue, Ui};
+
+use crate::{
+    audio::{frequency, note_name},
+    sim::tile_combo_box,
+};
+
+//only shows the parameters the tile uses
+pub fn ui(data: &mut TileData, ui: &mut Ui, tile: Tile) {
+    ui.horizontal(|ui| {
+        ui.label("note");
+        ui.text_edit_singleline(&mut data.note);
+    });
+    ui.horizontal(|ui| {
+        ui.label("truth table");
+        ui.selectable_value(&mut data.probe, None, "none");
+        ui.selectable_value(&mut data.probe, Some(Probe::Input), "input");
+        ui.selectable_value(&mut data.probe, Some(Probe::Output), "output");
+    });
+    if tile.piston_facing().is_some() {
+        ui.horizontal(|ui| {
+            ui.label("push every");
+            let mut interval = data.interval();
+            ui.add(DragValue::new(&mut interval).range(1..=1000));
+            ui.label("updates");
+            if interval != data.interval() {
+                data.interval = Some(interval);
+            }
+        });
+    }
+    if tile.portal_facing().is_some() {
+        ui.horizontal(|ui| {
+            let mut linked = data.link.is_some();
+            let mut link = data.link.unwrap_or_default();
+            ui.checkbox(&mut linked, "linked to");
+            ui.add_enabled(linked, DragValue::new(&mut link[0]).prefix("x "));
+            ui.add_enabled(linked, DragValue::new(&mut link[1]).prefix("y "));
+            data.link = linked.then_some(link);
+        });
+    }
+    if tile == Tile::PressurePlate {
+        let [mut from, mut to] = data.conversion();
+        ui.horizontal(|ui| {
+            ui.label("while pressed turns");
+            tile_combo_box(ui, "plate from", &mut from);
+            ui.label("into");
+            tile_combo_box(ui, "plate to", &mut to);
+        });
+        if [from, to] != data.conversion() {
+            data.conversion = Some([from, to]);
+        }
+    }
+    if tile == Tile::Note {
+        ui.horizontal(|ui| {
+            ui.label("plays");
+            let mut pitch = data.pitch();
+            ui.add(DragValue::new(&mut pitch).range(0..=127));
+            ui.label(format!("{} ({:.1}hz)", note_name(pitch), frequency(pitch)));
+            if pitch != data.pitch() {
+                data.pitch = Some(pitch);
+            }
+        });
+        ui.horizontal(|ui| {
+            let (mut channel, mut program) = (data.midi_channel(), data.midi_program());
+            ui.label("midi channel");
+            ui.add(DragValue::new(&mut channel).range(1..=16));
+            ui.label("program");
+            ui.add(DragValue::new(&mut program).range(0..=127));
+            if channel != data.midi_channel() {
+                data.midi_channel = Some(channel);
+            }
+            if program != data.midi_program() {
+                data.midi_program = Some(program);
+            }
+        });
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use engine::tiles::Tile;
+use image::{imageops, Rgba, RgbaImage};
+use shared::anyhow;
+
+use crate::blueprint::Blueprint;
+
+const ATLAS: &[u8] = include_bytes!("../../renderer/src/textures/sim_tiles.png");
+const ATLAS_TILE_SIZE: u32 = 16;
+const ATLAS_TILES_PER_ROW: u32 = 3;
+//pixels more transparent than this are empty when importing
+pub const ALPHA_THRESHOLD: u8 = 128;
+//the longer side of a world thumbnail in pixels
+pub const THUMBNAIL_SIZE: u32 = 64;
+const BALL_COLORS: [[u8; 4]; 2] = [[40, 40, 40, 255], [255, 255, 255, 255]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageMode {
+    //one pixel per tile in its palette color, reads back exactly
+    Pixels,
+    //each tile drawn with its sprite, for sharing
+    Sprites,
+}
+
+//empty is transparent so pictures can be layered, the rest get hues spread by the golden ratio
+pub fn tile_color(tile: Tile) -> [u8; 4] {
+    if tile == Tile::Empty {
+        return [0; 4];
+    }
+    let hue = (u8::from(tile) as f32 * 0.618_034).fract() * 6.0;
+    let value = if u8::from(tile).is_multiple_of(2) {
+        1.0
+    } else {
+        0.75
+    };
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let [r, g, b] = match hue as u32 {
+        0 => [1.0, x, 0.0],
+        1 => [x, 1.0, 0.0],
+        2 => [0.0, 1.0, x],
+        3 => [0.0, x, 1.0],
+        4 => [x, 0.0, 1.0],
+        _ => [1.0, 0.0, x],
+    };
+    let channel = |c: f32| ((0.25 + 0.75 * c) * value * 255.0).round() as u8;
+    [channel(r), channel(g), channel(b), 255]
+}
+
+pub fn nearest_tile(color: [u8; 4]) -> Tile {
+    let distance = |tile: Tile| -> i32 {
+        let other = tile_color(tile);
+        (0..3)
+            .map(|i| (color[i] as i32 - other[i] as i32).pow(2))
+            .sum()
+    };
+    (0..Tile::COUNT)
+        .filter_map(|value| Tile::try_from(value).ok())
+        .filter(|tile| *tile != Tile::Empty)
+        .min_by_key(|tile| distance(*tile))
+        .unwrap_or(Tile::Block)
+}
+
+//the pixel export shrunk until it fits, each pixel shows the last cell drawn into it
+//and balls are drawn over the tiles
+pub fn thumbnail(blueprint: &Blueprint, size: [i32; 2], path: &Path) -> anyhow::Result<()> {
+    let size = size.map(|side| side.max(1) as u32);
+    let scale = size[0].max(size[1]).div_ceil(THUMBNAIL_SIZE);
+    let [width, height] = size.map(|side| side.div_ceil(scale));
+    let mut image = RgbaImage::new(width, height);
+    let mut put = |pos: [i32; 2], color: [u8; 4]| {
+        image.put_pixel(
+            pos[0] as u32 / scale,
+            height - 1 - pos[1] as u32 / scale,
+            Rgba(color),
+        );
+    };
+    blueprint
+        .tiles
+        .iter()
+        .for_each(|(pos, tile)| put(*pos, tile_color(*tile)));
+    blueprint
+        .balls
+        .iter()
+        .for_each(|(pos, (on, _))| put(*pos, BALL_COLORS[*on as usize]));
+    image.save(path)?;
+    Ok(())
+}
+
+//`size` is the selection's size in cells, the top row of the image is the highest one
+pub fn export(
+    blueprint: &Blueprint,
+    size: [i32; 2],
+    mode: ImageMode,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let [width, height] = size.map(|side| side.max(0) as u32);
+    let row = |y: i32| height - 1 - y as u32;
+    let image = match mode {
+        ImageMode::Pixels => {
+            let mut image = RgbaImage::new(width, height);
+            blueprint.tiles.iter().for_each(|(pos, tile)| {
+                image.put_pixel(pos[0] as u32, row(pos[1]), Rgba(tile_color(*tile)));
+            });
+            image
+        }
+        ImageMode::Sprites => {
+            let atlas = image::load_from_memory(ATLAS)?.to_rgba8();
+            let mut image = RgbaImage::new(width * ATLAS_TILE_SIZE, height * ATLAS_TILE_SIZE);
+            blueprint.tiles.iter().for_each(|(pos, tile)| {
+                let index = u8::from(*tile) as u32;
+                let sprite = imageops::crop_imm(
+                    &atlas,
+                    index % ATLAS_TILES_PER_ROW * ATLAS_TILE_SIZE,
+                    index / ATLAS_TILES_PER_ROW * ATLAS_TILE_SIZE,
+                    ATLAS_TILE_SIZE,
+                    ATLAS_TILE_SIZE,
+                );
+                imageops::replace(
+                    &mut image,
+                    &sprite.to_image(),
+                    (pos[0] as u32 * ATLAS_TILE_SIZE) as i64,
+                    (row(pos[1]) * ATLAS_TILE_SIZE) as i64,
+                );
+            });
+            image
+        }
+    };
+    image.save(path)?;
+    Ok(())
+}
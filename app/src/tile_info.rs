@@ -0,0 +1,106 @@
+use engine::tiles::Tile;
+use shared::egui::{RichText, Ui};
+
+//what the palette tooltips say about a tile
+pub struct TileInfo {
+    pub summary: &'static str,
+    //what balls on it do during the four direction phases of an update
+    pub phases: &'static str,
+    //how it works together with hold and filter tiles
+    pub interactions: &'static str,
+}
+
+impl TileInfo {
+    pub fn of(tile: Tile) -> Self {
+        let (summary, phases, interactions) = match tile {
+            Tile::Up | Tile::Down | Tile::Left | Tile::Right => (
+                "turns balls to face the way it points",
+                "the ball moves one cell in the phase of the way it points and waits in the others",
+                "a ball it sends into a held ball shoves that one a cell ahead, \
+                 on a filter the ball is turned again by its color",
+            ),
+            Tile::FilterU | Tile::FilterR | Tile::FilterD | Tile::FilterL => (
+                "sends off balls the way it points and on balls the opposite way",
+                "the ball moves in the phase of the way it was sent and waits in the others",
+                "pointing one at a hold lets only balls of one color push through it, \
+                 the others are sent back the way they came",
+            ),
+            Tile::Hold => (
+                "keeps balls in place until another runs into them",
+                "a held ball doesn't move in any phase on its own",
+                "a ball running in shoves the held one a cell ahead, if that one can't move \
+                 it's a collision, a filter before a hold picks which balls can push",
+            ),
+            Tile::Sticky => (
+                "keeps any ball that enters it, only pistons move it out",
+                "a stuck ball doesn't move in any phase",
+                "unlike a hold, a ball running into a stuck one collides with it",
+            ),
+            Tile::Block => (
+                "solid, balls can't enter it",
+                "never holds a ball",
+                "a held ball in front of it can't be shoved, so the ball pushing it collides",
+            ),
+            Tile::DuplicateH | Tile::DuplicateV => (
+                "sends balls out along its axis and leaves a copy behind",
+                "in the two phases along its axis the ball moves and copies, \
+                 in the other two it waits, the tile copies once per update",
+                "the copy has the original's color and stays until a ball comes for it, \
+                 a hold next to it feeds it balls one push at a time",
+            ),
+            Tile::Destroy => (
+                "destroys every ball that enters it",
+                "the ball is gone at the first phase after it arrived",
+                "a hold in front of it keeps balls waiting until another ball shoves them in",
+            ),
+            Tile::DestroyOn | Tile::DestroyOff => (
+                "destroys balls of one color, the others pass as if it was empty",
+                "balls it keeps move on in the phase they were heading, or with gravity",
+                "on its own it sorts balls by color, like a filter that drops one color",
+            ),
+            Tile::Empty => (
+                "nothing, balls keep going",
+                "the ball moves in the phase it was heading, or falls with the rules' gravity",
+                "balls crossing it shove held balls in their way",
+            ),
+            Tile::PistonU | Tile::PistonR | Tile::PistonD | Tile::PistonL => (
+                "solid, every few updates pushes the balls in front of it a cell the way it faces",
+                "pushes happen after the phases, every 4 updates unless the tile sets its own interval",
+                "the only thing that moves balls off a sticky, held balls are pushed too",
+            ),
+            Tile::UpRight | Tile::DownRight | Tile::DownLeft | Tile::UpLeft => (
+                "moves balls a cell diagonally",
+                "the ball sits out the phases and jumps once they are done, if the cell is free",
+                "a held or filtered ball never jumps, only balls on the tile itself do",
+            ),
+            Tile::PortalU | Tile::PortalR | Tile::PortalD | Tile::PortalL => (
+                "sends balls to the cell in front of the portal it links to",
+                "the ball sits out the phases and jumps once they are done, \
+                 leaving facing the way the other portal does",
+                "an unlinked portal keeps balls like a hold, a blocked exit keeps the ball waiting",
+            ),
+            Tile::PressurePlate => (
+                "while a ball is on it, swaps the neighbouring tiles it is set up for",
+                "the ball moves on as it would on an empty tile, the swap is undone once it leaves",
+                "turns blocks into empty by default, so a ball parked on a hold can keep a wall open",
+            ),
+            Tile::Note => (
+                "plays its pitch whenever a ball enters it",
+                "the ball moves on as it would on an empty tile",
+                "a hold in front of it plays it every time a ball is shoved through",
+            ),
+        };
+        Self {
+            summary,
+            phases,
+            interactions,
+        }
+    }
+
+    pub fn ui(&self, ui: &mut Ui, tile: Tile) {
+        ui.label(RichText::new(tile.name()).strong());
+        ui.label(self.summary);
+        ui.label(format!("phases: {}", self.phases));
+        ui.label(format!("with holds and filters: {}", self.interactions));
+    }
+}
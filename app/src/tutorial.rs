@@ -0,0 +1,190 @@
+use renderer::state::CameraUniform;
+use shared::egui::{self, Align2, Color32, Context, Id, LayerId, Order, Stroke, StrokeKind};
+
+use crate::{
+    app::{App, State},
+    keybinds::Action,
+    sim::Simulation,
+};
+
+//updates the ball has to run for before playing counts as done
+const PLAY_TICKS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Welcome,
+    PlaceTile,
+    SpawnBall,
+    Update,
+    Play,
+    Done,
+}
+
+impl Step {
+    const ALL: [Step; 6] = [
+        Step::Welcome,
+        Step::PlaceTile,
+        Step::SpawnBall,
+        Step::Update,
+        Step::Play,
+        Step::Done,
+    ];
+
+    fn text(self, app: &App) -> String {
+        let key = |action| format!("{:?}", app.settings().keybinds.key(action));
+        match self {
+            Step::Welcome => "balls roll across a grid of tiles that steer them. \
+                this builds a tiny machine in a practice world, \
+                yours is put aside until the tutorial is over"
+                .to_owned(),
+            Step::PlaceTile => "pick a tile in the tile select window, Right for example, \
+                and click a cell to place it. hovering a tile there tells what it does"
+                .to_owned(),
+            Step::SpawnBall => "true and false at the top of tile select are on and off balls. \
+                pick one and click the cell with your tile to drop a ball on it"
+                .to_owned(),
+            Step::Update => format!(
+                "every update moves balls one cell. press step in the simulate window, \
+                or {}, and watch the ball move",
+                key(Action::StepSim)
+            ),
+            Step::Play => format!(
+                "play keeps updating until it is paused, {} and {} change the speed. \
+                let it run for {PLAY_TICKS} updates",
+                key(Action::SlowerSim),
+                key(Action::FasterSim)
+            ),
+            Step::Done => "that's the basics. the world rules window changes gravity and \
+                collisions, and the tutorial button in simulate starts this again"
+                .to_owned(),
+        }
+    }
+
+    //the window the step is about
+    fn window(self) -> Option<&'static str> {
+        match self {
+            Step::PlaceTile | Step::SpawnBall => Some("tile select"),
+            Step::Update | Step::Play => Some("simulate"),
+            Step::Welcome | Step::Done => None,
+        }
+    }
+}
+
+//walks through the basics in a practice world, then gives back the one that was open
+pub struct Tutorial {
+    sim: Simulation,
+    //the world, camera and zoom to go back to, taken once the tutorial ends
+    resume: Option<(Simulation, CameraUniform, f32)>,
+    step: usize,
+    //the tick the current step began at
+    step_tick: u64,
+    //asked for from the ui, acted on at the next update
+    leaving: bool,
+}
+
+impl Tutorial {
+    pub fn new(app: &App, world: Simulation) -> Self {
+        Self {
+            sim: Simulation::practice(app.get_mouse_position_world()),
+            resume: Some((world, *app.camera(), app.scroll_level())),
+            step: 0,
+            step_tick: 0,
+            leaving: false,
+        }
+    }
+
+    fn current(&self) -> Step {
+        Step::ALL[self.step]
+    }
+
+    //whether the user did what the current step asks
+    fn step_done(&self) -> bool {
+        let tick = self.sim.stats().tick;
+        match self.current() {
+            Step::Welcome | Step::Done => true,
+            Step::PlaceTile => self.sim.has_tiles(),
+            Step::SpawnBall => self.sim.ball_count() > 0,
+            Step::Update => tick > self.step_tick,
+            Step::Play => tick >= self.step_tick + PLAY_TICKS,
+        }
+    }
+
+    fn leave(&mut self, app: &mut App) {
+        if let Some((world, camera, scroll_level)) = self.resume.take() {
+            *app.camera_mut() = camera;
+            *app.scroll_level_mut() = scroll_level;
+            app.update_settings(|settings| settings.tutorial_done = true);
+            app.set_update_loop(Box::new(world));
+        }
+    }
+
+    fn highlight(&self, ctx: &Context) {
+        let Some(rect) = self
+            .current()
+            .window()
+            .and_then(|window| ctx.memory(|memory| memory.area_rect(Id::new(window))))
+        else {
+            return;
+        };
+        //pulsing so it stands out from the windows' own borders
+        let time = ctx.input(|input| input.time);
+        let alpha = (0.6 + 0.4 * (time * 4.0).sin()) as f32;
+        ctx.layer_painter(LayerId::new(
+            Order::Foreground,
+            Id::new("tutorial highlight"),
+        ))
+        .rect_stroke(
+            rect.expand(4.0),
+            6.0,
+            Stroke::new(3.0, Color32::YELLOW.gamma_multiply(alpha)),
+            StrokeKind::Outside,
+        );
+        ctx.request_repaint();
+    }
+}
+
+impl State for Tutorial {
+    fn update(&mut self, app: &mut App, delta_time: f32) {
+        //the practice world has nothing to save, the close is up to the real one
+        if app.take_close_request() {
+            self.leave(app);
+            app.request_close();
+            return;
+        }
+        if self.leaving {
+            self.leave(app);
+            return;
+        }
+        self.sim.update(app, delta_time);
+    }
+
+    fn ui(&mut self, app: &mut App, ctx: &Context) {
+        self.sim.ui(app, ctx);
+        self.highlight(ctx);
+        let step = self.current();
+        let done = self.step_done();
+        egui::Window::new("tutorial")
+            .anchor(Align2::CENTER_TOP, [0.0, 8.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("step {} of {}", self.step + 1, Step::ALL.len()));
+                ui.label(step.text(app));
+                ui.horizontal(|ui| {
+                    if step == Step::Done {
+                        if ui.button("finish").clicked() {
+                            self.leaving = true;
+                        }
+                        return;
+                    }
+                    if ui.button("skip the tutorial").clicked() {
+                        self.leaving = true;
+                    }
+                    if ui.add_enabled(done, egui::Button::new("next")).clicked() {
+                        self.step += 1;
+                        self.step_tick = self.sim.stats().tick;
+                    }
+                });
+            });
+    }
+}
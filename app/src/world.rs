@@ -0,0 +1,55 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use shared::anyhow;
+
+pub const DEFAULT_WORLD_PATH: &str = "untitled.world";
+//where the world browser looks
+pub const WORLD_DIR: &str = "worlds";
+pub const WORLD_EXTENSION: &str = "world";
+const QUICKSAVE_DIR: &str = "quicksaves";
+//quicksaves go round these, overwriting the oldest
+const QUICKSAVE_SLOTS: usize = 5;
+
+//next to the world file, the browser shows it as a preview
+pub fn thumbnail_path(path: &Path) -> PathBuf {
+    path.with_extension("thumb.png")
+}
+
+//kept apart from the slots so quicksaving never overwrites it
+pub fn autosave_path() -> PathBuf {
+    Path::new(QUICKSAVE_DIR)
+        .join("autosave")
+        .with_extension(WORLD_EXTENSION)
+}
+
+fn quicksave_path(slot: usize) -> PathBuf {
+    Path::new(QUICKSAVE_DIR)
+        .join(format!("slot {}", slot + 1))
+        .with_extension(WORLD_EXTENSION)
+}
+
+//the slots that have been saved to, newest first
+pub fn quicksaves() -> Vec<(usize, PathBuf, SystemTime)> {
+    let mut saves: Vec<(usize, PathBuf, SystemTime)> = (0..QUICKSAVE_SLOTS)
+        .map(|slot| (slot, quicksave_path(slot)))
+        .filter_map(|(slot, path)| {
+            let modified = fs::metadata(&path).and_then(|data| data.modified()).ok()?;
+            Some((slot, path, modified))
+        })
+        .collect();
+    saves.sort_by_key(|(_, _, modified)| std::cmp::Reverse(*modified));
+    saves
+}
+
+//the slot after the newest one
+pub fn next_quicksave_path() -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(QUICKSAVE_DIR)?;
+    let slot = quicksaves()
+        .first()
+        .map_or(0, |(slot, _, _)| (slot + 1) % QUICKSAVE_SLOTS);
+    Ok(quicksave_path(slot))
+}
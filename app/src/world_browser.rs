@@ -0,0 +1,247 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use shared::{
+    anyhow,
+    egui::{vec2, Button, ColorImage, Grid, Image, TextureHandle, TextureOptions, Ui},
+};
+
+use crate::world::{thumbnail_path, WORLD_DIR, WORLD_EXTENSION};
+
+//how big previews are drawn in the list, in points
+const PREVIEW_SIZE: f32 = 32.0;
+
+#[derive(Debug, Clone)]
+struct WorldEntry {
+    path: PathBuf,
+    name: String,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+pub enum BrowserAction {
+    Open(PathBuf),
+    //save the current world here
+    Save(PathBuf),
+}
+
+//the worlds saved in `WORLD_DIR`, so they can be managed without a file manager
+#[derive(Clone, Default)]
+pub struct WorldBrowser {
+    entries: Vec<WorldEntry>,
+    save_name: String,
+    //the world being renamed and the name typed so far
+    renaming: Option<(PathBuf, String)>,
+    //asks once more before deleting
+    deleting: Option<PathBuf>,
+    message: String,
+    //previews loaded so far, None for worlds saved without one
+    thumbnails: HashMap<PathBuf, Option<TextureHandle>>,
+}
+
+pub fn world_path(name: &str) -> PathBuf {
+    Path::new(WORLD_DIR)
+        .join(name)
+        .with_extension(WORLD_EXTENSION)
+}
+
+//the name itself if no world has it yet, otherwise "name 2", "name 3" and so on
+pub fn free_world_path(name: &str) -> PathBuf {
+    (1..)
+        .map(|i| match i {
+            1 => world_path(name),
+            _ => world_path(&format!("{name} {i}")),
+        })
+        .find(|path| !path.exists())
+        .expect("some name is free")
+}
+
+//"name copy", "name copy 2" and so on until one is free
+fn copy_path(name: &str) -> PathBuf {
+    (1..)
+        .map(|i| match i {
+            1 => format!("{name} copy"),
+            _ => format!("{name} copy {i}"),
+        })
+        .map(|name| world_path(&name))
+        .find(|path| !path.exists())
+        .expect("some copy name is free")
+}
+
+pub fn load_thumbnail(ui: &Ui, path: &Path) -> Option<TextureHandle> {
+    let image = image::open(thumbnail_path(path)).ok()?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    let image = ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Some(
+        ui.ctx()
+            .load_texture(path.to_string_lossy(), image, TextureOptions::NEAREST),
+    )
+}
+
+pub fn age_text(modified: Option<SystemTime>) -> String {
+    let Some(age) = modified.and_then(|time| time.elapsed().ok()) else {
+        return "?".to_owned();
+    };
+    match age.as_secs() {
+        0..60 => "just now".to_owned(),
+        secs @ 60..3600 => format!("{} min ago", secs / 60),
+        secs @ 3600..86400 => format!("{} h ago", secs / 3600),
+        secs => format!("{} days ago", secs / 86400),
+    }
+}
+
+fn size_text(size: u64) -> String {
+    match size {
+        0..1024 => format!("{size} B"),
+        1024..1048576 => format!("{:.1} KB", size as f64 / 1024.0),
+        _ => format!("{:.1} MB", size as f64 / 1048576.0),
+    }
+}
+
+impl WorldBrowser {
+    pub fn refresh(&mut self) {
+        //saving over a world changes its preview
+        self.thumbnails.clear();
+        let Ok(entries) = fs::read_dir(WORLD_DIR) else {
+            self.entries.clear();
+            return;
+        };
+        self.entries = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == WORLD_EXTENSION))
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some(WorldEntry {
+                    name: path.file_stem()?.to_string_lossy().into_owned(),
+                    modified: metadata.modified().ok(),
+                    size: metadata.len(),
+                    path,
+                })
+            })
+            .collect();
+        //newest first, that's usually the one being worked on
+        self.entries.sort_by_key(|entry| Reverse(entry.modified));
+    }
+
+    //runs a file operation and reports how it went
+    fn apply(&mut self, done: String, operation: impl FnOnce() -> anyhow::Result<()>) {
+        self.message = match operation() {
+            Ok(()) => done,
+            Err(e) => format!("failed: {e}"),
+        };
+        self.refresh();
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<BrowserAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.save_name);
+            if ui
+                .add_enabled(!self.save_name.is_empty(), Button::new("save as"))
+                .clicked()
+            {
+                if let Err(e) = fs::create_dir_all(WORLD_DIR) {
+                    self.message = format!("failed: {e}");
+                } else {
+                    action = Some(BrowserAction::Save(world_path(&self.save_name)));
+                }
+            }
+            if ui.button("refresh").clicked() {
+                self.refresh();
+            }
+        });
+        if self.entries.is_empty() {
+            ui.label(format!("no worlds saved in {WORLD_DIR}/ yet"));
+        }
+        let entries = self.entries.clone();
+        Grid::new("worlds").striped(true).show(ui, |ui| {
+            entries.iter().for_each(|entry| {
+                match &mut self.renaming {
+                    Some((path, name)) if *path == entry.path => {
+                        ui.text_edit_singleline(name);
+                    }
+                    _ => {
+                        ui.label(&entry.name);
+                    }
+                }
+                let thumbnail = self
+                    .thumbnails
+                    .entry(entry.path.clone())
+                    .or_insert_with(|| load_thumbnail(ui, &entry.path));
+                match thumbnail {
+                    Some(texture) => {
+                        //scaled so the longer side fills the preview
+                        let size = texture.size_vec2();
+                        let size = size * (PREVIEW_SIZE / size.max_elem());
+                        ui.add(Image::new(&*texture).fit_to_exact_size(size));
+                    }
+                    None => {
+                        ui.allocate_space(vec2(PREVIEW_SIZE, PREVIEW_SIZE));
+                    }
+                }
+                ui.label(age_text(entry.modified));
+                ui.label(size_text(entry.size));
+                if ui.button("open").clicked() {
+                    action = Some(BrowserAction::Open(entry.path.clone()));
+                }
+                match self.renaming.clone() {
+                    Some((path, name)) if path == entry.path => {
+                        if ui.button("done").clicked() {
+                            self.renaming = None;
+                            let to = world_path(&name);
+                            self.apply(format!("renamed {} to {name}", entry.name), || {
+                                if to.exists() {
+                                    anyhow::bail!("{name} already exists");
+                                }
+                                fs::rename(&path, &to)?;
+                                if thumbnail_path(&path).exists() {
+                                    fs::rename(thumbnail_path(&path), thumbnail_path(&to))?;
+                                }
+                                Ok(())
+                            });
+                        }
+                    }
+                    _ => {
+                        if ui.button("rename").clicked() {
+                            self.renaming = Some((entry.path.clone(), entry.name.clone()));
+                        }
+                    }
+                }
+                if ui.button("duplicate").clicked() {
+                    let to = copy_path(&entry.name);
+                    self.apply(format!("duplicated {}", entry.name), || {
+                        fs::copy(&entry.path, &to)?;
+                        if thumbnail_path(&entry.path).exists() {
+                            fs::copy(thumbnail_path(&entry.path), thumbnail_path(&to))?;
+                        }
+                        Ok(())
+                    });
+                }
+                if self.deleting.as_ref() == Some(&entry.path) {
+                    if ui.button("really delete").clicked() {
+                        self.deleting = None;
+                        self.apply(format!("deleted {}", entry.name), || {
+                            fs::remove_file(&entry.path)?;
+                            //the world is gone either way
+                            let _ = fs::remove_file(thumbnail_path(&entry.path));
+                            Ok(())
+                        });
+                    }
+                } else if ui.button("delete").clicked() {
+                    self.deleting = Some(entry.path.clone());
+                }
+                ui.end_row();
+            });
+        });
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+        action
+    }
+}
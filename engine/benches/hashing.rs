@@ -0,0 +1,46 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use engine::{ball::Direction, tiles::Tile, world::World};
+
+//run once with and once without `--features fast-hash` to compare the two
+const SIZE: i32 = 256;
+
+//random arrows everywhere keep most of the balls moving instead of piling up
+fn dense_world() -> World {
+    let mut rng = fastrand::Rng::with_seed(0);
+    let mut sim = World::default();
+    (-1..=SIZE).for_each(|x| {
+        (-1..=SIZE).for_each(|y| {
+            let edge = x < 0 || y < 0 || x == SIZE || y == SIZE;
+            let tile = match rng.u8(0..4) {
+                _ if edge => Tile::Block,
+                0 => Tile::Up,
+                1 => Tile::Down,
+                2 => Tile::Left,
+                _ => Tile::Right,
+            };
+            sim.set_tile([x, y], tile);
+            if !edge && rng.bool() {
+                sim.set_ball([x, y], (rng.bool(), Direction::Right));
+            }
+        });
+    });
+    sim
+}
+
+//the hasher is in the name so the results of both builds can be told apart
+fn hashing(c: &mut Criterion) {
+    let hasher = if cfg!(feature = "fast-hash") {
+        "fx"
+    } else {
+        "sip"
+    };
+    let mut group = c.benchmark_group("hashing");
+    group.throughput(Throughput::Elements(dense_world().ball_count() as u64));
+    group.bench_function(format!("dense world, {hasher}"), |b| {
+        b.iter_batched_ref(dense_world, |sim| sim.full_step(), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, hashing);
+criterion_main!(benches);
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use engine::{ball::Direction, tiles::Tile, world::World};
+
+//updates measured from a fresh duplicate bomb, it changes too much for repeated updates to
+//be comparable
+const BOMB_UPDATES: u32 = 30;
+
+//walled square of random arrows with a ball on about half the cells
+fn dense_field() -> World {
+    const SIZE: i32 = 128;
+    let mut rng = fastrand::Rng::with_seed(0);
+    let mut sim = World::default();
+    (-1..=SIZE).for_each(|x| {
+        (-1..=SIZE).for_each(|y| {
+            let edge = x < 0 || y < 0 || x == SIZE || y == SIZE;
+            let tile = match rng.u8(0..4) {
+                _ if edge => Tile::Block,
+                0 => Tile::Up,
+                1 => Tile::Down,
+                2 => Tile::Left,
+                _ => Tile::Right,
+            };
+            sim.set_tile([x, y], tile);
+            if !edge && rng.bool() {
+                sim.set_ball([x, y], (rng.bool(), Direction::Right));
+            }
+        });
+    });
+    sim
+}
+
+//a two lane loop, every ball keeps moving so no chunk ever sleeps
+fn long_conveyor() -> World {
+    const LENGTH: i32 = 4096;
+    let mut sim = World::default();
+    (0..LENGTH).for_each(|x| {
+        let (bottom, top) = match x {
+            0 => (Tile::Right, Tile::Down),
+            x if x == LENGTH - 1 => (Tile::Up, Tile::Left),
+            _ => (Tile::Right, Tile::Left),
+        };
+        sim.set_tile([x, 0], bottom);
+        sim.set_tile([x, 1], top);
+        if x % 3 == 0 {
+            sim.set_ball([x, 0], (false, Direction::Right));
+            sim.set_ball([x, 1], (true, Direction::Left));
+        }
+    });
+    sim
+}
+
+//duplicators feeding each other in a walled arena until it fills up
+fn duplicate_bomb() -> World {
+    const SIZE: i32 = 64;
+    let mut sim = World::default();
+    (-1..=SIZE).for_each(|x| {
+        (-1..=SIZE).for_each(|y| {
+            let edge = x < 0 || y < 0 || x == SIZE || y == SIZE;
+            let tile = match (x % 8, y % 8) {
+                _ if edge => Tile::Block,
+                (4, 4) => Tile::DuplicateH,
+                (0, 4) => Tile::DuplicateV,
+                (_, 0) => Tile::Right,
+                (0, _) => Tile::Up,
+                _ => Tile::Empty,
+            };
+            sim.set_tile([x, y], tile);
+        });
+    });
+    (0..SIZE / 8).for_each(|i| sim.set_ball([i * 8 + 4, 4], (i % 2 == 0, Direction::Right)));
+    sim
+}
+
+//a world that keeps being stepped settles and its chunks fall asleep, so every
+//measurement starts from a fresh one
+fn sim_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sim_step");
+    for (name, world) in [
+        ("dense field", dense_field as fn() -> World),
+        ("long conveyor", long_conveyor),
+    ] {
+        group.throughput(Throughput::Elements(world().ball_count() as u64));
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(
+                world,
+                |sim| sim.step_phase(Direction::Right),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn full_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full_update");
+    for (name, world) in [
+        ("dense field", dense_field as fn() -> World),
+        ("long conveyor", long_conveyor),
+    ] {
+        group.throughput(Throughput::Elements(world().ball_count() as u64));
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(world, |sim| sim.full_step(), BatchSize::LargeInput)
+        });
+    }
+    group.throughput(Throughput::Elements(BOMB_UPDATES as u64));
+    group.measurement_time(Duration::from_secs(10));
+    group.bench_function("duplicate bomb", |b| {
+        b.iter_batched_ref(
+            duplicate_bomb,
+            |sim| {
+                (0..BOMB_UPDATES).for_each(|_| {
+                    sim.full_step();
+                })
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sim_step, full_update);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[repr(C, align(4))]
+#[derive(
+    Copy,
+    Clone,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+)]
+pub struct BallPosition {
+    pub position: [i32; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<Direction> for u32 {
+    fn from(value: Direction) -> Self {
+        match value {
+            Direction::Right => 0,
+            Direction::Up => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+pub const CHUNK_SIZE: usize = 32;
+
+#[repr(C, align(4))]
+#[derive(
+    Copy,
+    Clone,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+)]
+pub struct ChunkPosition {
+    pub position: [i32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Chunk {
+    pub data: [u8; CHUNK_SIZE * CHUNK_SIZE],
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            data: [0; CHUNK_SIZE * CHUNK_SIZE],
+        }
+    }
+}
+
+impl Chunk {
+    pub fn set_tile(&mut self, pos: [u32; 2], tile: u8) {
+        self.data[(pos[0] + (CHUNK_SIZE as u32 - pos[1] - 1) * CHUNK_SIZE as u32) as usize] = tile;
+    }
+
+    pub fn get_tile(&self, pos: [u32; 2]) -> u8 {
+        self.data[(pos[0] + (CHUNK_SIZE as u32 - pos[1] - 1) * CHUNK_SIZE as u32) as usize]
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+//most messages sent per second, the rest are only counted
+const MAX_PER_SECOND: u32 = 50;
+
+//simulation tracing that costs nothing while disabled and can't flood the log while enabled
+pub struct DebugLog {
+    pub enabled: bool,
+    window_start: Instant,
+    sent: u32,
+    dropped: u32,
+}
+
+impl Default for DebugLog {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_start: Instant::now(),
+            sent: 0,
+            dropped: 0,
+        }
+    }
+}
+
+impl DebugLog {
+    //the message is only built when it is actually sent
+    pub fn log(&mut self, message: impl FnOnce() -> String) {
+        if !self.enabled {
+            return;
+        }
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            if self.dropped > 0 {
+                log::debug!(target: "sim", "dropped {} messages", self.dropped);
+            }
+            self.window_start = Instant::now();
+            self.sent = 0;
+            self.dropped = 0;
+        }
+        if self.sent < MAX_PER_SECOND {
+            self.sent += 1;
+            log::debug!(target: "sim", "{}", message());
+        } else {
+            self.dropped += 1;
+        }
+    }
+}
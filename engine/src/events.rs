@@ -0,0 +1,68 @@
+use crate::tiles::Tile;
+
+//what happens to balls during an update, for anything that wants to react without being part
+//of the step itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimEvent {
+    BallDestroyed([i32; 2]),
+    //a copy was left on the duplicator at this position
+    BallDuplicated([i32; 2]),
+    BallEntered(Tile, [i32; 2]),
+}
+
+impl SimEvent {
+    pub fn pos(&self) -> [i32; 2] {
+        match self {
+            SimEvent::BallDestroyed(pos)
+            | SimEvent::BallDuplicated(pos)
+            | SimEvent::BallEntered(_, pos) => *pos,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionId(u64);
+
+type Subscriber = Box<dyn FnMut(SimEvent)>;
+
+//events are only collected while someone is subscribed and are handed out once the update is done
+#[derive(Default)]
+pub struct Events {
+    subscribers: Vec<(SubscriptionId, Subscriber)>,
+    next_id: u64,
+    pending: Vec<SimEvent>,
+}
+
+impl Events {
+    pub fn subscribe(&mut self, subscriber: impl FnMut(SimEvent) + 'static) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, Box::new(subscriber)));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscribers.retain(|(other, _)| *other != id);
+    }
+
+    pub fn wanted(&self) -> bool {
+        !self.subscribers.is_empty()
+    }
+
+    //events found in hash map order, sorted so every run hands them out the same way
+    pub fn queue(&mut self, mut events: Vec<SimEvent>) {
+        if !self.wanted() {
+            return;
+        }
+        events.sort_by_key(|event| event.pos());
+        self.pending.extend(events);
+    }
+
+    pub fn dispatch(&mut self) {
+        self.pending.drain(..).for_each(|event| {
+            self.subscribers
+                .iter_mut()
+                .for_each(|(_, subscriber)| subscriber(event))
+        });
+    }
+}
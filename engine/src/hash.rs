@@ -0,0 +1,6 @@
+//the simulation hashes positions constantly, FxHash is a lot cheaper than the default SipHash
+//and nothing in there needs protection from crafted keys
+#[cfg(feature = "fast-hash")]
+pub use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+#[cfg(not(feature = "fast-hash"))]
+pub use std::collections::{HashMap, HashSet};
@@ -0,0 +1,319 @@
+use crate::{
+    ball::{BallPosition, Direction},
+    chunk::{Chunk, ChunkPosition},
+    hash::{HashMap, HashSet},
+    rules::Collision,
+    tiles::Tile,
+    world::GetTile,
+};
+
+//new values for cells, None removes what was there
+pub type Changes<V> = HashMap<BallPosition, Option<V>>;
+
+pub fn apply<V>(changes: Changes<V>, map: &mut HashMap<BallPosition, V>) {
+    changes.into_iter().for_each(|(pos, value)| match value {
+        Some(value) => {
+            map.insert(pos, value);
+        }
+        None => {
+            map.remove(&pos);
+        }
+    });
+}
+
+//changes made on top of a map shared by every lane, applied once they are all done
+struct Overlay<'a, V> {
+    base: &'a HashMap<BallPosition, V>,
+    changes: Changes<V>,
+}
+
+impl<'a, V: Copy> Overlay<'a, V> {
+    fn new(base: &'a HashMap<BallPosition, V>) -> Self {
+        Self {
+            base,
+            changes: HashMap::default(),
+        }
+    }
+
+    fn get(&self, pos: [i32; 2]) -> Option<V> {
+        let pos = BallPosition { position: pos };
+        match self.changes.get(&pos) {
+            Some(changed) => *changed,
+            None => self.base.get(&pos).copied(),
+        }
+    }
+
+    fn insert(&mut self, pos: [i32; 2], value: V) {
+        self.changes
+            .insert(BallPosition { position: pos }, Some(value));
+    }
+
+    fn remove(&mut self, pos: [i32; 2]) -> Option<V> {
+        let old = self.get(pos);
+        self.changes.insert(BallPosition { position: pos }, None);
+        old
+    }
+}
+
+//what a lane did, detached from the maps it read so it can be applied to them
+pub struct LaneResult {
+    pub balls: Changes<(bool, Direction)>,
+    pub ages: Changes<u32>,
+    //where each ball that moved came from and went, the cells they arrived in don't move
+    //again this update
+    pub moves: Vec<([i32; 2], [i32; 2])>,
+    pub duplicated: Vec<[i32; 2]>,
+    //cells where a ball appeared or disappeared
+    pub active: Vec<[i32; 2]>,
+    //cells a collision removed a ball from
+    pub destroyed: Vec<[i32; 2]>,
+}
+
+//the balls moving during one phase within a strip of chunks, balls only move along the
+//phase direction so no two strips can affect each other
+pub struct Lane<'a> {
+    chunks: &'a HashMap<ChunkPosition, Chunk>,
+    //balls that leave a copy behind when they move off their duplicator
+    to_duplicate: &'a HashSet<BallPosition>,
+    dir: Direction,
+    collision: Collision,
+    balls: Overlay<'a, (bool, Direction)>,
+    ages: Overlay<'a, u32>,
+    moves: Vec<([i32; 2], [i32; 2])>,
+    duplicated: Vec<[i32; 2]>,
+    active: Vec<[i32; 2]>,
+    destroyed: Vec<[i32; 2]>,
+}
+
+impl<'a> Lane<'a> {
+    pub fn new(
+        chunks: &'a HashMap<ChunkPosition, Chunk>,
+        balls: &'a HashMap<BallPosition, (bool, Direction)>,
+        ages: &'a HashMap<BallPosition, u32>,
+        to_duplicate: &'a HashSet<BallPosition>,
+        dir: Direction,
+        collision: Collision,
+    ) -> Self {
+        Self {
+            chunks,
+            to_duplicate,
+            dir,
+            collision,
+            balls: Overlay::new(balls),
+            ages: Overlay::new(ages),
+            moves: vec![],
+            duplicated: vec![],
+            active: vec![],
+            destroyed: vec![],
+        }
+    }
+
+    //`to_update` is sorted so the ball furthest along the direction is last
+    pub fn run(mut self, to_update: &mut Vec<[i32; 2]>) -> LaneResult {
+        let mut failed_holds: HashSet<_> = HashSet::default();
+        while let Some(pos) = to_update.pop() {
+            //a collision may have already removed it
+            let Some(ball) = self.balls.get(pos) else {
+                continue;
+            };
+            let next_pos = step_pos(pos, self.dir);
+            let blocked = self.chunks.get_tile(next_pos).is_solid();
+            if self.balls.get(next_pos).is_none() && !blocked {
+                self.balls.remove(pos);
+                self.balls.insert(next_pos, ball);
+                self.active.extend([pos, next_pos]);
+                if let Some(age) = self.ages.remove(pos) {
+                    self.ages.insert(next_pos, age);
+                }
+                self.moves.push((pos, next_pos));
+                if matches!(
+                    self.chunks.get_tile(pos),
+                    Tile::DuplicateH | Tile::DuplicateV
+                ) {
+                    self.duplicated.push(pos);
+                    if self.to_duplicate.contains(&BallPosition { position: pos }) {
+                        self.balls.insert(pos, ball);
+                    }
+                }
+            } else if self.balls.get(next_pos).is_some()
+                && self.chunks.get_tile(next_pos) == Tile::Hold
+                && !failed_holds.contains(&next_pos)
+            {
+                to_update.push(pos);
+                to_update.push(next_pos);
+            } else {
+                //a held ball that can't move holds up the ones behind it, even against a wall
+                if self.chunks.get_tile(pos) == Tile::Hold {
+                    failed_holds.insert(pos);
+                }
+                self.collide(pos, next_pos);
+            }
+        }
+        LaneResult {
+            balls: self.balls.changes,
+            ages: self.ages.changes,
+            moves: self.moves,
+            duplicated: self.duplicated,
+            active: self.active,
+            destroyed: self.destroyed,
+        }
+    }
+
+    //a ball moving from `from` ran into one at `to` that isn't getting out of the way
+    fn collide(&mut self, from: [i32; 2], to: [i32; 2]) {
+        let (Some(moving), Some(other)) = (self.balls.get(from), self.balls.get(to)) else {
+            return;
+        };
+        if self.collision != Collision::Block {
+            self.active.extend([from, to]);
+        }
+        match self.collision {
+            Collision::Block => {}
+            Collision::Swap => {
+                if !self.chunks.get_tile(to).is_solid() {
+                    self.balls.insert(to, moving);
+                    self.balls.insert(from, other);
+                    let (moving_age, other_age) = (self.ages.remove(from), self.ages.remove(to));
+                    if let Some(age) = moving_age {
+                        self.ages.insert(to, age);
+                    }
+                    if let Some(age) = other_age {
+                        self.ages.insert(from, age);
+                    }
+                    self.moves.extend([(from, to), (to, from)]);
+                }
+            }
+            Collision::Merge => {
+                self.balls.remove(from);
+                self.ages.remove(from);
+                self.balls.insert(to, (moving.0 || other.0, other.1));
+                self.destroyed.push(from);
+            }
+            Collision::Annihilate => {
+                if moving.0 != other.0 {
+                    self.balls.remove(from);
+                    self.balls.remove(to);
+                    self.ages.remove(from);
+                    self.ages.remove(to);
+                    self.destroyed.extend([from, to]);
+                }
+            }
+        }
+    }
+}
+
+pub fn step_pos(pos: [i32; 2], dir: Direction) -> [i32; 2] {
+    match dir {
+        Direction::Up => [pos[0], pos[1] + 1],
+        Direction::Down => [pos[0], pos[1] - 1],
+        Direction::Left => [pos[0] - 1, pos[1]],
+        Direction::Right => [pos[0] + 1, pos[1]],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ball::Direction::{self, *},
+        rules::{Collision, Rules},
+        tiles::Tile,
+        world::World,
+    };
+
+    type Balls = Vec<([i32; 2], (bool, Direction))>;
+
+    //the balls left after one Right phase along a row starting at the origin
+    fn step_right(collision: Collision, tiles: &[Tile], balls: Balls) -> Balls {
+        let mut world = World::default();
+        world
+            .set_rules(Rules {
+                collision,
+                ..Rules::default()
+            })
+            .unwrap();
+        tiles.iter().enumerate().for_each(|(x, tile)| {
+            world.set_tile([x as i32, 0], *tile);
+        });
+        balls
+            .into_iter()
+            .for_each(|(pos, ball)| world.set_ball(pos, ball));
+        world.step_phase(Right);
+        let mut balls: Balls = world.balls().collect();
+        balls.sort_by_key(|(pos, _)| *pos);
+        balls
+    }
+
+    //a ball heading right into one heading left, which stays put during the Right phase
+    fn head_on(collision: Collision, other_on: bool) -> Balls {
+        step_right(
+            collision,
+            &[Tile::Right, Tile::Left],
+            vec![([0, 0], (true, Right)), ([1, 0], (other_on, Left))],
+        )
+    }
+
+    //a ball shoving two held balls against a block, so they collide in turn from the front
+    fn hold_chain(collision: Collision) -> Balls {
+        step_right(
+            collision,
+            &[Tile::Right, Tile::Hold, Tile::Hold, Tile::Block],
+            vec![
+                ([0, 0], (true, Right)),
+                ([1, 0], (false, Up)),
+                ([2, 0], (true, Down)),
+            ],
+        )
+    }
+
+    #[test]
+    fn block() {
+        assert_eq!(
+            head_on(Collision::Block, false),
+            [([0, 0], (true, Right)), ([1, 0], (false, Left))]
+        );
+        assert_eq!(
+            hold_chain(Collision::Block),
+            [
+                ([0, 0], (true, Right)),
+                ([1, 0], (false, Up)),
+                ([2, 0], (true, Down))
+            ]
+        );
+    }
+
+    #[test]
+    fn swap() {
+        assert_eq!(
+            head_on(Collision::Swap, false),
+            [([0, 0], (false, Left)), ([1, 0], (true, Right))]
+        );
+        assert_eq!(
+            hold_chain(Collision::Swap),
+            [
+                ([0, 0], (true, Down)),
+                ([1, 0], (true, Right)),
+                ([2, 0], (false, Up))
+            ]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        assert_eq!(head_on(Collision::Merge, false), [([1, 0], (true, Left))]);
+        //the front pair merges first, leaving room for the ball that shoved them
+        assert_eq!(
+            hold_chain(Collision::Merge),
+            [([1, 0], (true, Right)), ([2, 0], (true, Down))]
+        );
+    }
+
+    #[test]
+    fn annihilate() {
+        assert_eq!(head_on(Collision::Annihilate, false), []);
+        assert_eq!(
+            head_on(Collision::Annihilate, true),
+            [([0, 0], (true, Right)), ([1, 0], (true, Left))]
+        );
+        assert_eq!(hold_chain(Collision::Annihilate), [([1, 0], (true, Right))]);
+    }
+}
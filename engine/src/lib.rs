@@ -0,0 +1,13 @@
+//the simulation on its own, the app, the python module and the c api all step worlds from here
+pub mod ball;
+pub mod chunk;
+pub mod debug_log;
+pub mod events;
+pub mod hash;
+pub mod lane;
+pub mod rules;
+pub mod save;
+pub mod stats;
+pub mod tile_data;
+pub mod tiles;
+pub mod world;
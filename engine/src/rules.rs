@@ -0,0 +1,178 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::Direction;
+
+//named orders for the moving directions of a full update
+pub const PHASE_PRESETS: [(&str, [Direction; 4]); 4] = [
+    (
+        "classic",
+        [
+            Direction::Up,
+            Direction::Right,
+            Direction::Left,
+            Direction::Down,
+        ],
+    ),
+    (
+        "clockwise",
+        [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ],
+    ),
+    (
+        "vertical first",
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ],
+    ),
+    (
+        "horizontal first",
+        [
+            Direction::Right,
+            Direction::Left,
+            Direction::Up,
+            Direction::Down,
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepMode {
+    //one direction at a time, balls see the moves made earlier in the same update
+    Sequential,
+    //every ball decides from the previous update and all moves happen at once
+    Synchronous,
+}
+
+//what happens when a moving ball runs into one that stays put
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Collision {
+    //the moving ball stalls
+    Block,
+    //the two balls trade places
+    Swap,
+    //the moving ball is absorbed, the other one is on if either was
+    Merge,
+    //balls in opposite states destroy each other, equal ones block
+    Annihilate,
+}
+
+impl Collision {
+    pub const ALL: [Collision; 4] = [
+        Collision::Block,
+        Collision::Swap,
+        Collision::Merge,
+        Collision::Annihilate,
+    ];
+}
+
+//what happens once there are more balls than the population cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapPolicy {
+    //duplicators stop making copies
+    StopDuplication,
+    //the oldest balls are removed after each update
+    DeleteOldest,
+    //updates are refused until balls are removed or the cap is raised
+    Pause,
+}
+
+impl CapPolicy {
+    pub const ALL: [CapPolicy; 3] = [
+        CapPolicy::StopDuplication,
+        CapPolicy::DeleteOldest,
+        CapPolicy::Pause,
+    ];
+}
+
+//simulation rules saved with each world so machines behave the same everywhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    pub step_mode: StepMode,
+    //where balls on Empty tiles head, None keeps them going the way they were
+    pub gravity: Option<Direction>,
+    //balls only move in the direction of the current phase, so this order decides who goes first
+    pub phase_order: [Direction; 4],
+    //only sequential stepping has balls running into each other, synchronous stepping needs Block
+    pub collision: Collision,
+    //updates a ball survives before it is destroyed
+    pub lifetime: Option<u32>,
+    pub population_cap: Option<u32>,
+    pub cap_policy: CapPolicy,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            step_mode: StepMode::Sequential,
+            gravity: None,
+            phase_order: PHASE_PRESETS[0].1,
+            collision: Collision::Block,
+            lifetime: None,
+            population_cap: None,
+            cap_policy: CapPolicy::StopDuplication,
+        }
+    }
+}
+
+impl Rules {
+    //stored as text in saves so new rules don't need a new save version
+    pub fn to_text(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        let mut rules: Self = toml::from_str(text).unwrap_or_else(|e| {
+            log::warn!("ignoring invalid world rules: {e}");
+            Self::default()
+        });
+        if let Err(e) = rules.check() {
+            log::warn!("ignoring the collision rule: {e}");
+            rules.collision = Collision::Block;
+        }
+        rules
+    }
+
+    //combinations the simulation has no way to follow
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.step_mode == StepMode::Synchronous && self.collision != Collision::Block {
+            bail!(
+                "synchronous stepping moves every ball at once, so it can't have {:?} collisions",
+                self.collision
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synchronous_stepping_only_blocks() {
+        let rules = Rules {
+            step_mode: StepMode::Synchronous,
+            collision: Collision::Swap,
+            ..Rules::default()
+        };
+        assert!(rules.check().is_err());
+        assert_eq!(
+            Rules::from_text(&rules.to_text()).collision,
+            Collision::Block
+        );
+        let rules = Rules {
+            collision: Collision::Block,
+            ..rules
+        };
+        assert!(rules.check().is_ok());
+    }
+}
@@ -0,0 +1,282 @@
+use std::{fs, io::Read, path::Path};
+
+use anyhow::{anyhow, bail};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{BallPosition, Direction},
+    chunk::{Chunk, ChunkPosition},
+    hash::HashMap,
+    rules::Rules,
+    tile_data::TileData,
+    tiles::Tile,
+};
+
+//bumped whenever the layout below changes
+const WORLD_VERSION: u32 = 1;
+//compressed saves start with zstd's frame magic number rather than a version
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const ZSTD_LEVEL: i32 = 3;
+//a few kilobytes of level code can decompress to gigabytes, no real world gets near this
+const MAX_WORLD_BYTES: u64 = 256 << 20;
+//marks level codes so pasting something else fails with a clear message
+const LEVEL_CODE_PREFIX: &str = "ballsim:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraSave {
+    pub pos: [f32; 2],
+    //cells across the screen, the zoom level is derived from it
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldSave {
+    version: u32,
+    pub camera: CameraSave,
+    //tiles in the same layout as `Chunk::data`, run length encoded in the file
+    #[serde(with = "runs")]
+    pub chunks: Vec<(ChunkPosition, Vec<u8>)>,
+    #[serde(with = "runs")]
+    pub overlay: Vec<(ChunkPosition, Vec<u8>)>,
+    pub balls: Vec<(BallPosition, (bool, Direction))>,
+    pub labels: Vec<([i32; 2], String)>,
+    //`Rules` as toml
+    rules: String,
+    //`TileData` as toml for each cell that has any
+    tile_data: Vec<([i32; 2], String)>,
+}
+
+//most chunks are nearly all one tile, so they are stored as runs of the same tile
+mod runs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::chunk::ChunkPosition;
+
+    type Chunks = Vec<(ChunkPosition, Vec<u8>)>;
+
+    pub fn encode(data: &[u8]) -> Vec<(u8, u16)> {
+        let mut runs: Vec<(u8, u16)> = vec![];
+        data.iter().for_each(|tile| match runs.last_mut() {
+            Some((last, count)) if last == tile && *count < u16::MAX => *count += 1,
+            _ => runs.push((*tile, 1)),
+        });
+        runs
+    }
+
+    pub fn decode(runs: &[(u8, u16)]) -> Vec<u8> {
+        runs.iter()
+            .flat_map(|(tile, count)| std::iter::repeat_n(*tile, *count as usize))
+            .collect()
+    }
+
+    pub fn serialize<S: Serializer>(chunks: &Chunks, serializer: S) -> Result<S::Ok, S::Error> {
+        chunks
+            .iter()
+            .map(|(pos, data)| (*pos, encode(data)))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Chunks, D::Error> {
+        let chunks: Vec<(ChunkPosition, Vec<(u8, u16)>)> = Deserialize::deserialize(deserializer)?;
+        Ok(chunks
+            .into_iter()
+            .map(|(pos, runs)| (pos, decode(&runs)))
+            .collect())
+    }
+}
+
+//checks every tile and the length of each chunk, a save from somewhere else can have anything
+pub fn to_chunks(
+    chunks: Vec<(ChunkPosition, Vec<u8>)>,
+) -> anyhow::Result<HashMap<ChunkPosition, Chunk>> {
+    chunks
+        .into_iter()
+        .map(|(pos, data)| {
+            if let Some(tile) = data.iter().find(|tile| Tile::try_from(**tile).is_err()) {
+                bail!("chunk {pos:?} has an unknown tile {tile}");
+            }
+            let data = data
+                .try_into()
+                .map_err(|data: Vec<u8>| anyhow!("chunk {pos:?} has {} tiles", data.len()))?;
+            Ok((pos, Chunk { data }))
+        })
+        .collect()
+}
+
+//stops at `limit` bytes instead of decompressing whatever the file claims
+fn decompress(bytes: &[u8], limit: u64) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    zstd::Decoder::new(bytes)?
+        .take(limit + 1)
+        .read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > limit {
+        bail!("the world is over {} MB once decompressed", limit >> 20);
+    }
+    Ok(decoded)
+}
+
+impl WorldSave {
+    pub fn new(
+        camera: CameraSave,
+        chunks: Vec<(ChunkPosition, Vec<u8>)>,
+        overlay: Vec<(ChunkPosition, Vec<u8>)>,
+        balls: Vec<(BallPosition, (bool, Direction))>,
+        labels: Vec<([i32; 2], String)>,
+        rules: &Rules,
+        tile_data: Vec<([i32; 2], &TileData)>,
+    ) -> Self {
+        Self {
+            version: WORLD_VERSION,
+            camera,
+            chunks,
+            overlay,
+            balls,
+            labels,
+            rules: rules.to_text(),
+            tile_data: tile_data
+                .into_iter()
+                .map(|(pos, data)| (pos, data.to_text()))
+                .collect(),
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules::from_text(&self.rules)
+    }
+
+    pub fn tile_data(&self) -> impl Iterator<Item = ([i32; 2], TileData)> + '_ {
+        self.tile_data
+            .iter()
+            .map(|(pos, text)| (*pos, TileData::from_text(text)))
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    //the whole world as text that survives being pasted into a chat,
+    //always compressed since that is where they have to be short
+    pub fn to_code(&self) -> anyhow::Result<String> {
+        let bytes = self.to_bytes(true)?;
+        Ok(format!(
+            "{LEVEL_CODE_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(bytes)
+        ))
+    }
+
+    //chat clients like to wrap long lines, so whitespace is ignored
+    pub fn from_code(code: &str) -> anyhow::Result<Self> {
+        let code = code
+            .trim()
+            .strip_prefix(LEVEL_CODE_PREFIX)
+            .ok_or_else(|| anyhow!("level codes start with {LEVEL_CODE_PREFIX}"))?;
+        let code: String = code.split_whitespace().collect();
+        Self::from_bytes(URL_SAFE_NO_PAD.decode(code)?)
+    }
+
+    pub fn from_bytes(mut bytes: Vec<u8>) -> anyhow::Result<Self> {
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            bytes = decompress(&bytes, MAX_WORLD_BYTES)?;
+        }
+        //the version always comes first so it can be read before knowing the layout
+        match bincode::deserialize::<u32>(&bytes)? {
+            WORLD_VERSION => Ok(bincode::deserialize(&bytes)?),
+            version => bail!(
+                "the world has version {version} but only version {WORLD_VERSION} is supported"
+            ),
+        }
+    }
+
+    pub fn write(&self, path: &Path, compress: bool) -> anyhow::Result<()> {
+        fs::write(path, self.to_bytes(compress)?)?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self, compress: bool) -> anyhow::Result<Vec<u8>> {
+        let bytes = bincode::serialize(self)?;
+        if compress {
+            return Ok(zstd::encode_all(bytes.as_slice(), ZSTD_LEVEL)?);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::CHUNK_SIZE;
+
+    fn chunk(x: i32, y: i32) -> ChunkPosition {
+        ChunkPosition { position: [x, y] }
+    }
+
+    //a run too long for one u16 count, a chunk of nothing but empty tiles and one with no
+    //data at all
+    fn world() -> WorldSave {
+        let mut long = vec![3; u16::MAX as usize + 10];
+        long.extend([4, 4, 0]);
+        WorldSave::new(
+            CameraSave {
+                pos: [1.5, -2.0],
+                width: 40.0,
+            },
+            vec![
+                (chunk(0, 0), long),
+                (chunk(-1, 2), vec![0; CHUNK_SIZE * CHUNK_SIZE]),
+            ],
+            vec![(chunk(5, 5), vec![])],
+            vec![(BallPosition { position: [3, -7] }, (true, Direction::Left))],
+            vec![([0, 1], "start".to_owned())],
+            &Rules::default(),
+            vec![],
+        )
+    }
+
+    fn assert_same(loaded: &WorldSave, saved: &WorldSave) {
+        assert_eq!(loaded.camera, saved.camera);
+        assert_eq!(loaded.chunks, saved.chunks);
+        assert_eq!(loaded.overlay, saved.overlay);
+        assert_eq!(loaded.balls, saved.balls);
+        assert_eq!(loaded.labels, saved.labels);
+        assert_eq!(loaded.rules, saved.rules);
+    }
+
+    #[test]
+    fn long_runs_are_split() {
+        let data = &world().chunks[0].1;
+        let runs = runs::encode(data);
+        assert_eq!(runs[..2], [(3, u16::MAX), (3, 10)]);
+        assert_eq!(runs::decode(&runs), *data);
+        assert!(runs::encode(&[]).is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let saved = world();
+        [false, true].into_iter().for_each(|compress| {
+            let bytes = saved.to_bytes(compress).unwrap();
+            assert_eq!(bytes.starts_with(&ZSTD_MAGIC), compress);
+            assert_same(&WorldSave::from_bytes(bytes).unwrap(), &saved);
+        });
+        assert_same(
+            &WorldSave::from_code(&saved.to_code().unwrap()).unwrap(),
+            &saved,
+        );
+    }
+
+    #[test]
+    fn decompressing_stops_at_the_limit() {
+        let bytes = zstd::encode_all([0; 4096].as_slice(), ZSTD_LEVEL).unwrap();
+        assert_eq!(decompress(&bytes, 4096).unwrap().len(), 4096);
+        assert!(decompress(&bytes, 4095).is_err());
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let mut bytes = world().to_bytes(false).unwrap();
+        bytes[..4].copy_from_slice(&(WORLD_VERSION + 1).to_le_bytes());
+        assert!(WorldSave::from_bytes(bytes).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+//what the simulation has been doing, refreshed after every update
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimStats {
+    //updates run since the simulation was created or a world was loaded
+    pub tick: u64,
+    //updates run since the world was last reset to how it was edited
+    pub run_ticks: u64,
+    pub balls: usize,
+    //counts for the last update only
+    pub moved: usize,
+    pub destroyed: usize,
+    pub duplicated: usize,
+    pub step_time: Duration,
+    //when the current ticks per second measurement began and the tick it began at
+    rate_start: Option<(Instant, u64)>,
+    ticks_per_second: f32,
+}
+
+impl SimStats {
+    pub fn count_tick(&mut self) {
+        //how long each ticks per second measurement covers
+        const RATE_WINDOW: Duration = Duration::from_millis(500);
+        self.tick += 1;
+        self.run_ticks += 1;
+        let now = Instant::now();
+        match self.rate_start {
+            Some((start, tick)) if now - start >= RATE_WINDOW => {
+                self.ticks_per_second = (self.tick - tick) as f32 / (now - start).as_secs_f32();
+                self.rate_start = Some((now, self.tick));
+            }
+            Some(_) => {}
+            None => self.rate_start = Some((now, self.tick)),
+        }
+    }
+
+    //zero once no update has come for a while
+    pub fn ticks_per_second(&self) -> f32 {
+        const STALE_AFTER: Duration = Duration::from_secs(2);
+        match self.rate_start {
+            Some((start, _)) if start.elapsed() < STALE_AFTER => self.ticks_per_second,
+            _ => 0.0,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "tick": self.tick,
+            "run_ticks": self.run_ticks,
+            "ticks_per_second": self.ticks_per_second(),
+            "balls": self.balls,
+            "moved": self.moved,
+            "destroyed": self.destroyed,
+            "duplicated": self.duplicated,
+            "step_time_ms": self.step_time.as_secs_f64() * 1000.0,
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::tiles::Tile;
+
+//updates between piston pushes when the tile doesn't set its own
+pub const DEFAULT_INTERVAL: u32 = 4;
+//what pressure plates do when the tile doesn't say otherwise
+pub const DEFAULT_CONVERSION: [Tile; 2] = [Tile::Block, Tile::Empty];
+//middle C
+pub const DEFAULT_PITCH: u8 = 60;
+
+//what a cell is for when sweeping a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Probe {
+    //gets an on or off ball at the start of each run
+    Input,
+    //records the first ball seen on it
+    Output,
+}
+
+//parameters a tile needs beyond its kind, only stored for cells where something is set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TileData {
+    //shown in the tile inspector, for explaining what a part of a machine is for
+    pub note: String,
+    //updates between activations of tiles that fire on their own
+    pub interval: Option<u32>,
+    //the portal balls on this one come out of
+    pub link: Option<[i32; 2]>,
+    //the neighbouring tile a pressure plate changes and what it changes it to
+    pub conversion: Option<[Tile; 2]>,
+    //midi note number a note tile plays
+    pub pitch: Option<u8>,
+    //where note tiles send their notes when midi output is on, the channel counts from 1
+    pub midi_channel: Option<u8>,
+    pub midi_program: Option<u8>,
+    //marks the cell as an input or output of the truth table sweep
+    pub probe: Option<Probe>,
+}
+
+impl TileData {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    //saved as toml so new parameters don't change the world format
+    pub fn to_text(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        toml::from_str(text).unwrap_or_else(|e| {
+            log::warn!("ignoring invalid tile data: {e}");
+            Self::default()
+        })
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval.unwrap_or(DEFAULT_INTERVAL).max(1)
+    }
+
+    pub fn conversion(&self) -> [Tile; 2] {
+        self.conversion.unwrap_or(DEFAULT_CONVERSION)
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch.unwrap_or(DEFAULT_PITCH).min(127)
+    }
+
+    pub fn midi_channel(&self) -> u8 {
+        self.midi_channel.unwrap_or(1).clamp(1, 16)
+    }
+
+    pub fn midi_program(&self) -> u8 {
+        self.midi_program.unwrap_or(0).min(127)
+    }
+}
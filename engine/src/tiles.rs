@@ -0,0 +1,297 @@
+use crate::ball::Direction;
+use serde::{Deserialize, Serialize};
+
+//the simulation only reads the ground, overlay tiles are just drawn on top of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Layer {
+    Ground,
+    Overlay,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 2] = [Layer::Ground, Layer::Overlay];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tile {
+    Up,
+    Down,
+    Left,
+    Right,
+    Hold,
+    Block,
+    DuplicateH,
+    FilterR,
+    Destroy,
+    Empty,
+    FilterU,
+    FilterD,
+    FilterL,
+    DuplicateV,
+    //only destroys on balls, off balls pass as if it was empty
+    DestroyOn,
+    DestroyOff,
+    //solid, every few updates pushes the balls in front of it one cell in the direction it faces
+    PistonU,
+    PistonR,
+    PistonD,
+    PistonL,
+    //keeps any ball that enters it, unlike hold the ball isn't shoved along by ones running
+    //into it, only pistons move it
+    Sticky,
+    //move the ball one cell diagonally once the four phases are done
+    UpRight,
+    DownRight,
+    DownLeft,
+    UpLeft,
+    //balls on a portal jump to the cell in front of the portal it links to and leave facing the
+    //same way as it, unlinked portals just hold them
+    PortalU,
+    PortalR,
+    PortalD,
+    PortalL,
+    //while a ball is on it, the neighbouring tiles it is set up for are swapped for another
+    PressurePlate,
+    //plays its pitch whenever a ball enters it
+    Note,
+}
+
+impl From<Tile> for u8 {
+    fn from(value: Tile) -> Self {
+        match value {
+            Tile::Up => 0,
+            Tile::Down => 1,
+            Tile::Left => 2,
+            Tile::Right => 3,
+            Tile::Hold => 4,
+            Tile::Block => 5,
+            Tile::DuplicateH => 6,
+            Tile::FilterR => 7,
+            Tile::Destroy => 8,
+            Tile::Empty => 9,
+            Tile::FilterU => 10,
+            Tile::FilterD => 11,
+            Tile::FilterL => 12,
+            Tile::DuplicateV => 13,
+            Tile::DestroyOn => 14,
+            Tile::DestroyOff => 15,
+            Tile::PistonU => 16,
+            Tile::PistonR => 17,
+            Tile::PistonD => 18,
+            Tile::PistonL => 19,
+            Tile::Sticky => 20,
+            Tile::UpRight => 21,
+            Tile::DownRight => 22,
+            Tile::DownLeft => 23,
+            Tile::UpLeft => 24,
+            Tile::PortalU => 25,
+            Tile::PortalR => 26,
+            Tile::PortalD => 27,
+            Tile::PortalL => 28,
+            Tile::PressurePlate => 29,
+            Tile::Note => 30,
+        }
+    }
+}
+
+impl TryFrom<u8> for Tile {
+    type Error = ();
+
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Tile::Up,
+            1 => Self::Down,
+            2 => Self::Left,
+            3 => Self::Right,
+            4 => Self::Hold,
+            5 => Self::Block,
+            6 => Self::DuplicateH,
+            7 => Self::FilterR,
+            8 => Self::Destroy,
+            9 => Self::Empty,
+            10 => Self::FilterU,
+            11 => Self::FilterD,
+            12 => Self::FilterL,
+            13 => Self::DuplicateV,
+            14 => Self::DestroyOn,
+            15 => Self::DestroyOff,
+            16 => Self::PistonU,
+            17 => Self::PistonR,
+            18 => Self::PistonD,
+            19 => Self::PistonL,
+            20 => Self::Sticky,
+            21 => Self::UpRight,
+            22 => Self::DownRight,
+            23 => Self::DownLeft,
+            24 => Self::UpLeft,
+            25 => Self::PortalU,
+            26 => Self::PortalR,
+            27 => Self::PortalD,
+            28 => Self::PortalL,
+            29 => Self::PressurePlate,
+            30 => Self::Note,
+            _ => Err(())?,
+        })
+    }
+}
+
+impl Tile {
+    //one past the highest tile value
+    pub const COUNT: u8 = 31;
+
+    //the variant's name, the same one serde uses
+    pub fn name(self) -> String {
+        format!("{self:?}")
+    }
+
+    pub fn from_name(name: &str) -> Option<Tile> {
+        (0..Tile::COUNT)
+            .filter_map(|value| Tile::try_from(value).ok())
+            .find(|tile| tile.name() == name)
+    }
+
+    //balls can't move into these
+    pub fn is_solid(self) -> bool {
+        matches!(
+            self,
+            Tile::Block | Tile::PistonU | Tile::PistonR | Tile::PistonD | Tile::PistonL
+        )
+    }
+
+    //the offset a diagonal tile moves balls by
+    pub fn diagonal(self) -> Option<[i32; 2]> {
+        match self {
+            Tile::UpRight => Some([1, 1]),
+            Tile::DownRight => Some([1, -1]),
+            Tile::DownLeft => Some([-1, -1]),
+            Tile::UpLeft => Some([-1, 1]),
+            _ => None,
+        }
+    }
+
+    fn from_diagonal(offset: [i32; 2]) -> Tile {
+        match offset {
+            [1, 1] => Tile::UpRight,
+            [1, _] => Tile::DownRight,
+            [_, 1] => Tile::UpLeft,
+            _ => Tile::DownLeft,
+        }
+    }
+
+    pub fn portal_facing(self) -> Option<Direction> {
+        match self {
+            Tile::PortalU => Some(Direction::Up),
+            Tile::PortalR => Some(Direction::Right),
+            Tile::PortalD => Some(Direction::Down),
+            Tile::PortalL => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    //balls on these skip the phases and move once they are done
+    pub fn jumps(self) -> bool {
+        self.diagonal().is_some() || self.portal_facing().is_some()
+    }
+
+    pub fn piston_facing(self) -> Option<Direction> {
+        match self {
+            Tile::PistonU => Some(Direction::Up),
+            Tile::PistonR => Some(Direction::Right),
+            Tile::PistonD => Some(Direction::Down),
+            Tile::PistonL => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    pub fn destroys(self, on: bool) -> bool {
+        match self {
+            Tile::Destroy => true,
+            Tile::DestroyOn => on,
+            Tile::DestroyOff => !on,
+            _ => false,
+        }
+    }
+
+    //the tile that behaves the same once the world is flipped on the given axes
+    pub fn mirrored(self, x: bool, y: bool) -> Tile {
+        if let Some([dx, dy]) = self.diagonal() {
+            return Tile::from_diagonal([if x { -dx } else { dx }, if y { -dy } else { dy }]);
+        }
+        match self {
+            Tile::Left if x => Tile::Right,
+            Tile::Right if x => Tile::Left,
+            Tile::FilterL if x => Tile::FilterR,
+            Tile::FilterR if x => Tile::FilterL,
+            Tile::PistonL if x => Tile::PistonR,
+            Tile::PistonR if x => Tile::PistonL,
+            Tile::PortalL if x => Tile::PortalR,
+            Tile::PortalR if x => Tile::PortalL,
+            Tile::Up if y => Tile::Down,
+            Tile::Down if y => Tile::Up,
+            Tile::FilterU if y => Tile::FilterD,
+            Tile::FilterD if y => Tile::FilterU,
+            Tile::PistonU if y => Tile::PistonD,
+            Tile::PistonD if y => Tile::PistonU,
+            Tile::PortalU if y => Tile::PortalD,
+            Tile::PortalD if y => Tile::PortalU,
+            tile => tile,
+        }
+    }
+
+    //the tile after turning the world a quarter turn clockwise
+    pub fn rotated(self) -> Tile {
+        match self {
+            Tile::Up => Tile::Right,
+            Tile::Right => Tile::Down,
+            Tile::Down => Tile::Left,
+            Tile::Left => Tile::Up,
+            Tile::FilterU => Tile::FilterR,
+            Tile::FilterR => Tile::FilterD,
+            Tile::FilterD => Tile::FilterL,
+            Tile::FilterL => Tile::FilterU,
+            Tile::DuplicateH => Tile::DuplicateV,
+            Tile::DuplicateV => Tile::DuplicateH,
+            Tile::PistonU => Tile::PistonR,
+            Tile::PistonR => Tile::PistonD,
+            Tile::PistonD => Tile::PistonL,
+            Tile::PistonL => Tile::PistonU,
+            Tile::UpRight => Tile::DownRight,
+            Tile::DownRight => Tile::DownLeft,
+            Tile::DownLeft => Tile::UpLeft,
+            Tile::UpLeft => Tile::UpRight,
+            Tile::PortalU => Tile::PortalR,
+            Tile::PortalR => Tile::PortalD,
+            Tile::PortalD => Tile::PortalL,
+            Tile::PortalL => Tile::PortalU,
+            tile => tile,
+        }
+    }
+}
+
+impl TryFrom<char> for Tile {
+    type Error = ();
+
+    //the characters used to draw tiles in template files
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        Ok(match value {
+            '^' => Tile::Up,
+            'v' => Tile::Down,
+            '<' => Tile::Left,
+            '>' => Tile::Right,
+            'H' => Tile::Hold,
+            'S' => Tile::Sticky,
+            '#' => Tile::Block,
+            '=' => Tile::DuplicateH,
+            '|' => Tile::DuplicateV,
+            'X' => Tile::Destroy,
+            '+' => Tile::DestroyOn,
+            '-' => Tile::DestroyOff,
+            '.' | ' ' => Tile::Empty,
+            'R' => Tile::FilterR,
+            'L' => Tile::FilterL,
+            'U' => Tile::FilterU,
+            'D' => Tile::FilterD,
+            _ => Err(())?,
+        })
+    }
+}
@@ -0,0 +1,1091 @@
+use std::{array::from_fn, cmp::Reverse, time::Instant};
+
+use rayon::prelude::*;
+
+use crate::{
+    ball::{BallPosition, Direction},
+    chunk::{Chunk, ChunkPosition, CHUNK_SIZE},
+    debug_log::DebugLog,
+    events::{Events, SimEvent, SubscriptionId},
+    hash::{HashMap, HashSet},
+    lane::{self, step_pos, Lane, LaneResult},
+    rules::{CapPolicy, Rules, StepMode},
+    save::{self, WorldSave},
+    stats::SimStats,
+    tile_data::{TileData, DEFAULT_CONVERSION, DEFAULT_INTERVAL},
+    tiles::Tile,
+};
+
+//below this many balls spreading the work over threads costs more than it saves
+const PARALLEL_MIN: usize = 4096;
+
+//cells that changed since they were last taken, only kept while `track_changes` is on
+#[derive(Debug, Default)]
+pub struct Changes {
+    //the world was swapped for another
+    pub everything: bool,
+    pub chunks: HashSet<ChunkPosition>,
+    pub balls: HashSet<[i32; 2]>,
+}
+
+//what happened to the followed ball, with the tick it happened on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Followed {
+    Moved(u64, [i32; 2]),
+    Destroyed(u64),
+}
+
+//the tiles and balls of a world and everything needed to step it, nothing here draws or plays
+//anything. iterating the hash maps below visits cells in a different order on every run, so
+//anything where the order changes the outcome sorts by position first
+pub struct World {
+    pub chunks: HashMap<ChunkPosition, Chunk>,
+    pub balls: HashMap<BallPosition, (bool, Direction)>,
+    //updates each ball has been around for, missing means it is new
+    pub ages: HashMap<BallPosition, u32>,
+    //parameters of the tiles that have any
+    pub tile_data: HashMap<[i32; 2], TileData>,
+    //cells held changed by pressure plates and the tile they had before
+    pub pressed: HashMap<[i32; 2], Tile>,
+    rules: Rules,
+    //chunks where nothing happened nearby last update, their balls are skipped
+    asleep: HashSet<ChunkPosition>,
+    //chunks where balls moved, appeared or disappeared during this update
+    active: HashSet<ChunkPosition>,
+    scratch: Scratch,
+    //balls needed before phases are stepped on several threads, PARALLEL_MIN unless a test forces it
+    parallel_min: usize,
+    pub debug_log: DebugLog,
+    pub stats: SimStats,
+    events: Events,
+    changes: Option<Changes>,
+    //the ball whose moves are written down, and what happened to it since it was last asked
+    follow: Option<[i32; 2]>,
+    followed: Vec<Followed>,
+}
+
+//buffers sim_step clears and reuses so running the simulation doesn't allocate every update
+#[derive(Default)]
+struct Scratch {
+    steered: Vec<(BallPosition, Steer)>,
+    //positions to move in each strip of chunks, keyed by the strip
+    lanes: HashMap<i32, Vec<[i32; 2]>>,
+    to_duplicate: Vec<BallPosition>,
+    //cells a ball arrived in during this update, they don't move again until the next
+    dont_move: HashSet<[i32; 2]>,
+    //duplicators that already made their copy this update
+    duplicated: HashSet<[i32; 2]>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::default(),
+            balls: HashMap::default(),
+            ages: HashMap::default(),
+            tile_data: HashMap::default(),
+            pressed: HashMap::default(),
+            rules: Rules::default(),
+            asleep: HashSet::default(),
+            active: HashSet::default(),
+            scratch: Scratch::default(),
+            parallel_min: PARALLEL_MIN,
+            debug_log: DebugLog::default(),
+            stats: SimStats::default(),
+            events: Events::default(),
+            changes: None,
+            follow: None,
+            followed: vec![],
+        }
+    }
+}
+
+impl World {
+    //everything a save holds that is stepped, the camera, overlay and labels are left to
+    //whoever shows them. all of it is checked first so a broken save leaves the world as it was
+    pub fn load(&mut self, save: WorldSave) -> anyhow::Result<()> {
+        let (rules, tile_data) = (save.rules(), save.tile_data().collect());
+        let chunks = save::to_chunks(save.chunks)?;
+        let mut balls = HashMap::default();
+        for (pos, ball) in save.balls {
+            if balls.insert(pos, ball).is_some() {
+                anyhow::bail!("there are two balls at {:?}", pos.position);
+            }
+        }
+        self.clear();
+        self.rules = rules;
+        self.tile_data = tile_data;
+        self.chunks = chunks;
+        self.balls = balls;
+        Ok(())
+    }
+
+    //an empty world with the default rules
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.balls.clear();
+        self.ages.clear();
+        self.tile_data.clear();
+        self.pressed.clear();
+        self.rules = Rules::default();
+        self.asleep.clear();
+        self.stats = SimStats::default();
+        self.follow = None;
+        if let Some(changes) = self.changed() {
+            changes.everything = true;
+        }
+    }
+
+    //returns the tile that was there, a cell a plate changed counts as what it was underneath
+    pub fn set_tile(&mut self, pos: [i32; 2], tile: Tile) -> Tile {
+        self.wake(pos);
+        //editing a cell a plate changed replaces what it was underneath
+        let old = self
+            .pressed
+            .remove(&pos)
+            .unwrap_or_else(|| self.get_tile(pos));
+        if let Some(changes) = self.changed() {
+            changes.chunks.insert(chunk_of(pos));
+        }
+        write_tile(&mut self.chunks, pos, tile);
+        old
+    }
+
+    pub fn get_tile(&self, pos: [i32; 2]) -> Tile {
+        self.chunks.get_tile(pos)
+    }
+
+    pub fn set_ball(&mut self, pos: [i32; 2], ball: (bool, Direction)) {
+        self.balls.insert(BallPosition { position: pos }, ball);
+        self.ages.remove(&BallPosition { position: pos });
+        self.wake(pos);
+        self.ball_changed(pos);
+    }
+
+    pub fn remove_ball(&mut self, pos: [i32; 2]) {
+        self.balls.remove(&BallPosition { position: pos });
+        self.ages.remove(&BallPosition { position: pos });
+        self.wake(pos);
+        self.ball_changed(pos);
+    }
+
+    pub fn get_ball(&self, pos: [i32; 2]) -> Option<(bool, Direction)> {
+        self.balls.get(&BallPosition { position: pos }).copied()
+    }
+
+    pub fn tile_data(&self, pos: [i32; 2]) -> Option<&TileData> {
+        self.tile_data.get(&pos)
+    }
+
+    //for state behaviors keep while running
+    pub fn tile_data_mut(&mut self, pos: [i32; 2]) -> &mut TileData {
+        self.tile_data.entry(pos).or_default()
+    }
+
+    //default data is the same as none, so it isn't stored
+    pub fn set_tile_data(&mut self, pos: [i32; 2], data: TileData) {
+        if data.is_default() {
+            self.tile_data.remove(&pos);
+        } else {
+            self.tile_data.insert(pos, data);
+        }
+    }
+
+    //cells changed from here on are collected until `take_changes`
+    pub fn track_changes(&mut self, on: bool) {
+        self.changes = on.then(Changes::default);
+    }
+
+    //what changed since the last time, None unless changes are tracked
+    pub fn take_changes(&mut self) -> Option<Changes> {
+        self.changed().map(std::mem::take)
+    }
+
+    fn changed(&mut self) -> Option<&mut Changes> {
+        self.changes.as_mut()
+    }
+
+    //moves and the destruction of the ball at `pos` are written down from now on
+    pub fn follow(&mut self, pos: Option<[i32; 2]>) {
+        self.follow = pos;
+        self.followed.clear();
+    }
+
+    //what happened to the followed ball since the last time
+    pub fn followed(&mut self) -> Vec<Followed> {
+        std::mem::take(&mut self.followed)
+    }
+
+    pub fn asleep_chunks(&self) -> usize {
+        self.asleep.len()
+    }
+
+    //returns false if the population cap refused the update
+    pub fn full_step(&mut self) -> bool {
+        if self.rules.cap_policy == CapPolicy::Pause && self.over_cap() {
+            return false;
+        }
+        let start = Instant::now();
+        let before = self.balls.len();
+        self.stats.moved = 0;
+        self.stats.duplicated = 0;
+        //every ball moves at once so there is nothing to skip
+        if self.rules.step_mode == StepMode::Synchronous {
+            self.asleep.clear();
+            self.sync_step();
+        } else {
+            self.scratch.dont_move.clear();
+            self.scratch.duplicated.clear();
+            self.rules
+                .phase_order
+                .into_iter()
+                .for_each(|dir| self.sim_step(dir));
+            self.jump_step();
+        }
+        self.fire_pistons();
+        self.update_plates();
+        self.age_balls();
+        if self.rules.cap_policy == CapPolicy::DeleteOldest {
+            self.delete_oldest();
+        }
+        self.check_followed();
+        self.update_sleep();
+        //balls only appear on duplicators, so everything else that changed the count removed some
+        self.stats.destroyed = (before + self.stats.duplicated).saturating_sub(self.balls.len());
+        self.stats.balls = self.balls.len();
+        self.stats.count_tick();
+        self.stats.step_time = start.elapsed();
+        self.events.dispatch();
+        self.debug_log.log(|| {
+            format!(
+                "update done with {} balls, {} chunks asleep",
+                self.balls.len(),
+                self.asleep.len()
+            )
+        });
+        true
+    }
+
+    //one phase on its own, as if it started a new update
+    pub fn step_phase(&mut self, dir: Direction) {
+        self.scratch.dont_move.clear();
+        self.scratch.duplicated.clear();
+        self.sim_step(dir);
+    }
+
+    //in no particular order
+    pub fn balls(&self) -> impl Iterator<Item = ([i32; 2], (bool, Direction))> + '_ {
+        self.balls.iter().map(|(pos, ball)| (pos.position, *ball))
+    }
+
+    pub fn ball_count(&self) -> usize {
+        self.balls.len()
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) -> anyhow::Result<()> {
+        rules.check()?;
+        self.rules = rules;
+        self.asleep.clear();
+        Ok(())
+    }
+
+    pub fn stats(&self) -> SimStats {
+        self.stats
+    }
+
+    //the subscriber is called with everything that happened once each update is done
+    pub fn subscribe(&mut self, subscriber: impl FnMut(SimEvent) + 'static) -> SubscriptionId {
+        self.events.subscribe(subscriber)
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.events.unsubscribe(id);
+    }
+
+    //a chunk sleeps when nothing happened in it or its neighbours, its balls would only repeat
+    //what they did last update
+    fn update_sleep(&mut self) {
+        let active = std::mem::take(&mut self.active);
+        if self.rules.step_mode == StepMode::Synchronous {
+            return;
+        }
+        let woken: HashSet<ChunkPosition> = active
+            .iter()
+            .flat_map(|chunk| chunk_neighbourhood(*chunk))
+            .collect();
+        self.asleep = self
+            .balls
+            .keys()
+            .map(|pos| chunk_of(pos.position))
+            .filter(|chunk| !woken.contains(chunk))
+            .collect();
+    }
+
+    //edits can change what the balls around them do
+    fn wake(&mut self, pos: [i32; 2]) {
+        if !self.asleep.is_empty() {
+            chunk_neighbourhood(chunk_of(pos)).for_each(|chunk| {
+                self.asleep.remove(&chunk);
+            });
+        }
+    }
+
+    //a ball changed at `pos` during an update
+    fn mark_active(&mut self, pos: [i32; 2]) {
+        let chunk = chunk_of(pos);
+        self.active.insert(chunk);
+        //a ball coming in from a neighbour wakes the chunk right away
+        self.asleep.remove(&chunk);
+    }
+
+    fn over_cap(&self) -> bool {
+        self.rules
+            .population_cap
+            .is_some_and(|cap| self.balls.len() > cap as usize)
+    }
+
+    //how many more balls duplicators are allowed to make
+    fn spare_copies(&self) -> usize {
+        match (self.rules.population_cap, self.rules.cap_policy) {
+            (Some(cap), CapPolicy::StopDuplication) => {
+                (cap as usize).saturating_sub(self.balls.len())
+            }
+            _ => usize::MAX,
+        }
+    }
+
+    //where a ball on a diagonal tile or a linked portal goes instead of following the phases,
+    //and the direction it leaves with
+    fn jump_target(&self, pos: [i32; 2], dir: Direction) -> Option<([i32; 2], Direction)> {
+        let tile = self.chunks.get_tile(pos);
+        if let Some([dx, dy]) = tile.diagonal() {
+            return Some(([pos[0] + dx, pos[1] + dy], dir));
+        }
+        tile.portal_facing()?;
+        let link = self.tile_data.get(&pos)?.link?;
+        let exit = self.chunks.get_tile(link).portal_facing()?;
+        Some((step_pos(link, exit), exit))
+    }
+
+    //a jump can't be split between phases, so balls on jumping tiles wait for them to finish
+    //and then move into free cells in position order. sleeping chunks are checked too since a
+    //portal exit can free up far away from its entrance
+    fn jump_step(&mut self) {
+        let mut movers: Vec<([i32; 2], [i32; 2], Direction)> = self
+            .balls
+            .iter()
+            .filter_map(|(pos, (_, dir))| {
+                let (to, dir) = self.jump_target(pos.position, *dir)?;
+                Some((pos.position, to, dir))
+            })
+            .collect();
+        if movers.is_empty() {
+            return;
+        }
+        movers.sort_by_key(|(from, _, _)| *from);
+        let mut moves = vec![];
+        let mut arrived: HashSet<[i32; 2]> = HashSet::default();
+        movers.into_iter().for_each(|(from, to, dir)| {
+            let blocked = self.get_ball(to).is_some() || self.chunks.get_tile(to).is_solid();
+            //balls that just arrived on another jumping tile wait for the next update
+            if blocked || arrived.contains(&from) {
+                return;
+            }
+            self.move_ball(from, to);
+            if let Some(ball) = self.balls.get_mut(&BallPosition { position: to }) {
+                ball.1 = dir;
+            }
+            arrived.insert(to);
+            moves.push((from, to));
+        });
+        self.stats.moved += moves.len();
+        let events = moves
+            .iter()
+            .map(|(_, to)| SimEvent::BallEntered(self.chunks.get_tile(*to), *to))
+            .collect();
+        self.events.queue(events);
+        self.track_followed(&moves);
+    }
+
+    //for moves made outside the lanes, the ball keeps its age
+    fn move_ball(&mut self, from: [i32; 2], to: [i32; 2]) {
+        let (from_pos, to_pos) = (
+            BallPosition { position: from },
+            BallPosition { position: to },
+        );
+        if let Some(ball) = self.balls.remove(&from_pos) {
+            self.balls.insert(to_pos, ball);
+        }
+        if let Some(age) = self.ages.remove(&from_pos) {
+            self.ages.insert(to_pos, age);
+        }
+        self.mark_active(from);
+        self.mark_active(to);
+        self.ball_changed(from);
+        self.ball_changed(to);
+    }
+
+    fn ball_changed(&mut self, pos: [i32; 2]) {
+        if let Some(changes) = self.changed() {
+            changes.balls.insert(pos);
+        }
+    }
+
+    //moves the followed ball along with its phase, or notices it is gone
+    fn track_followed(&mut self, moves: &[([i32; 2], [i32; 2])]) {
+        let Some(followed) = self.follow else {
+            return;
+        };
+        match moves.iter().find(|(from, _)| *from == followed) {
+            Some((_, to)) => {
+                self.follow = Some(*to);
+                self.followed
+                    .push(Followed::Moved(self.stats.tick + 1, *to));
+            }
+            None => self.check_followed(),
+        }
+    }
+
+    fn check_followed(&mut self) {
+        let Some(followed) = self.follow else {
+            return;
+        };
+        if self.get_ball(followed).is_none() {
+            self.follow = None;
+            self.followed.push(Followed::Destroyed(self.stats.tick + 1));
+        }
+    }
+
+    //plates are checked once the balls are done moving, what a ball left changes back right away
+    fn update_plates(&mut self) {
+        let mut plates: Vec<[i32; 2]> = self
+            .balls
+            .keys()
+            .map(|pos| pos.position)
+            .filter(|pos| self.chunks.get_tile(*pos) == Tile::PressurePlate)
+            .collect();
+        if plates.is_empty() && self.pressed.is_empty() {
+            return;
+        }
+        //the first plate in position order decides cells two plates both change
+        plates.sort();
+        let mut wanted: HashMap<[i32; 2], Tile> = HashMap::default();
+        plates.into_iter().for_each(|plate| {
+            let [from, to] = self
+                .tile_data
+                .get(&plate)
+                .map_or(DEFAULT_CONVERSION, TileData::conversion);
+            [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ]
+            .map(|dir| step_pos(plate, dir))
+            .into_iter()
+            .filter(|cell| {
+                let original = self.pressed.get(cell).copied();
+                original.unwrap_or_else(|| self.chunks.get_tile(*cell)) == from
+            })
+            .for_each(|cell| {
+                wanted.entry(cell).or_insert(to);
+            });
+        });
+        let released: Vec<[i32; 2]> = self
+            .pressed
+            .keys()
+            .filter(|cell| !wanted.contains_key(*cell))
+            .copied()
+            .collect();
+        released.into_iter().for_each(|cell| {
+            if let Some(original) = self.pressed.remove(&cell) {
+                write_tile(&mut self.chunks, cell, original);
+                self.mark_active(cell);
+                if let Some(changes) = self.changed() {
+                    changes.chunks.insert(chunk_of(cell));
+                }
+            }
+        });
+        wanted.into_iter().for_each(|(cell, tile)| {
+            if self.chunks.get_tile(cell) != tile {
+                let original = self.chunks.get_tile(cell);
+                self.pressed.entry(cell).or_insert(original);
+                write_tile(&mut self.chunks, cell, tile);
+                self.mark_active(cell);
+                if let Some(changes) = self.changed() {
+                    changes.chunks.insert(chunk_of(cell));
+                }
+            }
+        });
+    }
+
+    //pistons fire after everything else has moved so pushed balls only move once per update
+    fn fire_pistons(&mut self) {
+        let mut pistons: Vec<([i32; 2], Direction)> = self
+            .chunks
+            .iter()
+            .flat_map(|(chunk_pos, chunk)| {
+                let origin = chunk_pos.position.map(|v| v * CHUNK_SIZE as i32);
+                (0..CHUNK_SIZE as u32)
+                    .flat_map(|x| (0..CHUNK_SIZE as u32).map(move |y| [x, y]))
+                    .filter_map(move |[x, y]| {
+                        let tile = Tile::try_from(chunk.get_tile([x, y])).ok()?;
+                        Some((
+                            [origin[0] + x as i32, origin[1] + y as i32],
+                            tile.piston_facing()?,
+                        ))
+                    })
+            })
+            .filter(|(pos, _)| {
+                let interval = self
+                    .tile_data
+                    .get(pos)
+                    .map_or(DEFAULT_INTERVAL, TileData::interval);
+                (self.stats.tick + 1).is_multiple_of(interval as u64)
+            })
+            .collect();
+        pistons.sort_by_key(|(pos, _)| *pos);
+        pistons
+            .into_iter()
+            .for_each(|(pos, dir)| self.push_balls(step_pos(pos, dir), dir));
+    }
+
+    //moves the row of balls starting at `front` one cell along, unless something solid is at
+    //the far end
+    fn push_balls(&mut self, front: [i32; 2], dir: Direction) {
+        let mut row = vec![];
+        let mut end = front;
+        while self.get_ball(end).is_some() {
+            row.push(end);
+            end = step_pos(end, dir);
+        }
+        if row.is_empty() || self.chunks.get_tile(end).is_solid() {
+            return;
+        }
+        let mut events = vec![];
+        let moves: Vec<([i32; 2], [i32; 2])> = row
+            .into_iter()
+            .rev()
+            .map(|from| (from, step_pos(from, dir)))
+            .collect();
+        moves.iter().for_each(|(from, to)| {
+            self.move_ball(*from, *to);
+            events.push(SimEvent::BallEntered(self.chunks.get_tile(*to), *to));
+        });
+        self.stats.moved += moves.len();
+        self.debug_log
+            .log(|| format!("piston pushed {} balls {dir:?} from {front:?}", moves.len()));
+        self.events.queue(events);
+        self.track_followed(&moves);
+    }
+
+    fn age_balls(&mut self) {
+        let deleting_oldest =
+            self.rules.population_cap.is_some() && self.rules.cap_policy == CapPolicy::DeleteOldest;
+        if self.rules.lifetime.is_none() && !deleting_oldest {
+            self.ages.clear();
+            return;
+        }
+        self.balls
+            .keys()
+            .for_each(|pos| *self.ages.entry(*pos).or_default() += 1);
+        if let Some(lifetime) = self.rules.lifetime {
+            let mut events = vec![];
+            self.ages.retain(|pos, age| {
+                let alive = *age < lifetime;
+                if !alive {
+                    self.balls.remove(pos);
+                    self.active.insert(chunk_of(pos.position));
+                    if let Some(changes) = &mut self.changes {
+                        changes.balls.insert(pos.position);
+                    }
+                    events.push(SimEvent::BallDestroyed(pos.position));
+                }
+                alive
+            });
+            self.events.queue(events);
+        }
+    }
+
+    fn delete_oldest(&mut self) {
+        let Some(cap) = self.rules.population_cap else {
+            return;
+        };
+        let excess = self.balls.len().saturating_sub(cap as usize);
+        if excess == 0 {
+            return;
+        }
+        let mut by_age: Vec<(BallPosition, u32)> = self
+            .balls
+            .keys()
+            .map(|pos| (*pos, self.ages.get(pos).copied().unwrap_or(0)))
+            .collect();
+        by_age.sort_by_key(|(pos, age)| (Reverse(*age), pos.position));
+        let mut events = vec![];
+        by_age.into_iter().take(excess).for_each(|(pos, _)| {
+            self.balls.remove(&pos);
+            self.ages.remove(&pos);
+            self.active.insert(chunk_of(pos.position));
+            if let Some(changes) = self.changed() {
+                changes.balls.insert(pos.position);
+            }
+            events.push(SimEvent::BallDestroyed(pos.position));
+        });
+        self.events.queue(events);
+    }
+
+    //every ball claims a cell based on the previous update, then the claims are resolved:
+    //moves into blocks, into cells claimed more than once, or swapping with another ball
+    //fail and leave the ball where it was, which repeats until nothing else fails
+    #[allow(clippy::type_complexity)]
+    fn sync_step(&mut self) {
+        //origin, target and the ball, a staying ball claims its own cell
+        let mut claims: Vec<([i32; 2], [i32; 2], (bool, Direction))> = vec![];
+        let mut spare_copies = self.spare_copies();
+        let mut events = vec![];
+        let mut balls: Vec<_> = self.balls.iter().collect();
+        balls.sort_by_key(|(pos, _)| pos.position);
+        balls.into_iter().for_each(|(pos, ball)| {
+            let pos = pos.position;
+            let tile = self.chunks.get_tile(pos);
+            if let Some((target, dir)) = self.jump_target(pos, ball.1) {
+                claims.push((pos, target, (ball.0, dir)));
+                return;
+            }
+            let mut claim = |dir: Direction| claims.push((pos, step_pos(pos, dir), (ball.0, dir)));
+            match tile {
+                _ if tile.destroys(ball.0) => events.push(SimEvent::BallDestroyed(pos)),
+                Tile::Hold | Tile::Sticky => claims.push((pos, pos, *ball)),
+                //unlinked portals hold balls too
+                _ if tile.jumps() => claims.push((pos, pos, *ball)),
+                //duplicators split the ball both ways along their axis
+                Tile::DuplicateH | Tile::DuplicateV => {
+                    let (first, second) = if tile == Tile::DuplicateH {
+                        (Direction::Left, Direction::Right)
+                    } else {
+                        (Direction::Up, Direction::Down)
+                    };
+                    claim(first);
+                    if spare_copies > 0 {
+                        spare_copies -= 1;
+                        claim(second);
+                    }
+                }
+                _ => claim(heading(tile, *ball, self.rules.gravity)),
+            }
+        });
+        claims.iter_mut().for_each(|(origin, target, _)| {
+            if self.chunks.get_tile(*target).is_solid() {
+                *target = *origin;
+            }
+        });
+        loop {
+            let mut counts: HashMap<[i32; 2], u32> = HashMap::default();
+            claims
+                .iter()
+                .for_each(|(_, target, _)| *counts.entry(*target).or_default() += 1);
+            let leaving: HashMap<[i32; 2], [i32; 2]> = claims
+                .iter()
+                .filter(|(origin, target, _)| origin != target)
+                .map(|(origin, target, _)| (*origin, *target))
+                .collect();
+            let mut failed = false;
+            claims.iter_mut().for_each(|(origin, target, _)| {
+                let swapping = leaving.get(target) == Some(origin);
+                if origin != target && (counts[target] > 1 || swapping) {
+                    *target = *origin;
+                    failed = true;
+                }
+            });
+            if !failed {
+                break;
+            }
+        }
+        //the followed ball goes with the first half of a split
+        if let Some(followed) = self.follow {
+            let tick = self.stats.tick + 1;
+            match claims.iter().find(|(origin, _, _)| *origin == followed) {
+                Some((origin, target, _)) if origin != target => {
+                    self.follow = Some(*target);
+                    self.followed.push(Followed::Moved(tick, *target));
+                }
+                Some(_) => {}
+                None => {
+                    self.follow = None;
+                    self.followed.push(Followed::Destroyed(tick));
+                }
+            }
+        }
+        //a split only leaves a copy if its halves didn't both fail back onto the duplicator
+        let mut first_targets: HashMap<[i32; 2], [i32; 2]> = HashMap::default();
+        claims.iter().for_each(|(origin, target, _)| {
+            if origin != target {
+                self.stats.moved += 1;
+                let tile = self.chunks.get_tile(*target);
+                events.push(SimEvent::BallEntered(tile, *target));
+            }
+            match first_targets.get(origin) {
+                Some(first) if first != target => {
+                    self.stats.duplicated += 1;
+                    events.push(SimEvent::BallDuplicated(*origin));
+                }
+                Some(_) => {}
+                None => {
+                    first_targets.insert(*origin, *target);
+                }
+            }
+        });
+        //the first half of a split keeps the age, the other is a new ball
+        //every ball may have moved or turned
+        if let Some(changes) = &mut self.changes {
+            let changed = &mut changes.balls;
+            changed.extend(self.balls.keys().map(|pos| pos.position));
+            changed.extend(claims.iter().map(|(_, target, _)| *target));
+        }
+        let mut aged: HashSet<_> = HashSet::default();
+        let mut ages = HashMap::default();
+        self.balls = claims
+            .into_iter()
+            .map(|(origin, target, ball)| {
+                let target = BallPosition { position: target };
+                let age = self.ages.get(&BallPosition { position: origin });
+                if let Some(age) = age.filter(|_| aged.insert(origin)) {
+                    ages.insert(target, *age);
+                }
+                (target, ball)
+            })
+            .collect();
+        self.ages = ages;
+        self.events.queue(events);
+    }
+
+    fn sim_step(&mut self, dir: Direction) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let parallel = self.balls.len() >= self.parallel_min;
+        let (chunks, asleep, gravity) = (&self.chunks, &self.asleep, self.rules.gravity);
+        let (skipped, already_duplicated) = (&scratch.dont_move, &scratch.duplicated);
+        let steer_ball = |(pos, on): (&BallPosition, &mut (bool, Direction))| {
+            if skipped.contains(&pos.position) || asleep.contains(&chunk_of(pos.position)) {
+                return None;
+            }
+            let tile = chunks.get_tile(pos.position);
+            let copy = !already_duplicated.contains(&pos.position);
+            let before = *on;
+            let steer = steer(tile, on, dir, gravity, copy);
+            //a ball that stays as it was has nothing left to do this phase
+            (steer != Steer::Stay || *on != before).then_some((*pos, steer))
+        };
+        scratch.steered.clear();
+        if parallel {
+            scratch
+                .steered
+                .par_extend(self.balls.par_iter_mut().filter_map(steer_ball));
+        } else {
+            scratch
+                .steered
+                .extend(self.balls.iter_mut().filter_map(steer_ball));
+        }
+
+        //strips of chunks across the direction of travel, each one is resolved on its own
+        scratch.to_duplicate.clear();
+        let mut events = vec![];
+        scratch.steered.iter().for_each(|(pos, steer)| match steer {
+            Steer::Destroy => {
+                self.balls.remove(pos);
+                self.ages.remove(pos);
+                self.mark_active(pos.position);
+                self.ball_changed(pos.position);
+                events.push(SimEvent::BallDestroyed(pos.position));
+            }
+            //it only turned
+            Steer::Stay => self.ball_changed(pos.position),
+            Steer::Move | Steer::MoveAndCopy => {
+                if *steer == Steer::MoveAndCopy {
+                    scratch.to_duplicate.push(*pos);
+                }
+                let across = match dir {
+                    Direction::Up | Direction::Down => pos.position[0],
+                    Direction::Left | Direction::Right => pos.position[1],
+                };
+                scratch
+                    .lanes
+                    .entry(across.div_euclid(CHUNK_SIZE as i32))
+                    .or_default()
+                    .push(pos.position);
+            }
+        });
+
+        //with a population cap the copies go to the duplicators nearest the bottom left
+        scratch.to_duplicate.sort_by_key(|pos| pos.position);
+        scratch.to_duplicate.truncate(self.spare_copies());
+        let to_duplicate: HashSet<BallPosition> = scratch.to_duplicate.iter().copied().collect();
+        //lanes are emptied as they run, keeping their capacity for the next phase
+        let run_lane = |positions: &mut Vec<[i32; 2]>| {
+            positions.sort_by(|a, b| match dir {
+                Direction::Up => a[1].cmp(&b[1]),
+                Direction::Down => b[1].cmp(&a[1]),
+                Direction::Left => b[0].cmp(&a[0]),
+                Direction::Right => a[0].cmp(&b[0]),
+            });
+            Lane::new(
+                &self.chunks,
+                &self.balls,
+                &self.ages,
+                &to_duplicate,
+                dir,
+                self.rules.collision,
+            )
+            .run(positions)
+        };
+        let mut results: Vec<(i32, LaneResult)> = if parallel {
+            scratch
+                .lanes
+                .par_iter_mut()
+                .filter(|(_, positions)| !positions.is_empty())
+                .map(|(lane, positions)| (*lane, run_lane(positions)))
+                .collect()
+        } else {
+            scratch
+                .lanes
+                .iter_mut()
+                .filter(|(_, positions)| !positions.is_empty())
+                .map(|(lane, positions)| (*lane, run_lane(positions)))
+                .collect()
+        };
+        //the map's order changes from run to run, the events shouldn't
+        results.sort_unstable_by_key(|(lane, _)| *lane);
+        let mut moves = vec![];
+        results.into_iter().for_each(|(_, result)| {
+            result.moves.iter().for_each(|(_, pos)| {
+                self.debug_log
+                    .log(|| format!("ball moved {dir:?} into {pos:?}"))
+            });
+            self.stats.moved += result.moves.len();
+            let copies = result
+                .duplicated
+                .iter()
+                .filter(|pos| to_duplicate.contains(&BallPosition { position: **pos }));
+            self.stats.duplicated += copies.clone().count();
+            if self.events.wanted() {
+                events.extend(copies.map(|pos| SimEvent::BallDuplicated(*pos)));
+                events.extend(
+                    result
+                        .moves
+                        .iter()
+                        .map(|(_, pos)| SimEvent::BallEntered(self.chunks.get_tile(*pos), *pos)),
+                );
+                events.extend(
+                    result
+                        .destroyed
+                        .iter()
+                        .map(|pos| SimEvent::BallDestroyed(*pos)),
+                );
+            }
+            if let Some(changes) = self.changed() {
+                changes
+                    .balls
+                    .extend(result.balls.keys().map(|pos| pos.position));
+            }
+            lane::apply(result.balls, &mut self.balls);
+            lane::apply(result.ages, &mut self.ages);
+            scratch
+                .dont_move
+                .extend(result.moves.iter().map(|(_, to)| *to));
+            moves.extend(result.moves);
+            scratch.duplicated.extend(result.duplicated);
+            result
+                .active
+                .into_iter()
+                .for_each(|pos| self.mark_active(pos));
+        });
+        self.events.queue(events);
+        self.track_followed(&moves);
+        self.scratch = scratch;
+    }
+}
+
+//sets a tile without recording it anywhere
+pub fn write_tile(chunks: &mut HashMap<ChunkPosition, Chunk>, pos: [i32; 2], tile: Tile) {
+    chunks
+        .entry(chunk_of(pos))
+        .or_insert(Chunk {
+            data: from_fn(|_| u8::from(Tile::Empty)),
+        })
+        .set_tile(
+            [
+                pos[0].rem_euclid(CHUNK_SIZE as i32) as u32,
+                pos[1].rem_euclid(CHUNK_SIZE as i32) as u32,
+            ],
+            u8::from(tile),
+        );
+}
+
+pub fn chunk_of(pos: [i32; 2]) -> ChunkPosition {
+    ChunkPosition {
+        position: [
+            pos[0].div_euclid(CHUNK_SIZE as i32),
+            pos[1].div_euclid(CHUNK_SIZE as i32),
+        ],
+    }
+}
+
+//the chunk and the eight around it
+fn chunk_neighbourhood(chunk: ChunkPosition) -> impl Iterator<Item = ChunkPosition> {
+    (-1..=1).flat_map(move |x| {
+        (-1..=1).map(move |y| ChunkPosition {
+            position: [chunk.position[0] + x, chunk.position[1] + y],
+        })
+    })
+}
+
+//where a ball on a tile that only steers it goes next
+fn heading(tile: Tile, (on, dir): (bool, Direction), gravity: Option<Direction>) -> Direction {
+    match tile {
+        Tile::Up => Direction::Up,
+        Tile::Down => Direction::Down,
+        Tile::Left => Direction::Left,
+        Tile::Right => Direction::Right,
+        Tile::FilterR if on => Direction::Left,
+        Tile::FilterR => Direction::Right,
+        Tile::FilterL if on => Direction::Right,
+        Tile::FilterL => Direction::Left,
+        Tile::FilterU if on => Direction::Down,
+        Tile::FilterU => Direction::Up,
+        Tile::FilterD if on => Direction::Up,
+        Tile::FilterD => Direction::Down,
+        Tile::Empty | Tile::DestroyOn | Tile::DestroyOff | Tile::PressurePlate | Tile::Note => {
+            gravity.unwrap_or(dir)
+        }
+        _ => dir,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Steer {
+    Destroy,
+    Stay,
+    Move,
+    //moves and leaves a copy behind on the duplicator
+    MoveAndCopy,
+}
+
+//points the ball where its tile sends it and says what it does during the `dir` phase
+fn steer(
+    tile: Tile,
+    ball: &mut (bool, Direction),
+    dir: Direction,
+    gravity: Option<Direction>,
+    copy: bool,
+) -> Steer {
+    let along_axis = match tile {
+        _ if tile.destroys(ball.0) => return Steer::Destroy,
+        Tile::Hold | Tile::Sticky => return Steer::Stay,
+        //jumps happen after the phases
+        _ if tile.jumps() => return Steer::Stay,
+        Tile::DuplicateH => matches!(dir, Direction::Right | Direction::Left),
+        Tile::DuplicateV => matches!(dir, Direction::Up | Direction::Down),
+        _ => {
+            ball.1 = heading(tile, *ball, gravity);
+            return if ball.1 == dir {
+                Steer::Move
+            } else {
+                Steer::Stay
+            };
+        }
+    };
+    //duplicators only send balls out along their axis
+    if !along_axis {
+        return Steer::Stay;
+    }
+    ball.1 = dir;
+    if copy {
+        Steer::MoveAndCopy
+    } else {
+        Steer::Move
+    }
+}
+
+pub trait GetTile {
+    fn get_tile(&self, pos: [i32; 2]) -> Tile;
+}
+
+impl GetTile for HashMap<ChunkPosition, Chunk> {
+    fn get_tile(&self, pos: [i32; 2]) -> Tile {
+        self.get(&ChunkPosition {
+            position: [
+                pos[0].div_euclid(CHUNK_SIZE as i32),
+                pos[1].div_euclid(CHUNK_SIZE as i32),
+            ],
+        })
+        .and_then(|chunk| {
+            chunk
+                .get_tile([
+                    pos[0].rem_euclid(CHUNK_SIZE as i32) as u32,
+                    pos[1].rem_euclid(CHUNK_SIZE as i32) as u32,
+                ])
+                .try_into()
+                .ok()
+        })
+        .unwrap_or(Tile::Empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //walled square of random tiles that turn, copy, hold and destroy, with a ball on about
+    //half the cells
+    fn busy_field() -> World {
+        const SIZE: i32 = 64;
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut world = World::default();
+        (-1..=SIZE).for_each(|x| {
+            (-1..=SIZE).for_each(|y| {
+                let edge = x < 0 || y < 0 || x == SIZE || y == SIZE;
+                let tile = match rng.u8(0..10) {
+                    _ if edge => Tile::Block,
+                    0 => Tile::Up,
+                    1 => Tile::Down,
+                    2 => Tile::Left,
+                    3 => Tile::Right,
+                    4 => Tile::DuplicateH,
+                    5 => Tile::DuplicateV,
+                    6 => Tile::Hold,
+                    7 => Tile::Destroy,
+                    8 => Tile::FilterR,
+                    _ => Tile::Empty,
+                };
+                world.set_tile([x, y], tile);
+                if !edge && rng.bool() {
+                    world.set_ball([x, y], (rng.bool(), Direction::Right));
+                }
+            });
+        });
+        world
+    }
+
+    //the lanes go on threads whenever there are at least `parallel_min` balls
+    fn run(parallel_min: usize) -> Vec<([i32; 2], (bool, Direction))> {
+        let mut world = busy_field();
+        world.parallel_min = parallel_min;
+        (0..10).for_each(|_| {
+            world.full_step();
+        });
+        let mut balls: Vec<_> = world.balls().collect();
+        balls.sort_by_key(|(pos, _)| *pos);
+        balls
+    }
+
+    #[test]
+    fn lanes_on_threads_match_one_thread() {
+        let serial = run(usize::MAX);
+        assert_eq!(serial, run(usize::MAX));
+        assert_eq!(serial, run(0));
+        assert_eq!(serial, run(0));
+    }
+}
@@ -0,0 +1,88 @@
+/* generated by cbindgen from src/lib.rs, edit that instead */
+
+#ifndef BALL_SIM_H
+#define BALL_SIM_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * One past the highest tile value, values below it are tiles in the order of `Tile` in tiles.rs.
+ */
+#define BALL_SIM_TILE_COUNT 31
+
+#define BALL_SIM_UP 0
+
+#define BALL_SIM_RIGHT 1
+
+#define BALL_SIM_DOWN 2
+
+#define BALL_SIM_LEFT 3
+
+/**
+ * A simulated world, made by `ball_sim_world_new` and freed by `ball_sim_world_free`.
+ */
+typedef struct BallSimWorld BallSimWorld;
+
+typedef struct BallSimBall {
+  int32_t x;
+  int32_t y;
+  bool on;
+  /**
+   * One of the BALL_SIM_ direction values.
+   */
+  uint8_t direction;
+} BallSimBall;
+
+struct BallSimWorld *ball_sim_world_new(void);
+
+void ball_sim_world_free(struct BallSimWorld *world);
+
+/**
+ * Replaces the world with the contents of a .world file, returns false if they can't be read.
+ */
+bool ball_sim_world_load(struct BallSimWorld *world, const uint8_t *data, size_t len);
+
+/**
+ * Returns false for values that aren't tiles.
+ */
+bool ball_sim_set_tile(struct BallSimWorld *world, int32_t x, int32_t y, uint8_t tile);
+
+/**
+ * Cells that were never set are empty.
+ */
+uint8_t ball_sim_get_tile(const struct BallSimWorld *world, int32_t x, int32_t y);
+
+/**
+ * The tile's name as a static string, null for values that aren't tiles.
+ */
+const char *ball_sim_tile_name(uint8_t tile);
+
+/**
+ * Returns false for unknown directions.
+ */
+bool ball_sim_set_ball(struct BallSimWorld *world,
+                       int32_t x,
+                       int32_t y,
+                       bool on,
+                       uint8_t direction);
+
+void ball_sim_remove_ball(struct BallSimWorld *world, int32_t x, int32_t y);
+
+/**
+ * Runs `count` full updates, stopping early if the population cap refuses one.
+ */
+void ball_sim_step(struct BallSimWorld *world, uint32_t count);
+
+size_t ball_sim_ball_count(const struct BallSimWorld *world);
+
+/**
+ * Copies up to `capacity` balls sorted by position into `out` and returns how many balls there
+ * are in total, so calling it with a null `out` first gives the size to allocate.
+ */
+size_t ball_sim_balls(const struct BallSimWorld *world, struct BallSimBall *out, size_t capacity);
+
+#endif  /* BALL_SIM_H */
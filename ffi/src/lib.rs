@@ -0,0 +1,195 @@
+//the simulation behind a plain c api. ball_sim.h is generated from this file, after changing
+//the api run `cbindgen --config cbindgen.toml --output ball_sim.h` in this directory
+//a world may only be used from one thread at a time and every function is fine with null worlds
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::{c_char, CString},
+    slice,
+    sync::OnceLock,
+};
+
+use engine::{ball::Direction, save::WorldSave, tiles::Tile, world::World};
+
+/// One past the highest tile value, values below it are tiles in the order of `Tile` in tiles.rs.
+pub const BALL_SIM_TILE_COUNT: u8 = 31;
+//spelled out above so it ends up in the header
+const _: () = assert!(BALL_SIM_TILE_COUNT == Tile::COUNT);
+pub const BALL_SIM_UP: u8 = 0;
+pub const BALL_SIM_RIGHT: u8 = 1;
+pub const BALL_SIM_DOWN: u8 = 2;
+pub const BALL_SIM_LEFT: u8 = 3;
+
+/// A simulated world, made by `ball_sim_world_new` and freed by `ball_sim_world_free`.
+pub struct BallSimWorld {
+    sim: World,
+}
+
+#[repr(C)]
+pub struct BallSimBall {
+    pub x: i32,
+    pub y: i32,
+    pub on: bool,
+    /// One of the BALL_SIM_ direction values.
+    pub direction: u8,
+}
+
+fn direction(value: u8) -> Option<Direction> {
+    match value {
+        BALL_SIM_UP => Some(Direction::Up),
+        BALL_SIM_RIGHT => Some(Direction::Right),
+        BALL_SIM_DOWN => Some(Direction::Down),
+        BALL_SIM_LEFT => Some(Direction::Left),
+        _ => None,
+    }
+}
+
+fn direction_value(direction: Direction) -> u8 {
+    match direction {
+        Direction::Up => BALL_SIM_UP,
+        Direction::Right => BALL_SIM_RIGHT,
+        Direction::Down => BALL_SIM_DOWN,
+        Direction::Left => BALL_SIM_LEFT,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ball_sim_world_new() -> *mut BallSimWorld {
+    Box::into_raw(Box::new(BallSimWorld {
+        sim: World::default(),
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_world_free(world: *mut BallSimWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// Replaces the world with the contents of a .world file, returns false if they can't be read.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_world_load(
+    world: *mut BallSimWorld,
+    data: *const u8,
+    len: usize,
+) -> bool {
+    let Some(world) = world.as_mut() else {
+        return false;
+    };
+    if data.is_null() {
+        return false;
+    }
+    let bytes = slice::from_raw_parts(data, len).to_vec();
+    WorldSave::from_bytes(bytes)
+        .and_then(|save| world.sim.load(save))
+        .is_ok()
+}
+
+/// Returns false for values that aren't tiles.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_set_tile(
+    world: *mut BallSimWorld,
+    x: i32,
+    y: i32,
+    tile: u8,
+) -> bool {
+    match (world.as_mut(), Tile::try_from(tile)) {
+        (Some(world), Ok(tile)) => {
+            world.sim.set_tile([x, y], tile);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Cells that were never set are empty.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_get_tile(world: *const BallSimWorld, x: i32, y: i32) -> u8 {
+    let tile = world
+        .as_ref()
+        .map_or(Tile::Empty, |world| world.sim.get_tile([x, y]));
+    tile.into()
+}
+
+/// The tile's name as a static string, null for values that aren't tiles.
+#[no_mangle]
+pub extern "C" fn ball_sim_tile_name(tile: u8) -> *const c_char {
+    static NAMES: OnceLock<Vec<CString>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        (0..Tile::COUNT)
+            .filter_map(|value| Tile::try_from(value).ok())
+            .map(|tile| CString::new(tile.name()).expect("tile names have no nul"))
+            .collect()
+    });
+    names
+        .get(tile as usize)
+        .map_or(std::ptr::null(), |name| name.as_ptr())
+}
+
+/// Returns false for unknown directions.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_set_ball(
+    world: *mut BallSimWorld,
+    x: i32,
+    y: i32,
+    on: bool,
+    direction: u8,
+) -> bool {
+    match (world.as_mut(), self::direction(direction)) {
+        (Some(world), Some(direction)) => {
+            world.sim.set_ball([x, y], (on, direction));
+            true
+        }
+        _ => false,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_remove_ball(world: *mut BallSimWorld, x: i32, y: i32) {
+    if let Some(world) = world.as_mut() {
+        world.sim.remove_ball([x, y]);
+    }
+}
+
+/// Runs `count` full updates, stopping early if the population cap refuses one.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_step(world: *mut BallSimWorld, count: u32) {
+    if let Some(world) = world.as_mut() {
+        (0..count).all(|_| world.sim.full_step());
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_ball_count(world: *const BallSimWorld) -> usize {
+    world.as_ref().map_or(0, |world| world.sim.ball_count())
+}
+
+/// Copies up to `capacity` balls sorted by position into `out` and returns how many balls there
+/// are in total, so calling it with a null `out` first gives the size to allocate.
+#[no_mangle]
+pub unsafe extern "C" fn ball_sim_balls(
+    world: *const BallSimWorld,
+    out: *mut BallSimBall,
+    capacity: usize,
+) -> usize {
+    let Some(world) = world.as_ref() else {
+        return 0;
+    };
+    let mut balls: Vec<([i32; 2], (bool, Direction))> = world.sim.balls().collect();
+    balls.sort_by_key(|(pos, _)| *pos);
+    if !out.is_null() {
+        let out = slice::from_raw_parts_mut(out, capacity);
+        out.iter_mut()
+            .zip(&balls)
+            .for_each(|(slot, ([x, y], (on, direction)))| {
+                *slot = BallSimBall {
+                    x: *x,
+                    y: *y,
+                    on: *on,
+                    direction: direction_value(*direction),
+                }
+            });
+    }
+    balls.len()
+}
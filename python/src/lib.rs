@@ -0,0 +1,144 @@
+//the simulation as a python module, `pip install ./python` builds it
+use std::{fs, path::PathBuf};
+
+use engine::{ball::Direction, save::WorldSave, tiles, world};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+fn direction(name: &str) -> PyResult<Direction> {
+    match name {
+        "Up" => Ok(Direction::Up),
+        "Down" => Ok(Direction::Down),
+        "Left" => Ok(Direction::Left),
+        "Right" => Ok(Direction::Right),
+        _ => Err(PyValueError::new_err(format!(
+            "{name} isn't one of Up, Down, Left or Right"
+        ))),
+    }
+}
+
+fn direction_name(direction: Direction) -> String {
+    format!("{direction:?}")
+}
+
+//compares equal to other tiles with the same name
+#[pyclass(frozen, eq)]
+#[derive(Clone, Copy, PartialEq)]
+struct Tile(tiles::Tile);
+
+#[pymethods]
+impl Tile {
+    #[new]
+    fn new(name: &str) -> PyResult<Self> {
+        tiles::Tile::from_name(name)
+            .map(Tile)
+            .ok_or_else(|| PyValueError::new_err(format!("there is no {name} tile")))
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Tile({:?})", self.0.name())
+    }
+}
+
+//the simulation isn't thread safe, so a world stays on the thread that made it
+#[pyclass(unsendable)]
+struct World {
+    world: world::World,
+}
+
+#[pymethods]
+impl World {
+    #[new]
+    fn new() -> Self {
+        Self {
+            world: world::World::default(),
+        }
+    }
+
+    //a .world file saved by the app
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let bytes = fs::read(&path).map_err(|e| PyIOError::new_err(e.to_string()))?;
+        let mut world = Self::new();
+        WorldSave::from_bytes(bytes)
+            .and_then(|save| world.world.load(save))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(world)
+    }
+
+    fn set_tile(&mut self, x: i32, y: i32, tile: Tile) {
+        self.world.set_tile([x, y], tile.0);
+    }
+
+    fn get_tile(&self, x: i32, y: i32) -> Tile {
+        Tile(self.world.get_tile([x, y]))
+    }
+
+    #[pyo3(signature = (x, y, on, direction = "Right"))]
+    fn set_ball(&mut self, x: i32, y: i32, on: bool, direction: &str) -> PyResult<()> {
+        self.world
+            .set_ball([x, y], (on, self::direction(direction)?));
+        Ok(())
+    }
+
+    fn remove_ball(&mut self, x: i32, y: i32) {
+        self.world.remove_ball([x, y]);
+    }
+
+    //whether the ball is on and the direction it faces, None without a ball
+    fn get_ball(&self, x: i32, y: i32) -> Option<(bool, String)> {
+        self.world
+            .get_ball([x, y])
+            .map(|(on, dir)| (on, direction_name(dir)))
+    }
+
+    //(x, y, on, direction) sorted by position
+    fn balls(&self) -> Vec<(i32, i32, bool, String)> {
+        let mut balls: Vec<_> = self
+            .world
+            .balls()
+            .map(|([x, y], (on, dir))| (x, y, on, direction_name(dir)))
+            .collect();
+        balls.sort_by_key(|(x, y, _, _)| (*x, *y));
+        balls
+    }
+
+    //stops early if the population cap refuses an update
+    #[pyo3(signature = (count = 1))]
+    fn step(&mut self, count: u32) {
+        (0..count).all(|_| self.world.full_step());
+    }
+
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let stats = self.world.stats();
+        let dict = PyDict::new(py);
+        dict.set_item("tick", stats.tick)?;
+        dict.set_item("balls", stats.balls)?;
+        dict.set_item("moved", stats.moved)?;
+        dict.set_item("destroyed", stats.destroyed)?;
+        dict.set_item("duplicated", stats.duplicated)?;
+        dict.set_item("step_time", stats.step_time.as_secs_f64())?;
+        Ok(dict)
+    }
+}
+
+#[pymodule]
+fn ball_sim(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Tile>()?;
+    module.add_class::<World>()?;
+    //every tile name Tile accepts
+    let names: Vec<String> = (0..tiles::Tile::COUNT)
+        .filter_map(|value| tiles::Tile::try_from(value).ok())
+        .map(tiles::Tile::name)
+        .collect();
+    module.add("TILES", names)?;
+    Ok(())
+}
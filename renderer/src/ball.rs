@@ -1,15 +1,18 @@
 use bytemuck::cast_slice;
 use egui_wgpu_backend::wgpu::{
-    self, util::DeviceExt, BindGroupEntry, BindGroupLayoutEntry,
-    BindingType, BufferUsages, PipelineCompilationOptions, PrimitiveState, RenderPass,
-    ShaderStages, SurfaceConfiguration,
+    self, util::DeviceExt, BindGroupEntry, BindGroupLayoutEntry, BindingType, BufferUsages,
+    PipelineCompilationOptions, PrimitiveState, RenderPass, ShaderStages, SurfaceConfiguration,
 };
-use shared::egui::Direction as EguiDirection;
+use engine::ball::{BallPosition, Direction};
 
-use crate::{texture::Texture, vertex::Vertex};
+use crate::{shader, texture::Texture, vertex::Vertex};
 
 pub struct BallRenderingData {
     pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader: wgpu::ShaderModule,
+    sample_count: u32,
 
     instance_position_buffer: wgpu::Buffer,
     instance_on_buffer: wgpu::Buffer,
@@ -22,12 +25,6 @@ pub struct BallRenderingData {
     vertex_buffer: wgpu::Buffer,
 }
 
-#[repr(C, align(4))]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug, PartialEq, Eq, Hash, Default)]
-pub struct BallPosition {
-    pub position: [i32; 2],
-}
-
 pub struct BallsOn {
     data: Vec<u32>,
 }
@@ -43,31 +40,11 @@ impl From<Vec<(bool, Direction)>> for BallsOn {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl From<Direction> for u32 {
-    fn from(value: Direction) -> Self {
-        match value {
-            Direction::Right => 0,
-            Direction::Up => 1,
-            Direction::Down => 2,
-            Direction::Left => 3,
-        }
-    }
-}
-
-const MAX_BALLS: u32 = 2 << 14;
+pub(crate) const MAX_BALLS: u32 = 2 << 14;
 
 impl BallRenderingData {
     pub fn new(
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
         camera_bind_group_layout: &wgpu::BindGroupLayout,
         ball_texture: Texture,
         dir_texture: Texture,
@@ -157,13 +134,15 @@ impl BallRenderingData {
         let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("texture_bind_group"),
             layout: &texture_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&ball_texture.view),
-            },BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::TextureView(&dir_texture.view),
-            }
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&ball_texture.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&dir_texture.view),
+                },
             ],
         });
 
@@ -182,21 +161,62 @@ impl BallRenderingData {
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &ball_shader,
+            surface_config.format,
+            1,
+        );
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("chunk_vertex_buffer"),
+            contents: cast_slice::<Vertex, u8>(&[
+                [0.0, 0.0].into(),
+                [1.0, 0.0].into(),
+                [0.0, 1.0].into(),
+                [1.0, 1.0].into(),
+            ]),
+            usage: BufferUsages::VERTEX,
+        });
+
+        Self {
+            pipeline_layout,
+            format: surface_config.format,
+            shader: ball_shader,
+            sample_count: 1,
+            pipeline,
+            instance_position_buffer,
+            instance_on_buffer,
+            instance_array_size,
+            instance_bind_group,
+            texture_bind_group,
+            vertex_buffer,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("chunk_pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &ball_shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 buffers: &[Vertex::desc()],
             },
             fragment: Some(wgpu::FragmentState {
-                module: &ball_shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format,
+                    format,
                     blend: None,
                     write_mask: wgpu::ColorWrites::all(),
                 })],
@@ -212,36 +232,47 @@ impl BallRenderingData {
             },
             depth_stencil: None,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
             cache: None,
-        });
+        })
+    }
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("chunk_vertex_buffer"),
-            contents: cast_slice::<Vertex, u8>(&[
-                [0.0, 0.0].into(),
-                [1.0, 0.0].into(),
-                [0.0, 1.0].into(),
-                [1.0, 1.0].into(),
-            ]),
-            usage: BufferUsages::VERTEX,
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        let pipeline = shader::validated(device, || {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("ball_shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            let pipeline = Self::create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &shader,
+                self.format,
+                self.sample_count,
+            );
+            (shader, pipeline)
         });
-
-        Self {
-            pipeline,
-            instance_position_buffer,
-            instance_on_buffer,
-            instance_array_size,
-            instance_bind_group,
-            texture_bind_group,
-            vertex_buffer,
+        if let Some((shader, pipeline)) = pipeline {
+            self.shader = shader;
+            self.pipeline = pipeline;
         }
     }
 
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.pipeline = Self::create_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
     pub fn render(&self, render_pass: &mut RenderPass, camera_bind_group: &wgpu::BindGroup) {
         if self.instance_array_size > 0 {
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
@@ -254,7 +285,12 @@ impl BallRenderingData {
         }
     }
 
-    pub fn update_balls(&mut self, queue: &wgpu::Queue, pos: Vec<BallPosition>, data: Vec<(bool, Direction)>) {
+    pub fn update_balls(
+        &mut self,
+        queue: &wgpu::Queue,
+        pos: Vec<BallPosition>,
+        data: Vec<(bool, Direction)>,
+    ) {
         if pos.len() != data.len() {
             panic!("sizes of data is incorrect");
         }
@@ -272,7 +308,7 @@ impl BallRenderingData {
             0,
             bytemuck::cast_slice(
                 data.iter()
-                    .map(|(on, dir)| if *on { 1 } else { 0 } | u32::from(*dir)<<1)
+                    .map(|(on, dir)| if *on { 1 } else { 0 } | u32::from(*dir) << 1)
                     .collect::<Vec<u32>>()
                     .as_slice(),
             ),
@@ -7,17 +7,25 @@ use egui_wgpu_backend::wgpu::{
     RenderPipeline, ShaderStages, SurfaceConfiguration, TextureDescriptor, TextureFormat,
     TextureUsages, TextureViewDescriptor,
 };
+use engine::chunk::{Chunk, ChunkPosition, CHUNK_SIZE};
 
-use crate::{texture::Texture, vertex::Vertex};
+use crate::{shader, texture::Texture, vertex::Vertex};
 
 pub struct ChunkRenderingData {
     pipeline: RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader: wgpu::ShaderModule,
+    sample_count: u32,
 
     //group 0
     instance_array_buffer: wgpu::Buffer,
     instance_data: wgpu::Texture,
     instance_array_size: u32,
     instance_array_bind_group: wgpu::BindGroup,
+    debug_buffer: wgpu::Buffer,
+    positions: Vec<ChunkPosition>,
+    max_chunks: usize,
 
     //group 1
     atlas_bind_group: wgpu::BindGroup,
@@ -27,37 +35,14 @@ pub struct ChunkRenderingData {
     vertex_buffer: wgpu::Buffer,
 }
 
-pub const CHUNK_SIZE: usize = 32;
-const MAX_CHUNKS: usize = 256;
-
-#[repr(C, align(4))]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug, PartialEq, Eq, Hash, Default)]
-pub struct ChunkPosition {
-    pub position: [i32; 2],
-}
+//upper bound on the chunk array, the device limit may lower it further
+const MAX_CHUNKS: usize = 2048;
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Chunk {
-    pub data: [u8; CHUNK_SIZE * CHUNK_SIZE],
-}
-
-impl Default for Chunk {
-    fn default() -> Self {
-        Self {
-            data: [0; CHUNK_SIZE * CHUNK_SIZE],
-        }
-    }
-}
-
-impl Chunk {
-    pub fn set_tile(&mut self, pos: [u32; 2], tile: u8) {
-        self.data[(pos[0] + (CHUNK_SIZE as u32 - pos[1] - 1) * CHUNK_SIZE as u32) as usize] = tile;
-    }
-
-    pub fn get_tile(&self, pos: [u32; 2]) -> u8 {
-        self.data[(pos[0] + (CHUNK_SIZE as u32 - pos[1] - 1) * CHUNK_SIZE as u32) as usize]
-    }
+#[derive(Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
+struct ChunkDebug {
+    show_borders: u32,
+    _pad: [u32; 3],
 }
 
 #[repr(C)]
@@ -74,16 +59,17 @@ impl ChunkRenderingData {
         queue: &wgpu::Queue,
         surface_config: &SurfaceConfiguration,
         camera_bind_group_layout: &wgpu::BindGroupLayout,
-        atlas_texture: Texture,
+        atlas_texture: &Texture,
         atlas_info: &AtlasInfo,
     ) -> Self {
+        let max_chunks = MAX_CHUNKS.min(device.limits().max_texture_array_layers as usize);
         let instance_array: Vec<ChunkPosition> =
-            vec![ChunkPosition { position: [0; 2] }; MAX_CHUNKS];
+            vec![ChunkPosition { position: [0; 2] }; max_chunks];
         let chunks = vec![
             Chunk {
                 data: [0; CHUNK_SIZE * CHUNK_SIZE],
             };
-            MAX_CHUNKS
+            max_chunks
         ];
         let instance_data = device.create_texture_with_data(
             queue,
@@ -92,7 +78,7 @@ impl ChunkRenderingData {
                 size: wgpu::Extent3d {
                     width: CHUNK_SIZE as u32,
                     height: CHUNK_SIZE as u32,
-                    depth_or_array_layers: MAX_CHUNKS as u32,
+                    depth_or_array_layers: max_chunks as u32,
                 },
                 mip_level_count: 1,
                 sample_count: 1,
@@ -111,6 +97,11 @@ impl ChunkRenderingData {
             contents: cast_slice(&instance_array),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
+        let debug_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("chunk_debug_buffer"),
+            contents: bytes_of(&ChunkDebug::default()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
         let instance_array_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("instance_array_data_bind_group_layout"),
@@ -135,6 +126,16 @@ impl ChunkRenderingData {
                         },
                         count: None,
                     },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             });
         let instance_array_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -161,6 +162,10 @@ impl ChunkRenderingData {
                         },
                     )),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: debug_buffer.as_entire_binding(),
+                },
             ],
         });
 
@@ -224,21 +229,68 @@ impl ChunkRenderingData {
             ],
             push_constant_ranges: &[],
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &chunk_shader,
+            surface_config.format,
+            1,
+        );
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("chunk_vertex_buffer"),
+            contents: cast_slice::<Vertex, u8>(&[
+                [0.0, 0.0].into(),
+                [1.0, 0.0].into(),
+                [0.0, 1.0].into(),
+                [1.0, 1.0].into(),
+            ]),
+            usage: BufferUsages::VERTEX,
+        });
+
+        Self {
+            pipeline_layout,
+            format: surface_config.format,
+            shader: chunk_shader,
+            sample_count: 1,
+            instance_array_buffer,
+            instance_data,
+            instance_array_size,
+            instance_array_bind_group,
+            debug_buffer,
+            positions: vec![],
+            max_chunks,
+
+            atlas_bind_group,
+
+            pipeline,
+
+            vertex_buffer,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("chunk_pipeline"),
-            layout: Some(&pipeline_layout),
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &chunk_shader,
+                module: shader,
                 entry_point: Some("vs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 buffers: &[Vertex::desc()],
             },
             fragment: Some(wgpu::FragmentState {
-                module: &chunk_shader,
+                module: shader,
                 entry_point: Some("fs_main"),
                 compilation_options: PipelineCompilationOptions::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format,
+                    format,
                     blend: None,
                     write_mask: ColorWrites::all(),
                 })],
@@ -254,39 +306,47 @@ impl ChunkRenderingData {
             },
             depth_stencil: None,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
             cache: None,
-        });
+        })
+    }
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("chunk_vertex_buffer"),
-            contents: cast_slice::<Vertex, u8>(&[
-                [0.0, 0.0].into(),
-                [1.0, 0.0].into(),
-                [0.0, 1.0].into(),
-                [1.0, 1.0].into(),
-            ]),
-            usage: BufferUsages::VERTEX,
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        let pipeline = shader::validated(device, || {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("chunk_shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            let pipeline = Self::create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &shader,
+                self.format,
+                self.sample_count,
+            );
+            (shader, pipeline)
         });
-
-        Self {
-            instance_array_buffer,
-            instance_data,
-            instance_array_size,
-            instance_array_bind_group,
-
-            atlas_bind_group,
-
-            pipeline,
-
-            vertex_buffer,
+        if let Some((shader, pipeline)) = pipeline {
+            self.shader = shader;
+            self.pipeline = pipeline;
         }
     }
 
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.pipeline = Self::create_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
     pub fn render(&self, render_pass: &mut RenderPass, camera_bind_group: &BindGroup) {
         if self.instance_array_size > 0 {
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
@@ -299,6 +359,26 @@ impl ChunkRenderingData {
         }
     }
 
+    pub fn set_show_borders(&mut self, queue: &wgpu::Queue, show_borders: bool) {
+        queue.write_buffer(
+            &self.debug_buffer,
+            0,
+            bytes_of(&ChunkDebug {
+                show_borders: show_borders as u32,
+                ..Default::default()
+            }),
+        );
+    }
+
+    pub fn max_chunks(&self) -> usize {
+        self.max_chunks
+    }
+
+    //the texture array slot of each chunk is its index in here
+    pub fn positions(&self) -> &[ChunkPosition] {
+        &self.positions
+    }
+
     pub fn update_chunks(
         &mut self,
         queue: &wgpu::Queue,
@@ -308,7 +388,7 @@ impl ChunkRenderingData {
         if pos.len() != data.len() {
             panic!("sizes of data is incorrect");
         }
-        if data.len() > MAX_CHUNKS {
+        if data.len() > self.max_chunks {
             panic!("drawing too many chunks");
         }
         queue.write_buffer(
@@ -316,6 +396,7 @@ impl ChunkRenderingData {
             0,
             bytemuck::cast_slice(pos.as_slice()),
         );
+        self.positions = pos;
         let ext = wgpu::Extent3d {
             width: CHUNK_SIZE as u32,
             height: CHUNK_SIZE as u32,
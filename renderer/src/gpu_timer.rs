@@ -0,0 +1,160 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use egui_wgpu_backend::wgpu::{self, BufferUsages};
+
+//where each timestamp of a frame is written, the scene pass is split up when timestamps can go inside passes
+pub const SCENE_START: u32 = 0;
+pub const AFTER_BALLS: u32 = 1;
+pub const AFTER_CHUNKS: u32 = 2;
+pub const AFTER_OVERLAY: u32 = 3;
+pub const SCENE_END: u32 = 4;
+pub const POST_START: u32 = 5;
+pub const POST_END: u32 = 6;
+pub const EGUI_START: u32 = 7;
+pub const EGUI_END: u32 = 8;
+const TIMESTAMP_COUNT: u32 = 9;
+
+//gpu time spent on each part of a frame, read back a frame or two late
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    pub inside_passes: bool,
+    pub inside_encoders: bool,
+    //nanoseconds per timestamp tick
+    period: f32,
+    //set while the readback buffer holds a frame that hasn't been read yet
+    in_flight: bool,
+    //whether mapping it worked, once it is done
+    mapped: Arc<Mutex<Option<bool>>>,
+    timings: Vec<(&'static str, Duration)>,
+}
+
+impl GpuTimer {
+    //the features to ask the device for, out of what the adapter has
+    pub fn features(adapter: &wgpu::Adapter) -> wgpu::Features {
+        adapter.features()
+            & (wgpu::Features::TIMESTAMP_QUERY
+                | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS
+                | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+    }
+
+    //None when the device can't write timestamps at all
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        let features = device.features();
+        if !features.contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let size = TIMESTAMP_COUNT as u64 * wgpu::QUERY_SIZE as u64;
+        Some(Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("gpu_timer_queries"),
+                ty: wgpu::QueryType::Timestamp,
+                count: TIMESTAMP_COUNT,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("gpu_timer_resolve_buffer"),
+                size,
+                usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("gpu_timer_readback_buffer"),
+                size,
+                usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            inside_passes: features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES),
+            inside_encoders: features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS),
+            period: queue.get_timestamp_period(),
+            in_flight: false,
+            mapped: Arc::default(),
+            timings: vec![],
+        })
+    }
+
+    pub fn query_set(&self) -> &wgpu::QuerySet {
+        &self.query_set
+    }
+
+    //whether this frame can be timed, frames are skipped while the last one is still being read back
+    pub fn begin(&mut self, device: &wgpu::Device) -> bool {
+        if self.in_flight {
+            device.poll(wgpu::Maintain::Poll);
+            let mapped = match self.mapped.lock() {
+                Ok(mut mapped) => mapped.take(),
+                Err(_) => Some(false),
+            };
+            match mapped {
+                Some(true) => self.read(),
+                //the frame is lost but the next one can be timed
+                Some(false) => self.in_flight = false,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn read(&mut self) {
+        let ticks: Vec<u64> = {
+            let view = self.readback_buffer.slice(..).get_mapped_range();
+            bytemuck::cast_slice(&view).to_vec()
+        };
+        self.readback_buffer.unmap();
+        self.in_flight = false;
+        let span = |name, start: u32, end: u32| {
+            let ticks = ticks[end as usize].saturating_sub(ticks[start as usize]);
+            (
+                name,
+                Duration::from_nanos((ticks as f64 * self.period as f64) as u64),
+            )
+        };
+        let mut timings = if self.inside_passes {
+            vec![
+                span("balls", SCENE_START, AFTER_BALLS),
+                span("chunks", AFTER_BALLS, AFTER_CHUNKS),
+                span("overlay", AFTER_CHUNKS, AFTER_OVERLAY),
+                span("text", AFTER_OVERLAY, SCENE_END),
+            ]
+        } else {
+            vec![span("scene", SCENE_START, SCENE_END)]
+        };
+        timings.push(span("post", POST_START, POST_END));
+        if self.inside_encoders {
+            timings.push(span("egui", EGUI_START, EGUI_END));
+        }
+        self.timings = timings;
+    }
+
+    //copies this frame's timestamps out, after everything that writes them was recorded
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..TIMESTAMP_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.resolve_buffer.size(),
+        );
+    }
+
+    //after the frame was submitted
+    pub fn read_back(&mut self) {
+        let mapped = self.mapped.clone();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Ok(mut mapped) = mapped.lock() {
+                    *mapped = Some(result.is_ok());
+                }
+            });
+        self.in_flight = true;
+    }
+
+    pub fn timings(&self) -> &[(&'static str, Duration)] {
+        &self.timings
+    }
+}
@@ -1,5 +1,9 @@
+pub mod ball;
+pub mod chunk;
+mod gpu_timer;
+pub mod post;
+mod shader;
 pub mod state;
+pub mod text;
 mod texture;
-pub mod chunk;
-pub mod ball;
 mod vertex;
@@ -0,0 +1,329 @@
+use bytemuck::bytes_of;
+use egui_wgpu_backend::wgpu::{
+    self, util::DeviceExt, BindGroupEntry, BindGroupLayoutEntry, BindingType, BufferUsages,
+    PipelineCompilationOptions, PrimitiveState, RenderPass, ShaderStages, SurfaceConfiguration,
+    TextureUsages,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::shader;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostEffects {
+    pub crt: bool,
+    pub bloom: bool,
+    pub vignette: bool,
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
+struct PostUniform {
+    crt: u32,
+    bloom: u32,
+    vignette: u32,
+    _pad: u32,
+}
+
+impl From<PostEffects> for PostUniform {
+    fn from(value: PostEffects) -> Self {
+        Self {
+            crt: value.crt as u32,
+            bloom: value.bloom as u32,
+            vignette: value.vignette as u32,
+            _pad: 0,
+        }
+    }
+}
+
+pub struct PostRenderingData {
+    pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+
+    //the scene is drawn into this before the final full screen pass
+    scene_texture: wgpu::Texture,
+    scene_view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    //multisampled target that gets resolved into the scene texture
+    msaa_view: Option<wgpu::TextureView>,
+    sample_count: u32,
+
+    effects_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl PostRenderingData {
+    pub fn new(device: &wgpu::Device, surface_config: &SurfaceConfiguration) -> Self {
+        let (scene_texture, scene_view) = Self::create_scene_texture(device, surface_config);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let effects_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("post_effects_buffer"),
+            contents: bytes_of(&PostUniform::default()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post_bind_group_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &scene_view,
+            &sampler,
+            &effects_buffer,
+        );
+
+        let post_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/post.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &post_shader,
+            surface_config.format,
+        );
+
+        Self {
+            pipeline,
+            pipeline_layout,
+            format: surface_config.format,
+            scene_texture,
+            scene_view,
+            sampler,
+            msaa_view: None,
+            sample_count: 1,
+            effects_buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn create_scene_texture(
+        device: &wgpu::Device,
+        surface_config: &SurfaceConfiguration,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("scene_texture"),
+            size: wgpu::Extent3d {
+                width: surface_config.width.max(1),
+                height: surface_config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: surface_config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+
+    fn create_msaa_view(
+        device: &wgpu::Device,
+        surface_config: &SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count <= 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("msaa_scene_texture"),
+            size: wgpu::Extent3d {
+                width: surface_config.width.max(1),
+                height: surface_config.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: surface_config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        scene_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        effects_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post_bind_group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(scene_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: effects_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, surface_config: &SurfaceConfiguration) {
+        let size = self.scene_texture.size();
+        if size.width == surface_config.width && size.height == surface_config.height {
+            return;
+        }
+        (self.scene_texture, self.scene_view) = Self::create_scene_texture(device, surface_config);
+        self.msaa_view = Self::create_msaa_view(device, surface_config, self.sample_count);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.scene_view,
+            &self.sampler,
+            &self.effects_buffer,
+        );
+    }
+
+    pub fn set_sample_count(
+        &mut self,
+        device: &wgpu::Device,
+        surface_config: &SurfaceConfiguration,
+        sample_count: u32,
+    ) {
+        self.sample_count = sample_count;
+        self.msaa_view = Self::create_msaa_view(device, surface_config, sample_count);
+    }
+
+    //the view the scene should be drawn to and the target it resolves into, if any
+    pub fn scene_attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.msaa_view {
+            Some(msaa_view) => (msaa_view, Some(&self.scene_view)),
+            None => (&self.scene_view, None),
+        }
+    }
+
+    pub fn update_effects(&mut self, queue: &wgpu::Queue, effects: PostEffects) {
+        queue.write_buffer(
+            &self.effects_buffer,
+            0,
+            bytes_of(&PostUniform::from(effects)),
+        );
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post_pipeline"),
+            layout: Some(pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::all(),
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        let pipeline = shader::validated(device, || {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("post_shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            Self::create_pipeline(device, &self.pipeline_layout, &shader, self.format)
+        });
+        if let Some(pipeline) = pipeline {
+            self.pipeline = pipeline;
+        }
+    }
+
+    pub fn render(&self, render_pass: &mut RenderPass) {
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_pipeline(&self.pipeline);
+
+        render_pass.draw(0..3, 0..1);
+    }
+}
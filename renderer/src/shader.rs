@@ -0,0 +1,96 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use egui_wgpu_backend::wgpu;
+use shared::log;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderKind {
+    Chunk,
+    Ball,
+    Text,
+    Post,
+}
+
+impl ShaderKind {
+    const ALL: [ShaderKind; 4] = [Self::Chunk, Self::Ball, Self::Text, Self::Post];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            ShaderKind::Chunk => "chunk.wgsl",
+            ShaderKind::Ball => "ball.wgsl",
+            ShaderKind::Text => "text.wgsl",
+            ShaderKind::Post => "post.wgsl",
+        }
+    }
+}
+
+//polls the shader sources in the crate directory so pipelines can be rebuilt while the app is running
+pub struct ShaderWatcher {
+    watched: Vec<(ShaderKind, PathBuf, Option<SystemTime>)>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders"));
+        Self {
+            watched: ShaderKind::ALL
+                .into_iter()
+                .map(|kind| {
+                    let path = dir.join(kind.file_name());
+                    let modified = modified_time(&path);
+                    (kind, path, modified)
+                })
+                .collect(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    //returns the new source of every shader that changed since the last poll
+    pub fn poll(&mut self) -> Vec<(ShaderKind, String)> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return vec![];
+        }
+        self.last_poll = Instant::now();
+        self.watched
+            .iter_mut()
+            .filter_map(|(kind, path, last_modified)| {
+                let modified = modified_time(path);
+                if modified == *last_modified {
+                    return None;
+                }
+                *last_modified = modified;
+                match fs::read_to_string(&*path) {
+                    Ok(source) => Some((*kind, source)),
+                    Err(e) => {
+                        log::error!("couldn't read {}: {e}", path.display());
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+//builds a pipeline and checks whether wgpu rejected it, so a typo in a shader doesn't crash the app
+pub fn validated<T>(device: &wgpu::Device, create: impl FnOnce() -> T) -> Option<T> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let out = create();
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(e) => {
+            log::error!("shader reload failed: {e}");
+            None
+        }
+        None => Some(out),
+    }
+}
@@ -30,6 +30,41 @@ fn vs_main(input: VertexInput) -> VertexOutput{
   return out;
 }
 
+struct Globals{
+  time: f32,
+  highlight_enabled: u32,
+  highlight: vec2<i32>,
+  on_color: vec4<f32>,
+  off_color: vec4<f32>,
+  recolor: u32,
+  patterns: u32,
+  selection_enabled: u32,
+  selection_min: vec2<i32>,
+  selection_max: vec2<i32>,
+}
+
+// the textures draw on things red and off things blue, the palette swaps those hues while keeping the shading
+const ON_BASE: vec3<f32> = vec3<f32>(0.412, 0.032, 0.032);
+const OFF_BASE: vec3<f32> = vec3<f32>(0.105, 0.156, 0.753);
+
+fn luminance(color: vec3<f32>) -> f32 {
+  return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn recolor(color: vec4<f32>) -> vec4<f32> {
+  if globals.recolor == 0u {
+    return color;
+  }
+  let saturation = max(max(color.r, color.g), color.b) - min(min(color.r, color.g), color.b);
+  if saturation < 0.05 {
+    return color;
+  }
+  if color.r > color.b {
+    return vec4<f32>(globals.on_color.rgb * luminance(color.rgb) / luminance(ON_BASE), color.w);
+  }
+  return vec4<f32>(globals.off_color.rgb * luminance(color.rgb) / luminance(OFF_BASE), color.w);
+}
+
 struct Camera{
   pos: vec2<f32>,
   screensize: vec2<f32>,
@@ -48,6 +83,7 @@ struct BallInstance{
 @group(1) @binding(1) var arrow_tex: texture_2d<f32>;
 
 @group(2) @binding(0) var<uniform> camera: Camera;
+@group(2) @binding(1) var<uniform> globals: Globals;
 
 @fragment
 fn fs_main(
@@ -63,7 +99,16 @@ fn fs_main(
   var color = textureLoad(arrow_tex, current_pixel_arrow, 0);
   
   if color.w<0.999{
-    color = textureLoad(ball_tex, current_pixel, 0);
+    color = recolor(textureLoad(ball_tex, current_pixel, 0));
+    if on%2 != 1 && globals.patterns != 0u && distance(uv, vec2<f32>(0.5)) < 0.2 {
+      // hollow centre so off balls differ in shape as well as color
+      color = vec4<f32>(color.rgb * 0.3, color.w);
+    }
+    if on%2 == 1{
+      // a bright band sweeping diagonally across on balls
+      let band = fract((uv.x + uv.y) * 0.5 - globals.time * 0.75);
+      color = vec4<f32>(color.rgb + vec3<f32>(0.25 * smoothstep(0.9, 1.0, band)), color.w);
+    }
   }
   if color.w<0.999{
     discard;
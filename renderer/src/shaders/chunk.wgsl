@@ -1,4 +1,5 @@
 const CHUNK_SIZE: u32 = 32;
+const HOLD_TILE: u32 = 4;
 
 struct ChunkInstance {
     position: vec2<i32>,
@@ -33,6 +34,45 @@ fn vs_main(input: VertexInput) -> VertexOutput{
   return out;
 }
 
+struct ChunkDebug{
+  show_borders: u32,
+}
+
+struct Globals{
+  time: f32,
+  highlight_enabled: u32,
+  highlight: vec2<i32>,
+  on_color: vec4<f32>,
+  off_color: vec4<f32>,
+  recolor: u32,
+  patterns: u32,
+  selection_enabled: u32,
+  selection_min: vec2<i32>,
+  selection_max: vec2<i32>,
+}
+
+// the textures draw on things red and off things blue, the palette swaps those hues while keeping the shading
+const ON_BASE: vec3<f32> = vec3<f32>(0.412, 0.032, 0.032);
+const OFF_BASE: vec3<f32> = vec3<f32>(0.105, 0.156, 0.753);
+
+fn luminance(color: vec3<f32>) -> f32 {
+  return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn recolor(color: vec4<f32>) -> vec4<f32> {
+  if globals.recolor == 0u {
+    return color;
+  }
+  let saturation = max(max(color.r, color.g), color.b) - min(min(color.r, color.g), color.b);
+  if saturation < 0.05 {
+    return color;
+  }
+  if color.r > color.b {
+    return vec4<f32>(globals.on_color.rgb * luminance(color.rgb) / luminance(ON_BASE), color.w);
+  }
+  return vec4<f32>(globals.off_color.rgb * luminance(color.rgb) / luminance(OFF_BASE), color.w);
+}
+
 struct Camera{
   pos: vec2<f32>,
   screensize: vec2<f32>,
@@ -42,16 +82,24 @@ struct Camera{
 
 @group(0) @binding(0) var<storage, read> chunkInstances: array<ChunkInstance>;
 @group(0) @binding(1) var chunk_data: texture_2d_array<u32>; 
+@group(0) @binding(2) var<uniform> debug: ChunkDebug;
 
 @group(1) @binding(0) var atlasTex: texture_2d<f32>;
 
 @group(2) @binding(0) var<uniform> camera: Camera;
+@group(2) @binding(1) var<uniform> globals: Globals;
 
 @fragment
 fn fs_main(
   @location(0) uv: vec2<f32>,
   @location(1) instanceIndex: u32
 ) -> @location(0) vec4<f32> {
+  // keep the border a couple of screen pixels wide at any zoom
+  let border = fwidth(uv) * 2.0;
+  if debug.show_borders != 0u && (any(uv < border) || any(uv > 1.0 - border)) {
+    return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+  }
+
   let tileSize = 16.0f;
   let tilesPerRow = 3u;
 
@@ -69,9 +117,23 @@ fn fs_main(
   let atlas_tile_offset = vec2<u32>(tile_col, tile_row)*u32(tileSize);
   let atlas_uv:vec2<u32> = atlas_tile_offset + current_pixel%u32(tileSize);
 
-  let color = textureLoad(atlasTex, atlas_uv, 0);
+  var color = recolor(textureLoad(atlasTex, atlas_uv, 0));
   if color.w<0.999{
     discard;
   }
+
+  if tileIndex == HOLD_TILE {
+    color = vec4<f32>(color.rgb * (0.85 + 0.15 * sin(globals.time * 4.0)), color.w);
+  }
+
+  let chunk_pos = chunkInstances[instanceIndex].position;
+  let world_tile = chunk_pos * i32(CHUNK_SIZE) + vec2<i32>(i32(tileCoord.x), i32(CHUNK_SIZE - 1u - tileCoord.y));
+  if globals.highlight_enabled != 0u && all(world_tile == globals.highlight) {
+    let blink = step(0.0, sin(globals.time * 8.0));
+    color = vec4<f32>(mix(color.rgb, vec3<f32>(1.0), 0.5 * blink), color.w);
+  }
+  if globals.selection_enabled != 0u && all(world_tile >= globals.selection_min) && all(world_tile <= globals.selection_max) {
+    color = vec4<f32>(mix(color.rgb, vec3<f32>(0.3, 0.6, 1.0), 0.35), color.w);
+  }
   return color;
 }
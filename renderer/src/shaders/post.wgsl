@@ -0,0 +1,80 @@
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// a single triangle covering the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput{
+  let pos = vec2<f32>(f32((index << 1) & 2), f32(index & 2));
+
+  var out: VertexOutput;
+  out.uv = vec2<f32>(pos.x, 1.0 - pos.y);
+  out.position = vec4<f32>(pos * 2.0 - 1.0, 0.0, 1.0);
+  return out;
+}
+
+struct Effects{
+  crt: u32,
+  bloom: u32,
+  vignette: u32,
+}
+
+@group(0) @binding(0) var scene_tex: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+@group(0) @binding(2) var<uniform> effects: Effects;
+
+const BLOOM_RADIUS: i32 = 4;
+const BLOOM_THRESHOLD: f32 = 0.6;
+
+fn luminance(color: vec3<f32>) -> f32 {
+  return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn fs_main(
+  @location(0) uv: vec2<f32>,
+) -> @location(0) vec4<f32> {
+  let size = vec2<f32>(textureDimensions(scene_tex));
+  var sample_uv = uv;
+
+  if effects.crt != 0u {
+    // barrel distortion
+    let centered = sample_uv * 2.0 - 1.0;
+    let offset = centered.yx * centered.yx * 0.08;
+    sample_uv = (centered + centered * offset) * 0.5 + 0.5;
+    if any(sample_uv < vec2<f32>(0.0)) || any(sample_uv > vec2<f32>(1.0)) {
+      return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+  }
+
+  var color = textureSampleLevel(scene_tex, scene_sampler, sample_uv, 0.0).rgb;
+
+  if effects.bloom != 0u {
+    // cheap single pass glow around bright pixels, which in practice are the on balls
+    var glow = vec3<f32>(0.0);
+    var weight = 0.0;
+    for (var x = -BLOOM_RADIUS; x <= BLOOM_RADIUS; x++) {
+      for (var y = -BLOOM_RADIUS; y <= BLOOM_RADIUS; y++) {
+        let offset = vec2<f32>(f32(x), f32(y)) * 2.0 / size;
+        let neighbour = textureSampleLevel(scene_tex, scene_sampler, sample_uv + offset, 0.0).rgb;
+        let falloff = 1.0 / (1.0 + f32(x * x + y * y));
+        glow += neighbour * step(BLOOM_THRESHOLD, luminance(neighbour)) * falloff;
+        weight += falloff;
+      }
+    }
+    color += glow / weight * 1.5;
+  }
+
+  if effects.crt != 0u {
+    // scanlines
+    color *= 0.85 + 0.15 * sin(sample_uv.y * size.y * 3.14159);
+  }
+
+  if effects.vignette != 0u {
+    let d = distance(uv, vec2<f32>(0.5));
+    color *= smoothstep(0.8, 0.35, d);
+  }
+
+  return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,66 @@
+const GLYPH_SIZE: vec2<u32> = vec2<u32>(16, 32);
+const GLYPHS_PER_ROW: u32 = 16;
+const GLYPH_ASPECT: f32 = 0.5;
+
+struct VertexInput {
+  @location(0) position: vec2<f32>, // local vertex position of quad
+  @builtin(instance_index) index: u32,
+};
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) glyph: u32,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput{
+  let glyph = glyphInstances[input.index];
+
+  let world_pos = input.position * vec2<f32>(glyph.size * GLYPH_ASPECT, glyph.size) + glyph.pos;
+  let scale = min(camera.screensize.x, camera.screensize.y*camera.min_ratio)/camera.width;
+
+  let camera_relative_pos = ((world_pos-camera.pos)*scale/camera.screensize)*camera.screensize;
+  let ndc = camera_relative_pos/camera.screensize*2.0;
+
+  var out: VertexOutput;
+  out.uv = input.position;
+  out.uv.y = 1.0 - out.uv.y;
+  out.position = vec4<f32>(ndc, 0.0, 1.0);
+  out.glyph = glyph.glyph;
+  return out;
+}
+
+struct Camera{
+  pos: vec2<f32>,
+  screensize: vec2<f32>,
+  width:f32,
+  min_ratio: f32,
+}
+
+struct GlyphInstance{
+  pos: vec2<f32>,
+  size: f32,
+  glyph: u32,
+}
+
+@group(0) @binding(0) var<storage, read> glyphInstances: array<GlyphInstance>;
+
+@group(1) @binding(0) var font_tex: texture_2d<f32>;
+
+@group(2) @binding(0) var<uniform> camera: Camera;
+
+@fragment
+fn fs_main(
+  @location(0) uv: vec2<f32>,
+  @location(1) glyph: u32,
+) -> @location(0) vec4<f32> {
+  let cell = vec2<u32>(glyph % GLYPHS_PER_ROW, glyph / GLYPHS_PER_ROW) * GLYPH_SIZE;
+  let current_pixel = min(vec2<u32>(uv * vec2<f32>(GLYPH_SIZE)), GLYPH_SIZE - 1);
+  let color = textureLoad(font_tex, cell + current_pixel, 0);
+  if color.w<0.01{
+    discard;
+  }
+
+  return color;
+}
@@ -1,22 +1,34 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use bytemuck::bytes_of;
 use egui_wgpu_backend::{
     wgpu::{self, BindingType, BufferUsages},
     ScreenDescriptor,
 };
+use engine::{
+    ball::{BallPosition, Direction},
+    chunk::{Chunk, ChunkPosition, CHUNK_SIZE},
+};
 use shared::winit::window::Window;
 use shared::{
     anyhow,
     egui::{self, Context},
     egui_winit_platform::Platform,
+    log,
 };
 pub use wgpu::SurfaceError;
 use wgpu::{util::DeviceExt, BindGroupLayoutEntry, ShaderStages};
 
 use crate::{
-    ball::{BallPosition, BallRenderingData, Direction},
-    chunk::{AtlasInfo, Chunk, ChunkPosition, ChunkRenderingData},
+    ball::BallRenderingData,
+    chunk::{AtlasInfo, ChunkRenderingData},
+    gpu_timer::{self, GpuTimer},
+    post::{PostEffects, PostRenderingData},
+    shader::{ShaderKind, ShaderWatcher},
+    text::{TextLabel, TextRenderingData},
     texture::Texture,
 };
 
@@ -30,8 +42,12 @@ pub struct CameraUniform {
 }
 
 impl CameraUniform {
+    pub fn pixels_per_cell(&self) -> f32 {
+        self.screensize[0].min(self.screensize[1] * self.min_ratio) / self.width
+    }
+
     pub fn world_viewport_size(&self) -> [f32; 2] {
-        let scale = self.screensize[0].min(self.screensize[1] * self.min_ratio) / self.width;
+        let scale = self.pixels_per_cell();
         [self.screensize[0] / scale, self.screensize[1] / scale]
     }
 
@@ -44,21 +60,99 @@ impl CameraUniform {
     }
 }
 
+//shared by every world shader next to the camera
+#[repr(C)]
+#[derive(Default, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug)]
+struct Globals {
+    time: f32,
+    highlight_enabled: u32,
+    highlight: [i32; 2],
+    on_color: [f32; 4],
+    off_color: [f32; 4],
+    recolor: u32,
+    patterns: u32,
+    selection_enabled: u32,
+    _pad: u32,
+    //inclusive corners of the selected cells
+    selection_min: [i32; 2],
+    selection_max: [i32; 2],
+}
+
+//colors replacing the red/blue of on and off balls and filter halves, in srgb
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub on: [f32; 3],
+    pub off: [f32; 3],
+    //draws off balls hollow so they can be told apart without color
+    pub patterns: bool,
+}
+
+fn srgb_to_linear(color: [f32; 3]) -> [f32; 4] {
+    let [r, g, b] = color.map(|c| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    [r, g, b, 1.0]
+}
+
+#[derive(Default, Copy, Clone, Debug)]
+pub struct UploadStats {
+    pub chunk_uploads: u32,
+    pub chunks: u32,
+    pub ball_uploads: u32,
+    pub balls: u32,
+    pub text_uploads: u32,
+}
+
+enum RenderTarget {
+    Window {
+        surface: wgpu::Surface<'static>,
+        window: Arc<Window>,
+    },
+    //used for headless rendering, frames can be read back with read_frame
+    Offscreen(wgpu::Texture),
+}
+
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
 pub struct RenderState {
-    surface: wgpu::Surface<'static>,
+    target: RenderTarget,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     is_surface_configured: bool,
     camera_buffer: wgpu::Buffer,
+    globals_buffer: wgpu::Buffer,
+    highlighted_cell: Option<[i32; 2]>,
+    selection: Option<([i32; 2], [i32; 2])>,
+    animate: bool,
+    palette: Option<Palette>,
     camera_bind_group: wgpu::BindGroup,
     egui_renderer: egui_wgpu_backend::RenderPass,
     pub egui_platform: Platform,
     start_time: Instant,
-    pub window: Arc<Window>,
 
     chunk_rendering_data: ChunkRenderingData,
+    //decorative tiles drawn over the ground layer
+    overlay_rendering_data: ChunkRenderingData,
     ball_rendering_data: BallRenderingData,
+    text_rendering_data: TextRenderingData,
+    post_rendering_data: PostRenderingData,
+
+    shader_watcher: Option<ShaderWatcher>,
+
+    clear_color: [f32; 3],
+    debug_overlay: bool,
+    supported_sample_counts: Vec<u32>,
+    sample_count: u32,
+    uploads: UploadStats,
+    last_frame_uploads: UploadStats,
+    egui_wants_repaint: bool,
+    //None when the adapter has no timestamp queries
+    gpu_timer: Option<GpuTimer>,
 }
 
 impl RenderState {
@@ -80,17 +174,6 @@ impl RenderState {
             })
             .await
             .expect("lets hope this never hapens");
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
-            .await?;
 
         // surface
         let surface_caps = surface.get_capabilities(&adapter);
@@ -110,47 +193,141 @@ impl RenderState {
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
+        let scale_factor = window.scale_factor();
+
+        Self::from_adapter(adapter, config, Some((surface, window)), scale_factor).await
+    }
+
+    //renders into a texture instead of a window, for tests and thumbnails
+    pub async fn new_headless(width: u32, height: u32) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::PRIMARY,
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or_else(|| anyhow::anyhow!("no adapter available for headless rendering"))?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: OFFSCREEN_FORMAT,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let mut state = Self::from_adapter(adapter, config, None, 1.0).await?;
+        state.is_surface_configured = true;
+        Ok(state)
+    }
+
+    async fn from_adapter(
+        adapter: wgpu::Adapter,
+        config: wgpu::SurfaceConfiguration,
+        window: Option<(wgpu::Surface<'static>, Arc<Window>)>,
+        scale_factor: f64,
+    ) -> anyhow::Result<Self> {
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: GpuTimer::features(&adapter),
+                    //wide zoom outs need more chunk layers than the default allows
+                    required_limits: wgpu::Limits {
+                        max_texture_array_layers: adapter.limits().max_texture_array_layers,
+                        ..Default::default()
+                    },
+                    memory_hints: Default::default(),
+                },
+                None,
+            )
+            .await?;
+        let gpu_timer = GpuTimer::new(&device, &queue);
+        let surface_format = config.format;
+        let size = [config.width, config.height];
+        let target = match window {
+            Some((surface, window)) => RenderTarget::Window { surface, window },
+            None => RenderTarget::Offscreen(Self::create_offscreen_texture(&device, &config)),
+        };
+
+        let format_flags = adapter.get_texture_format_features(surface_format).flags;
+        let supported_sample_counts = [1, 2, 4, 8]
+            .into_iter()
+            .filter(|count| format_flags.sample_count_supported(*count))
+            .collect();
 
         //camera
         let camera_uniform = CameraUniform {
             pos: [0.0; 2],
             min_ratio: 1.25,
             width: 4.0,
-            screensize: window.inner_size().into(),
+            screensize: [size[0] as f32, size[1] as f32],
         };
         let camera_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("camera_uniform_buffer"),
             contents: bytemuck::bytes_of(&camera_uniform),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
+        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("globals_buffer"),
+            contents: bytemuck::bytes_of(&Globals::default()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("camera_bind_group_layout"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX_FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX_FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::VERTEX_FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
             });
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("camera_bind_group"),
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_uniform_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: globals_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         let platform = Platform::new(shared::egui_winit_platform::PlatformDescriptor {
-            physical_width: size.width,
-            physical_height: size.height,
-            scale_factor: window.scale_factor(),
+            physical_width: size[0],
+            physical_height: size[1],
+            scale_factor,
             ..Default::default()
         });
         let egui_renderer = egui_wgpu_backend::RenderPass::new(&device, surface_format, 1);
@@ -176,71 +353,353 @@ impl RenderState {
             "dir_texture",
         )?;
 
+        let font_texture = Texture::from_bytes(
+            &device,
+            &queue,
+            include_bytes!("./textures/font.png"),
+            "font_texture",
+        )?;
+
+        let atlas_info = AtlasInfo {
+            tiles_per_row: 3,
+            tiles_size: [16; 2],
+            ..Default::default()
+        };
         let chunk_rendering_data = ChunkRenderingData::new(
             &device,
             &queue,
             &config,
             &camera_bind_group_layout,
-            atlas_texture,
-            &AtlasInfo {
-                tiles_per_row: 3,
-                tiles_size: [16; 2],
-                ..Default::default()
-            },
+            &atlas_texture,
+            &atlas_info,
+        );
+        //the overlay draws from the same atlas through its own bind group
+        let overlay_rendering_data = ChunkRenderingData::new(
+            &device,
+            &queue,
+            &config,
+            &camera_bind_group_layout,
+            &atlas_texture,
+            &atlas_info,
         );
 
         let ball_rendering_data = BallRenderingData::new(
             &device,
-            &queue,
             &camera_bind_group_layout,
             ball_texture,
             dir_texture,
             &config,
         );
 
+        let text_rendering_data =
+            TextRenderingData::new(&device, &camera_bind_group_layout, font_texture, &config);
+
+        let post_rendering_data = PostRenderingData::new(&device, &config);
+
         Ok(Self {
-            surface,
+            target,
             device,
             queue,
             config,
             is_surface_configured: false,
-            window,
             egui_renderer,
             egui_platform: platform,
             camera_buffer: camera_uniform_buffer,
+            globals_buffer,
+            highlighted_cell: None,
+            selection: None,
+            animate: true,
+            palette: None,
             camera_bind_group,
             chunk_rendering_data,
+            overlay_rendering_data,
             ball_rendering_data,
+            text_rendering_data,
+            post_rendering_data,
+            shader_watcher: None,
+            clear_color: [0.1, 0.2, 0.3],
+            debug_overlay: false,
+            supported_sample_counts,
+            sample_count: 1,
+            uploads: UploadStats::default(),
+            last_frame_uploads: UploadStats::default(),
+            egui_wants_repaint: true,
+            gpu_timer,
             start_time: Instant::now(),
         })
     }
 
+    fn create_offscreen_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen_target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    //panics for headless states, which only the app never creates
+    pub fn window(&self) -> &Arc<Window> {
+        match &self.target {
+            RenderTarget::Window { window, .. } => window,
+            RenderTarget::Offscreen(_) => panic!("headless render states have no window"),
+        }
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        [self.config.width, self.config.height]
+    }
+
+    fn configure_target(&mut self) {
+        match &mut self.target {
+            RenderTarget::Window { surface, .. } => surface.configure(&self.device, &self.config),
+            RenderTarget::Offscreen(texture) => {
+                *texture = Self::create_offscreen_texture(&self.device, &self.config)
+            }
+        }
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.config.width = width;
             self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+            self.configure_target();
+            self.post_rendering_data.resize(&self.device, &self.config);
             self.is_surface_configured = true;
         }
     }
 
+    pub fn set_clear_color(&mut self, color: [f32; 3]) {
+        self.clear_color = color;
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let present_mode = if vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        if present_mode != self.config.present_mode {
+            self.config.present_mode = present_mode;
+            if self.is_surface_configured {
+                self.configure_target();
+            }
+        }
+    }
+
     pub fn update_camera(&mut self, camera: CameraUniform) {
         self.queue
             .write_buffer(&self.camera_buffer, 0, bytes_of(&camera));
     }
 
+    //the highlighted cell blinks in the chunk shader
+    pub fn set_highlighted_cell(&mut self, cell: Option<[i32; 2]>) {
+        self.highlighted_cell = cell;
+    }
+
+    //cells between the two inclusive corners get tinted by the chunk shader
+    pub fn set_selection(&mut self, selection: Option<([i32; 2], [i32; 2])>) {
+        self.selection = selection;
+    }
+
+    //with animations off time stays at zero so every frame looks the same
+    pub fn set_animate(&mut self, animate: bool) {
+        self.animate = animate;
+    }
+
+    //None keeps the colors from the textures
+    pub fn set_palette(&mut self, palette: Option<Palette>) {
+        self.palette = palette;
+    }
+
+    fn update_globals(&mut self) {
+        let globals = Globals {
+            time: if self.animate {
+                self.start_time.elapsed().as_secs_f32()
+            } else {
+                0.0
+            },
+            highlight_enabled: self.highlighted_cell.is_some() as u32,
+            highlight: self.highlighted_cell.unwrap_or_default(),
+            on_color: self
+                .palette
+                .map_or([0.0; 4], |palette| srgb_to_linear(palette.on)),
+            off_color: self
+                .palette
+                .map_or([0.0; 4], |palette| srgb_to_linear(palette.off)),
+            recolor: self.palette.is_some() as u32,
+            patterns: self.palette.is_some_and(|palette| palette.patterns) as u32,
+            selection_enabled: self.selection.is_some() as u32,
+            _pad: 0,
+            selection_min: self.selection.map_or([0; 2], |(min, _)| min),
+            selection_max: self.selection.map_or([0; 2], |(_, max)| max),
+        };
+        self.queue
+            .write_buffer(&self.globals_buffer, 0, bytes_of(&globals));
+    }
+
     pub fn update_chunks(&mut self, pos: Vec<ChunkPosition>, chunks: Vec<Chunk>) {
+        self.uploads.chunk_uploads += 1;
+        self.uploads.chunks += chunks.len() as u32;
         self.chunk_rendering_data
             .update_chunks(&self.queue, pos, chunks);
     }
 
+    pub fn update_overlay_chunks(&mut self, pos: Vec<ChunkPosition>, chunks: Vec<Chunk>) {
+        self.uploads.chunk_uploads += 1;
+        self.uploads.chunks += chunks.len() as u32;
+        self.overlay_rendering_data
+            .update_chunks(&self.queue, pos, chunks);
+    }
+
     pub fn update_balls(&mut self, pos: Vec<BallPosition>, balls: Vec<(bool, Direction)>) {
+        self.uploads.ball_uploads += 1;
+        self.uploads.balls += balls.len() as u32;
         self.ball_rendering_data
             .update_balls(&self.queue, pos, balls);
     }
 
+    pub fn update_text(&mut self, labels: &[TextLabel]) {
+        self.uploads.text_uploads += 1;
+        if self.debug_overlay {
+            let mut labels = labels.to_vec();
+            labels.extend(self.chunk_slot_labels());
+            self.text_rendering_data.update_text(&self.queue, &labels);
+        } else {
+            self.text_rendering_data.update_text(&self.queue, labels);
+        }
+    }
+
+    fn chunk_slot_labels(&self) -> Vec<TextLabel> {
+        self.chunk_rendering_data
+            .positions()
+            .iter()
+            .enumerate()
+            .map(|(slot, pos)| TextLabel {
+                position: [
+                    (pos.position[0] * CHUNK_SIZE as i32) as f32 + 0.5,
+                    ((pos.position[1] + 1) * CHUNK_SIZE as i32) as f32 - 1.5,
+                ],
+                size: 1.0,
+                text: format!("slot {slot} {:?}", pos.position),
+            })
+            .collect()
+    }
+
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        if enabled != self.debug_overlay {
+            self.debug_overlay = enabled;
+            self.chunk_rendering_data
+                .set_show_borders(&self.queue, enabled);
+        }
+    }
+
+    pub fn supported_sample_counts(&self) -> &[u32] {
+        &self.supported_sample_counts
+    }
+
+    //falls back to the highest supported count below the requested one
+    pub fn set_msaa_samples(&mut self, requested: u32) {
+        let sample_count = self
+            .supported_sample_counts
+            .iter()
+            .copied()
+            .filter(|count| *count <= requested)
+            .max()
+            .unwrap_or(1);
+        if sample_count == self.sample_count {
+            return;
+        }
+        self.sample_count = sample_count;
+        self.chunk_rendering_data
+            .set_sample_count(&self.device, sample_count);
+        self.overlay_rendering_data
+            .set_sample_count(&self.device, sample_count);
+        self.ball_rendering_data
+            .set_sample_count(&self.device, sample_count);
+        self.text_rendering_data
+            .set_sample_count(&self.device, sample_count);
+        self.post_rendering_data
+            .set_sample_count(&self.device, &self.config, sample_count);
+    }
+
+    //true while egui is animating or otherwise asked to be drawn again right away
+    pub fn wants_repaint(&self) -> bool {
+        self.egui_wants_repaint
+    }
+
+    //uploads made between the previous two frames
+    pub fn upload_stats(&self) -> UploadStats {
+        self.last_frame_uploads
+    }
+
+    //how many chunks and balls can be drawn in a single frame
+    pub fn max_chunks(&self) -> usize {
+        self.chunk_rendering_data.max_chunks()
+    }
+
+    pub fn max_balls(&self) -> usize {
+        crate::ball::MAX_BALLS as usize
+    }
+
+    //gpu time of each part of a recent frame, None when the adapter can't measure it
+    pub fn gpu_timings(&self) -> Option<Vec<(&'static str, Duration)>> {
+        self.gpu_timer
+            .as_ref()
+            .map(|timer| timer.timings().to_vec())
+    }
+
+    pub fn update_post_effects(&mut self, effects: PostEffects) {
+        self.post_rendering_data
+            .update_effects(&self.queue, effects);
+    }
+
+    //only available in debug builds since release builds don't ship the shader sources
+    pub fn set_shader_hot_reload(&mut self, enabled: bool) {
+        if !cfg!(debug_assertions) || enabled == self.shader_watcher.is_some() {
+            return;
+        }
+        self.shader_watcher = enabled.then(ShaderWatcher::new);
+    }
+
+    fn reload_changed_shaders(&mut self) {
+        let Some(watcher) = &mut self.shader_watcher else {
+            return;
+        };
+        for (kind, source) in watcher.poll() {
+            log::info!("reloading {kind:?} shader");
+            match kind {
+                ShaderKind::Chunk => {
+                    self.chunk_rendering_data
+                        .reload_shader(&self.device, &source);
+                    self.overlay_rendering_data
+                        .reload_shader(&self.device, &source);
+                }
+                ShaderKind::Ball => self
+                    .ball_rendering_data
+                    .reload_shader(&self.device, &source),
+                ShaderKind::Text => self
+                    .text_rendering_data
+                    .reload_shader(&self.device, &source),
+                ShaderKind::Post => self
+                    .post_rendering_data
+                    .reload_shader(&self.device, &source),
+            }
+        }
+    }
+
     pub fn render(&mut self, ui_code: impl FnOnce(&Context)) -> Result<(), wgpu::SurfaceError> {
-        self.window.request_redraw();
         self.egui_platform
             .update_time(self.start_time.elapsed().as_secs_f64());
 
@@ -248,25 +707,67 @@ impl RenderState {
             return Ok(());
         }
 
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.reload_changed_shaders();
+        self.update_globals();
+        self.last_frame_uploads = std::mem::take(&mut self.uploads);
+
+        let (output, view) = match &self.target {
+            RenderTarget::Window { surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+                (Some(output), view)
+            }
+            RenderTarget::Offscreen(texture) => (
+                None,
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            ),
+        };
+        let (window, scale_factor) = match &self.target {
+            RenderTarget::Window { window, .. } => (Some(window), window.scale_factor() as f32),
+            RenderTarget::Offscreen(_) => (None, 1.0),
+        };
 
         //egui stuff
         self.egui_platform.begin_pass();
         ui_code(&self.egui_platform.context());
 
-        let full_output = self.egui_platform.end_pass(Some(&self.window));
+        let full_output = self
+            .egui_platform
+            .end_pass(window.map(|window| window.as_ref()));
+        self.egui_wants_repaint = full_output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .is_some_and(|viewport| viewport.repaint_delay.is_zero());
         let paint_jobs = self
             .egui_platform
             .context()
-            .tessellate(full_output.shapes, self.window.scale_factor() as f32);
+            .tessellate(full_output.shapes, scale_factor);
 
         let screen_descriptor = ScreenDescriptor {
-            physical_width: self.window.inner_size().width,
-            physical_height: self.window.inner_size().height,
-            scale_factor: self.window.scale_factor() as f32,
+            physical_width: self.config.width,
+            physical_height: self.config.height,
+            scale_factor,
+        };
+
+        let timed = self
+            .gpu_timer
+            .as_mut()
+            .is_some_and(|timer| timer.begin(&self.device));
+        let timer = self.gpu_timer.as_ref().filter(|_| timed);
+        //splitting the scene pass up needs timestamps inside passes
+        let mark = |render_pass: &mut wgpu::RenderPass, index| {
+            if let Some(timer) = timer.filter(|timer| timer.inside_passes) {
+                render_pass.write_timestamp(timer.query_set(), index);
+            }
+        };
+        let pass_timestamps = |start, end| {
+            timer.map(|timer| wgpu::RenderPassTimestampWrites {
+                query_set: timer.query_set(),
+                beginning_of_pass_write_index: Some(start),
+                end_of_pass_write_index: Some(end),
+            })
         };
 
         let mut encoder = self
@@ -275,16 +776,17 @@ impl RenderState {
                 label: Some("Render Encoder"),
             });
         {
+            let (scene_view, resolve_target) = self.post_rendering_data.scene_attachment();
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
+                    view: scene_view,
+                    resolve_target,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
+                            r: self.clear_color[0] as f64,
+                            g: self.clear_color[1] as f64,
+                            b: self.clear_color[2] as f64,
                             a: 1.0,
                         }),
                         store: wgpu::StoreOp::Store,
@@ -292,13 +794,40 @@ impl RenderState {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: pass_timestamps(gpu_timer::SCENE_START, gpu_timer::SCENE_END),
             });
 
             self.ball_rendering_data
                 .render(&mut render_pass, &self.camera_bind_group);
+            mark(&mut render_pass, gpu_timer::AFTER_BALLS);
             self.chunk_rendering_data
                 .render(&mut render_pass, &self.camera_bind_group);
+            mark(&mut render_pass, gpu_timer::AFTER_CHUNKS);
+            self.overlay_rendering_data
+                .render(&mut render_pass, &self.camera_bind_group);
+            mark(&mut render_pass, gpu_timer::AFTER_OVERLAY);
+            self.text_rendering_data
+                .render(&mut render_pass, &self.camera_bind_group);
+
+            render_pass.forget_lifetime();
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: pass_timestamps(gpu_timer::POST_START, gpu_timer::POST_END),
+            });
+
+            self.post_rendering_data.render(&mut render_pass);
 
             render_pass.forget_lifetime();
         }
@@ -312,13 +841,81 @@ impl RenderState {
             paint_jobs.as_slice(),
             &screen_descriptor,
         );
+        //egui makes its own pass, so it is timed from the encoder around it
+        let egui_timer = timer.filter(|timer| timer.inside_encoders);
+        if let Some(timer) = egui_timer {
+            encoder.write_timestamp(timer.query_set(), gpu_timer::EGUI_START);
+        }
         self.egui_renderer
             .execute(&mut encoder, &view, &paint_jobs, &screen_descriptor, None)
             .expect("ui couldn't render properly");
+        if let Some(timer) = egui_timer {
+            encoder.write_timestamp(timer.query_set(), gpu_timer::EGUI_END);
+        }
+        if let Some(timer) = timer {
+            timer.resolve(&mut encoder);
+        }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if let Some(timer) = self.gpu_timer.as_mut().filter(|_| timed) {
+            timer.read_back();
+        }
+        if let Some(output) = output {
+            output.present();
+        }
 
         Ok(())
     }
+
+    //copies the last headless frame back to the cpu
+    pub fn read_frame(&self) -> anyhow::Result<image::RgbaImage> {
+        let RenderTarget::Offscreen(texture) = &self.target else {
+            anyhow::bail!("only headless render states can read back frames");
+        };
+        let [width, height] = self.size();
+        //rows have to be padded to the copy alignment
+        let padded_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame_readback_buffer"),
+            size: (padded_row * height) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).ok();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let data = slice.get_mapped_range();
+        let pixels = data
+            .chunks(padded_row as usize)
+            .flat_map(|row| &row[..(width * 4) as usize])
+            .copied()
+            .collect();
+        image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| anyhow::anyhow!("frame readback had the wrong size"))
+    }
 }
@@ -0,0 +1,281 @@
+use bytemuck::cast_slice;
+use egui_wgpu_backend::wgpu::{
+    self, util::DeviceExt, BindGroupEntry, BindGroupLayoutEntry, BindingType, BufferUsages,
+    PipelineCompilationOptions, PrimitiveState, RenderPass, ShaderStages, SurfaceConfiguration,
+};
+
+use crate::{shader, texture::Texture, vertex::Vertex};
+
+pub struct TextRenderingData {
+    pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    format: wgpu::TextureFormat,
+    shader: wgpu::ShaderModule,
+    sample_count: u32,
+
+    instance_buffer: wgpu::Buffer,
+    instance_array_size: u32,
+    instance_bind_group: wgpu::BindGroup,
+
+    font_bind_group: wgpu::BindGroup,
+
+    //quad
+    vertex_buffer: wgpu::Buffer,
+}
+
+//the font atlas is 16 glyphs wide and covers the printable ascii range
+const FIRST_GLYPH: u32 = 32;
+const GLYPH_COUNT: u32 = 96;
+const MAX_GLYPHS: u32 = 1 << 14;
+//width of a glyph relative to its height
+pub const GLYPH_ASPECT: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLabel {
+    //bottom left corner of the first glyph in world space
+    pub position: [f32; 2],
+    //height of a glyph in world units
+    pub size: f32,
+    pub text: String,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable, Debug, Default)]
+struct GlyphInstance {
+    position: [f32; 2],
+    size: f32,
+    glyph: u32,
+}
+
+fn glyph_index(c: char) -> u32 {
+    let c = c as u32;
+    if (FIRST_GLYPH..FIRST_GLYPH + GLYPH_COUNT).contains(&c) {
+        c - FIRST_GLYPH
+    } else {
+        '?' as u32 - FIRST_GLYPH
+    }
+}
+
+impl TextRenderingData {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        font_texture: Texture,
+        surface_config: &SurfaceConfiguration,
+    ) -> Self {
+        let instance_array_size = 0;
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("glyph_instance_buffer"),
+            contents: cast_slice(&vec![GlyphInstance::default(); MAX_GLYPHS as usize]),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let instance_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("glyph_instance_bind_group_layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let instance_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("glyph_instance_bind_group"),
+            layout: &instance_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: instance_buffer.as_entire_binding(),
+            }],
+        });
+
+        let font_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("font_bind_group_layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+        let font_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("font_bind_group"),
+            layout: &font_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&font_texture.view),
+            }],
+        });
+
+        let text_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("text_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/text.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("text_pipeline_layout"),
+            bind_group_layouts: &[
+                &instance_bind_group_layout,
+                &font_bind_group_layout,
+                camera_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &text_shader,
+            surface_config.format,
+            1,
+        );
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("text_vertex_buffer"),
+            contents: cast_slice::<Vertex, u8>(&[
+                [0.0, 0.0].into(),
+                [1.0, 0.0].into(),
+                [0.0, 1.0].into(),
+                [1.0, 1.0].into(),
+            ]),
+            usage: BufferUsages::VERTEX,
+        });
+
+        Self {
+            pipeline_layout,
+            format: surface_config.format,
+            shader: text_shader,
+            sample_count: 1,
+            pipeline,
+            instance_buffer,
+            instance_array_size,
+            instance_bind_group,
+            font_bind_group,
+            vertex_buffer,
+        }
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("text_pipeline"),
+            layout: Some(pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::all(),
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    pub fn reload_shader(&mut self, device: &wgpu::Device, source: &str) {
+        let pipeline = shader::validated(device, || {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("text_shader"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            let pipeline = Self::create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &shader,
+                self.format,
+                self.sample_count,
+            );
+            (shader, pipeline)
+        });
+        if let Some((shader, pipeline)) = pipeline {
+            self.shader = shader;
+            self.pipeline = pipeline;
+        }
+    }
+
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) {
+        self.sample_count = sample_count;
+        self.pipeline = Self::create_pipeline(
+            device,
+            &self.pipeline_layout,
+            &self.shader,
+            self.format,
+            sample_count,
+        );
+    }
+
+    pub fn render(&self, render_pass: &mut RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        if self.instance_array_size > 0 {
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, &self.instance_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.font_bind_group, &[]);
+            render_pass.set_bind_group(2, camera_bind_group, &[]);
+            render_pass.set_pipeline(&self.pipeline);
+
+            render_pass.draw(0..4, 0..self.instance_array_size);
+        }
+    }
+
+    pub fn update_text(&mut self, queue: &wgpu::Queue, labels: &[TextLabel]) {
+        let glyphs: Vec<GlyphInstance> = labels
+            .iter()
+            .flat_map(|label| {
+                label
+                    .text
+                    .chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c != ' ')
+                    .map(|(i, c)| GlyphInstance {
+                        position: [
+                            label.position[0] + i as f32 * label.size * GLYPH_ASPECT,
+                            label.position[1],
+                        ],
+                        size: label.size,
+                        glyph: glyph_index(c),
+                    })
+            })
+            .collect();
+        if glyphs.len() > MAX_GLYPHS as usize {
+            panic!("drawing too many glyphs");
+        }
+        self.instance_array_size = glyphs.len() as u32;
+        queue.write_buffer(&self.instance_buffer, 0, cast_slice(glyphs.as_slice()));
+    }
+}
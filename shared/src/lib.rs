@@ -1,7 +1,6 @@
 pub use anyhow;
+pub use egui;
+pub use egui_winit_platform;
 pub use env_logger;
 pub use log;
 pub use winit;
-pub use egui_winit_platform;
-pub use egui;
-